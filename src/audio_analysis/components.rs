@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use crate::{audio_analysis::*, visualizer::{analysis::AudioAnalysisFrame, components::AudioVisualizerSettings}};
+
+#[derive(Resource, Default)]
+pub struct AudioAnalysis {
+    pub audio_visualizer: Option<Entity>,
+    pub audio_visualizer_settings: AudioVisualizerSettings,
+    pub frame: u64,
+    pub beat_detector: BeatDetector
+}
+
+#[derive(Default)]
+pub struct BeatDetector {
+    pub energy_history: Vec<VecDeque<f32>>
+}
+
+impl BeatDetector {
+    pub fn detect(&mut self, band_energies: &[f32]) -> Vec<bool> {
+        self.energy_history.resize_with(band_energies.len(), VecDeque::new);
+
+        band_energies.iter().zip(self.energy_history.iter_mut()).map(|(&energy, energy_history)| {
+            let average_energy = energy_history.iter().sum::<f32>() / energy_history.len().max(1) as f32;
+            let beat = energy_history.len() == BEAT_HISTORY_LENGTH && energy > BEAT_MINIMUM_ENERGY && energy > average_energy * BEAT_THRESHOLD;
+
+            if energy_history.len() == BEAT_HISTORY_LENGTH {
+                energy_history.pop_front();
+            }
+            energy_history.push_back(energy);
+
+            beat
+        }).collect()
+    }
+}
+
+#[derive(Event, Clone)]
+pub struct AudioAnalysisEvent {
+    pub frame: u64,
+    pub time: f64,
+    pub columns: AudioAnalysisFrame,
+    pub band_energies: Vec<f32>,
+    pub beats: Vec<bool>
+}
@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+use crate::visualizer::components::AudioVisualizerUpdateTimer;
+
+pub mod components;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct AudioAnalysisPlugin;
+
+impl Plugin for AudioAnalysisPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<AudioAnalysis>()
+        .add_event::<AudioAnalysisEvent>()
+        .add_systems(PostUpdate, (
+            sync_audio_analysis_settings,
+            analyze_live_audio.run_if(|audio_visualizer_update_timer: Option<Res<AudioVisualizerUpdateTimer>>| audio_visualizer_update_timer.is_none_or(|audio_visualizer_update_timer| audio_visualizer_update_timer.timer.just_finished()))
+        ).chain().in_set(AudioAnalysisSet));
+    }
+}
+
+pub const AUDIO_BANDS: [(&str, f32, f32); 3] = [
+    ("bass", 20.0, 250.0),
+    ("mid", 250.0, 4000.0),
+    ("treble", 4000.0, 20000.0)
+];
+pub const BEAT_HISTORY_LENGTH: usize = 43;
+pub const BEAT_THRESHOLD: f32 = 1.5;
+pub const BEAT_MINIMUM_ENERGY: f32 = 0.01;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AudioAnalysisSet;
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use spectrum_analyzer::{samples_fft_to_spectrum, scaling::divide_by_N, windows::hann_window, FrequencyLimit};
+use crate::{audio_analysis::*, audio_data::components::{AudioData, AudioSamples}, visualizer::{analysis::analyze_audio, components::{AudioVisualizerAnalysis, AudioVisualizerContainer, AudioVisualizerSettings, SelectedAudioVisualizer}}};
+use super::components::*;
+
+pub fn sync_audio_analysis_settings(
+    audio_visualizer_container_query: Query<(Entity, &AudioVisualizerSettings), With<AudioVisualizerContainer>>,
+    selected_audio_visualizer: Option<Res<SelectedAudioVisualizer>>,
+    mut audio_analysis: ResMut<AudioAnalysis>
+) {
+    let selected_audio_visualizer_settings = selected_audio_visualizer
+        .and_then(|selected_audio_visualizer| selected_audio_visualizer.0)
+        .and_then(|entity| audio_visualizer_container_query.get(entity).ok())
+        .or_else(|| audio_visualizer_container_query.iter().next());
+
+    audio_analysis.audio_visualizer = selected_audio_visualizer_settings.map(|(entity, _)| entity);
+    if let Some((_, audio_visualizer_settings)) = selected_audio_visualizer_settings {
+        if audio_analysis.audio_visualizer_settings != *audio_visualizer_settings {
+            audio_analysis.audio_visualizer_settings = audio_visualizer_settings.clone();
+        }
+    }
+}
+
+pub fn analyze_live_audio(
+    audio_data: NonSend<AudioData>,
+    audio_samples: Res<AudioSamples>,
+    audio_visualizer_analysis_query: Query<&AudioVisualizerAnalysis>,
+    mut audio_analysis: ResMut<AudioAnalysis>,
+    mut audio_analysis_event_writer: EventWriter<AudioAnalysisEvent>,
+    time: Res<Time>
+) {
+    // The visualizers already analyzed this update, without one (e.g. in headless mode) the analysis runs here
+    let visualized_columns = audio_analysis.audio_visualizer
+        .and_then(|entity| audio_visualizer_analysis_query.get(entity).ok())
+        .and_then(|audio_visualizer_analysis| audio_visualizer_analysis.frame.clone());
+    let Some(columns) = visualized_columns.or_else(|| analyze_audio(&audio_samples.samples, &audio_analysis.audio_visualizer_settings, audio_analysis.audio_visualizer_settings.column_count)) else {
+        return;
+    };
+
+    let band_energies = analyze_audio_bands(&audio_samples.samples, audio_data.sample_rate);
+    let beats = audio_analysis.beat_detector.detect(&band_energies);
+
+    audio_analysis_event_writer.send(AudioAnalysisEvent {
+        frame: audio_analysis.frame,
+        time: time.elapsed_seconds_f64(),
+        columns,
+        band_energies,
+        beats
+    });
+    audio_analysis.frame += 1;
+}
+
+fn analyze_audio_bands(
+    samples: &[f32],
+    sample_rate: u32
+) -> Vec<f32> {
+    let nyquist_frequency = sample_rate as f32 / 2.0;
+    let frequencies = samples_fft_to_spectrum(
+        &hann_window(samples),
+        sample_rate,
+        FrequencyLimit::Range(AUDIO_BANDS[0].1, AUDIO_BANDS[AUDIO_BANDS.len() - 1].2.min(nyquist_frequency)),
+        Some(&divide_by_N)
+    );
+
+    let Ok(frequencies) = frequencies else {
+        return vec![0.0; AUDIO_BANDS.len()];
+    };
+
+    // Scaled so that a tone with an amplitude of 1.0 has an energy of 1.0 despite the Hann window spreading it over 3 bins
+    AUDIO_BANDS.iter().map(|(_, lower_frequency, upper_frequency)| {
+        (frequencies.data().iter()
+            .filter(|(frequency, _)| frequency.val() >= *lower_frequency && frequency.val() < *upper_frequency)
+            .map(|(_, value)| value.val() * value.val())
+            .sum::<f32>() * 32.0 / 3.0)
+            .sqrt()
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::{audio_data::SPECTRUM_DATA_LENGTH, visualizer::analysis::AudioAnalysisFrame};
+
+    fn analysis_world(samples: Vec<f32>) -> World {
+        let mut world = World::new();
+        world.insert_non_send_resource(AudioData::default());
+        world.insert_resource(AudioSamples { samples });
+        world.init_resource::<AudioAnalysis>();
+        world.init_resource::<Events<AudioAnalysisEvent>>();
+        world.init_resource::<Time>();
+        world
+    }
+
+    fn analyzed_columns(world: &mut World) -> Vec<AudioAnalysisFrame> {
+        world.run_system_once(sync_audio_analysis_settings);
+        world.run_system_once(analyze_live_audio);
+        world.resource_mut::<Events<AudioAnalysisEvent>>().drain().map(|audio_analysis_event| audio_analysis_event.columns).collect()
+    }
+
+    #[test]
+    fn visualized_frame_is_reused() {
+        let mut world = analysis_world(Vec::new());
+        let audio_analysis_frame = AudioAnalysisFrame { values: vec![0.25; 4], highlighted: vec![true; 4], average_value: 0.25 };
+        world.spawn((AudioVisualizerContainer, AudioVisualizerSettings::default(), AudioVisualizerAnalysis { average_frequency_value: 0.25, frame: Some(audio_analysis_frame) }));
+
+        let columns = analyzed_columns(&mut world);
+        assert_eq!(columns.len(), 1);
+        assert_eq!(columns[0].values, vec![0.25; 4]);
+    }
+
+    #[test]
+    fn audio_is_analyzed_without_a_visualizer() {
+        let samples = (0..SPECTRUM_DATA_LENGTH).map(|i| (i as f32 * 0.37).sin()).collect();
+        let mut world = analysis_world(samples);
+
+        let columns = analyzed_columns(&mut world);
+        assert_eq!(columns.len(), 1);
+        assert_eq!(columns[0].values.len(), AudioVisualizerSettings::default().column_count);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::audio_data::*;
+use bevy::prelude::*;
+use crate::{audio_data::*, cli::AudioSourceType};
 use std::sync::{Arc, Mutex};
 use cpal::{self, Stream};
 use ringbuffer::AllocRingBuffer;
@@ -6,7 +7,9 @@ use ringbuffer::AllocRingBuffer;
 pub struct AudioData {
     pub latest_audio_data: Arc<Mutex<AllocRingBuffer<f32>>>,
     pub stream: Option<Stream>,
-    pub latest_average_frequency_value: f32
+    pub sample_rate: u32,
+    pub device_name: Option<String>,
+    pub source: AudioSourceType
 }
 
 impl Default for AudioData {
@@ -14,7 +17,37 @@ impl Default for AudioData {
         Self {
             latest_audio_data: Arc::new(Mutex::new(AllocRingBuffer::new(SPECTRUM_DATA_LENGTH))),
             stream: None,
-            latest_average_frequency_value: 0.0
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            device_name: None,
+            source: AudioSourceType::default()
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Resource, Clone)]
+pub struct AudioCaptureSettings {
+    pub enabled: bool,
+    pub device: Option<String>,
+    pub source: AudioSourceType
+}
+
+impl Default for AudioCaptureSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            device: None,
+            source: AudioSourceType::default()
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct AudioSamples {
+    pub samples: Vec<f32>
+}
+
+#[derive(Event)]
+pub struct AudioDeviceSelectEvent {
+    pub device: Option<String>,
+    pub source: AudioSourceType
+}
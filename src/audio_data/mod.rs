@@ -1,9 +1,11 @@
 use bevy::prelude::*;
 
 pub mod components;
+pub mod wav;
 mod systems;
 
 use systems::*;
+pub use systems::audio_device_names;
 use components::*;
 
 pub struct AudioDataPlugin;
@@ -12,8 +14,14 @@ impl Plugin for AudioDataPlugin {
     fn build(&self, app: &mut App) {
         app
         .init_non_send_resource::<AudioData>()
-        .add_systems(Startup, setup_audio_data_updater);
+        .init_resource::<AudioSamples>()
+        .init_resource::<AudioCaptureSettings>()
+        .add_event::<AudioDeviceSelectEvent>()
+        .add_systems(Startup, setup_audio_data_updater.run_if(|audio_capture_settings: Res<AudioCaptureSettings>| audio_capture_settings.enabled))
+        .add_systems(PreUpdate, update_audio_samples)
+        .add_systems(Update, select_audio_device.run_if(on_event::<AudioDeviceSelectEvent>()).run_if(|audio_capture_settings: Res<AudioCaptureSettings>| audio_capture_settings.enabled));
     }
 }
 
-pub const SPECTRUM_DATA_LENGTH: usize = 8192;
\ No newline at end of file
+pub const SPECTRUM_DATA_LENGTH: usize = 8192;
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
\ No newline at end of file
@@ -1,25 +1,100 @@
 use bevy::prelude::*;
+use ringbuffer::RingBuffer;
 use audio_visualizer::dynamic::live_input::AudioDevAndCfg;
 use cpal::traits::{HostTrait, DeviceTrait, StreamTrait};
+use crate::cli::AudioSourceType;
 use super::components::*;
 
 pub fn setup_audio_data_updater(
     mut audio_data: NonSendMut<AudioData>,
+    audio_capture_settings: Res<AudioCaptureSettings>
 ) {
-    let output_device = cpal::default_host().default_output_device().unwrap();
+    if let Err(error) = start_audio_capture(&mut audio_data, audio_capture_settings.device.as_deref(), audio_capture_settings.source) {
+        error!("Failed to start audio capture: {}", error);
+    }
+}
 
-    let preffered_cfg = output_device.default_output_config().unwrap();
+pub fn update_audio_samples(
+    audio_data: NonSend<AudioData>,
+    mut audio_samples: ResMut<AudioSamples>
+) {
+    let latest_audio_data = audio_data.latest_audio_data.lock().unwrap();
+    audio_samples.samples.clear();
+    audio_samples.samples.extend(latest_audio_data.iter());
+}
+
+pub fn select_audio_device(
+    mut audio_device_select_event_reader: EventReader<AudioDeviceSelectEvent>,
+    mut audio_data: NonSendMut<AudioData>
+) {
+    for audio_device_select_event in audio_device_select_event_reader.read() {
+        match start_audio_capture(&mut audio_data, audio_device_select_event.device.as_deref(), audio_device_select_event.source) {
+            Ok(()) => info!("Capturing audio from `{}`", audio_data.device_name.as_deref().unwrap_or("unknown device")),
+            Err(error) => error!("Failed to switch audio device: {}", error)
+        }
+    }
+}
+
+pub fn audio_device_names(
+    source: AudioSourceType
+) -> Vec<String> {
+    audio_devices(&cpal::default_host(), source).iter().filter_map(|device| device.name().ok()).collect()
+}
+
+fn audio_devices(
+    host: &cpal::Host,
+    source: AudioSourceType
+) -> Vec<cpal::Device> {
+    match source {
+        AudioSourceType::Output => host.output_devices().map(|devices| devices.collect()),
+        AudioSourceType::Input => host.input_devices().map(|devices| devices.collect())
+    }.unwrap_or_default()
+}
+
+fn start_audio_capture(
+    audio_data: &mut AudioData,
+    device_name: Option<&str>,
+    source: AudioSourceType
+) -> Result<(), String> {
+    let host = cpal::default_host();
+    let devices = audio_devices(&host, source);
+
+    let selected_device = device_name.and_then(|device_name| {
+        let device = devices.iter().find(|device| device.name().is_ok_and(|name| name == device_name)).cloned();
+        if device.is_none() {
+            let device_names: Vec<String> = devices.iter().filter_map(|device| device.name().ok()).collect();
+            warn!("Audio device `{}` not found, using the default device. Available devices: {}", device_name, device_names.join(", "));
+        }
+        device
+    });
+
+    let audio_device = match source {
+        AudioSourceType::Output => selected_device.or_else(|| host.default_output_device()),
+        AudioSourceType::Input => selected_device.or_else(|| host.default_input_device())
+    }.ok_or("no audio device available")?;
+
+    let preffered_cfg = match source {
+        AudioSourceType::Output => audio_device.default_output_config(),
+        AudioSourceType::Input => audio_device.default_input_config()
+    }.map_err(|error| error.to_string())?;
+
+    // Stop the previous stream before opening a new one on the same buffer
+    audio_data.stream = None;
+    audio_data.sample_rate = preffered_cfg.sample_rate().0;
     let latest_audio_data = &audio_data.latest_audio_data;
 
     let audio_dev_and_cfg = AudioDevAndCfg::new(
-        Some(output_device.clone()),
+        Some(audio_device.clone()),
         Some(preffered_cfg.clone().into()),
     );
     let stream = audio_visualizer::dynamic::live_input::setup_audio_input_loop(
         latest_audio_data.clone(),
         audio_dev_and_cfg,
     );
+    stream.play().map_err(|error| error.to_string())?;
 
     audio_data.stream = Some(stream);
-    audio_data.stream.as_ref().unwrap().play().unwrap();
-}
\ No newline at end of file
+    audio_data.device_name = audio_device.name().ok();
+    audio_data.source = source;
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use std::{fs, path::Path};
+
+pub struct AudioFile {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32
+}
+
+impl AudioFile {
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / self.sample_rate as f64
+    }
+}
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+pub fn read_wav_file(
+    path: &Path
+) -> Result<AudioFile, String> {
+    let bytes = fs::read(path).map_err(|error| error.to_string())?;
+    decode_wav(&bytes)
+}
+
+pub fn decode_wav(
+    bytes: &[u8]
+) -> Result<AudioFile, String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("not a WAV file".to_string());
+    }
+
+    let mut format_chunk = None;
+    let mut data_chunk = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let chunk_size = u32::from_le_bytes([bytes[offset + 4], bytes[offset + 5], bytes[offset + 6], bytes[offset + 7]]) as usize;
+        let chunk = &bytes[offset + 8..(offset + 8).saturating_add(chunk_size).min(bytes.len())];
+        match &bytes[offset..offset + 4] {
+            b"fmt " => format_chunk = Some(chunk),
+            b"data" => data_chunk = Some(chunk),
+            _ => {}
+        }
+        offset = offset.saturating_add(8 + chunk_size + chunk_size % 2);
+    }
+
+    let format_chunk = format_chunk.filter(|chunk| chunk.len() >= 16).ok_or("missing fmt chunk")?;
+    let data_chunk = data_chunk.ok_or("missing data chunk")?;
+
+    let read_u16 = |index: usize| u16::from_le_bytes([format_chunk[index], format_chunk[index + 1]]);
+    let mut format = read_u16(0);
+    let channels = read_u16(2) as usize;
+    let sample_rate = u32::from_le_bytes([format_chunk[4], format_chunk[5], format_chunk[6], format_chunk[7]]);
+    let bits_per_sample = read_u16(14);
+    if format == WAVE_FORMAT_EXTENSIBLE && format_chunk.len() >= 26 {
+        format = read_u16(24);
+    }
+    if channels == 0 || sample_rate == 0 {
+        return Err("invalid channel count or sample rate".to_string());
+    }
+
+    let decode_sample: fn(&[u8]) -> f32 = match (format, bits_per_sample) {
+        (WAVE_FORMAT_PCM, 8) => |bytes| (bytes[0] as f32 - 128.0) / 128.0,
+        (WAVE_FORMAT_PCM, 16) => |bytes| i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+        (WAVE_FORMAT_PCM, 24) => |bytes| (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 / 8388608.0,
+        (WAVE_FORMAT_PCM, 32) => |bytes| i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 2147483648.0,
+        (WAVE_FORMAT_IEEE_FLOAT, 32) => |bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        (WAVE_FORMAT_IEEE_FLOAT, 64) => |bytes| f64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]) as f32,
+        _ => return Err(format!("unsupported sample format {} with {} bits per sample", format, bits_per_sample))
+    };
+
+    let bytes_per_sample = bits_per_sample as usize / 8;
+    let samples = data_chunk.chunks_exact(bytes_per_sample * channels)
+        .map(|frame| frame.chunks_exact(bytes_per_sample).map(decode_sample).sum::<f32>() / channels as f32)
+        .collect();
+
+    Ok(AudioFile { samples, sample_rate })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_chunk(format: u16, channels: u16, sample_rate: u32, bits_per_sample: u16) -> Vec<u8> {
+        let block_align = channels * bits_per_sample / 8;
+        let mut chunk = Vec::new();
+        chunk.extend(format.to_le_bytes());
+        chunk.extend(channels.to_le_bytes());
+        chunk.extend(sample_rate.to_le_bytes());
+        chunk.extend((sample_rate * block_align as u32).to_le_bytes());
+        chunk.extend(block_align.to_le_bytes());
+        chunk.extend(bits_per_sample.to_le_bytes());
+        chunk
+    }
+
+    fn extensible_format_chunk(sub_format: u16, channels: u16, sample_rate: u32, bits_per_sample: u16) -> Vec<u8> {
+        let mut chunk = format_chunk(WAVE_FORMAT_EXTENSIBLE, channels, sample_rate, bits_per_sample);
+        chunk.extend(22u16.to_le_bytes());
+        chunk.extend(bits_per_sample.to_le_bytes());
+        chunk.extend(0u32.to_le_bytes());
+        chunk.extend(sub_format.to_le_bytes());
+        chunk.extend([0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71]);
+        chunk
+    }
+
+    fn wav_bytes(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut body = b"WAVE".to_vec();
+        for (id, chunk) in chunks {
+            body.extend(*id);
+            body.extend((chunk.len() as u32).to_le_bytes());
+            body.extend(chunk);
+            if chunk.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend((body.len() as u32).to_le_bytes());
+        bytes.extend(body);
+        bytes
+    }
+
+    fn decode(format: Vec<u8>, data: Vec<u8>) -> Result<AudioFile, String> {
+        decode_wav(&wav_bytes(&[(b"fmt ", format), (b"data", data)]))
+    }
+
+    fn assert_samples(audio_file: &AudioFile, expected: &[f32]) {
+        assert_eq!(audio_file.samples.len(), expected.len());
+        for (sample, expected) in audio_file.samples.iter().zip(expected) {
+            assert!((sample - expected).abs() < 1e-4, "{} != {}", sample, expected);
+        }
+    }
+
+    #[test]
+    fn decodes_8_bit_pcm() {
+        let audio_file = decode(format_chunk(WAVE_FORMAT_PCM, 1, 8000, 8), vec![0, 128, 192, 255]).unwrap();
+        assert_eq!(audio_file.sample_rate, 8000);
+        assert_samples(&audio_file, &[-1.0, 0.0, 0.5, 127.0 / 128.0]);
+    }
+
+    #[test]
+    fn decodes_16_bit_pcm() {
+        let data = [i16::MIN, 0, 16384, i16::MAX].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let audio_file = decode(format_chunk(WAVE_FORMAT_PCM, 1, 44100, 16), data).unwrap();
+        assert_samples(&audio_file, &[-1.0, 0.0, 0.5, 1.0]);
+        assert!((audio_file.duration() - 4.0 / 44100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn decodes_24_bit_pcm() {
+        let data = [-8388608i32, 0, 4194304, -4194304].iter().flat_map(|sample| sample.to_le_bytes()[..3].to_vec()).collect();
+        let audio_file = decode(format_chunk(WAVE_FORMAT_PCM, 1, 48000, 24), data).unwrap();
+        assert_samples(&audio_file, &[-1.0, 0.0, 0.5, -0.5]);
+    }
+
+    #[test]
+    fn decodes_32_bit_pcm() {
+        let data = [i32::MIN, 0, 1 << 30].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let audio_file = decode(format_chunk(WAVE_FORMAT_PCM, 1, 48000, 32), data).unwrap();
+        assert_samples(&audio_file, &[-1.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn decodes_float() {
+        let data = [-0.25f32, 0.75].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        assert_samples(&decode(format_chunk(WAVE_FORMAT_IEEE_FLOAT, 1, 48000, 32), data).unwrap(), &[-0.25, 0.75]);
+
+        let data = [0.125f64, -1.0].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        assert_samples(&decode(format_chunk(WAVE_FORMAT_IEEE_FLOAT, 1, 48000, 64), data).unwrap(), &[0.125, -1.0]);
+    }
+
+    #[test]
+    fn decodes_wave_format_extensible() {
+        let data = [0.5f32, -0.5].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        assert_samples(&decode(extensible_format_chunk(WAVE_FORMAT_IEEE_FLOAT, 1, 48000, 32), data).unwrap(), &[0.5, -0.5]);
+
+        let data = [16384i16, -16384].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        assert_samples(&decode(extensible_format_chunk(WAVE_FORMAT_PCM, 1, 48000, 16), data).unwrap(), &[0.5, -0.5]);
+    }
+
+    #[test]
+    fn mixes_channels_down_to_mono() {
+        let data = [16384i16, -16384, 32767, 32767].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        assert_samples(&decode(format_chunk(WAVE_FORMAT_PCM, 2, 44100, 16), data).unwrap(), &[0.0, 32767.0 / 32768.0]);
+    }
+
+    #[test]
+    fn skips_unknown_and_odd_sized_chunks() {
+        let bytes = wav_bytes(&[
+            (b"LIST", vec![1, 2, 3]),
+            (b"fmt ", format_chunk(WAVE_FORMAT_PCM, 1, 8000, 8)),
+            (b"junk", vec![0; 5]),
+            (b"data", vec![128, 255])
+        ]);
+        assert_samples(&decode_wav(&bytes).unwrap(), &[0.0, 127.0 / 128.0]);
+    }
+
+    #[test]
+    fn truncated_data_keeps_complete_frames() {
+        let mut bytes = wav_bytes(&[(b"fmt ", format_chunk(WAVE_FORMAT_PCM, 1, 8000, 16)), (b"data", [0i16, 16384, 32767].iter().flat_map(|sample| sample.to_le_bytes()).collect())]);
+        bytes.truncate(bytes.len() - 1);
+        assert_samples(&decode_wav(&bytes).unwrap(), &[0.0, 0.5]);
+    }
+
+    #[test]
+    fn chunk_sizes_past_the_end_are_clamped() {
+        let mut bytes = wav_bytes(&[(b"fmt ", format_chunk(WAVE_FORMAT_PCM, 1, 8000, 8)), (b"data", vec![128, 192])]);
+        let data_size_offset = bytes.len() - 6;
+        bytes[data_size_offset..data_size_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_samples(&decode_wav(&bytes).unwrap(), &[0.0, 0.5]);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert!(decode_wav(b"RIFF").is_err());
+        assert!(decode_wav(b"RIFF\0\0\0\0AVI LIST").is_err());
+        assert!(decode_wav(&wav_bytes(&[(b"data", vec![0; 4])])).is_err());
+        assert!(decode_wav(&wav_bytes(&[(b"fmt ", format_chunk(WAVE_FORMAT_PCM, 1, 8000, 8))])).is_err());
+        assert!(decode(format_chunk(WAVE_FORMAT_PCM, 1, 8000, 8)[..12].to_vec(), vec![0; 4]).is_err());
+        assert!(decode(format_chunk(WAVE_FORMAT_PCM, 0, 8000, 8), vec![0; 4]).is_err());
+        assert!(decode(format_chunk(WAVE_FORMAT_PCM, 1, 0, 8), vec![0; 4]).is_err());
+        assert!(decode(format_chunk(WAVE_FORMAT_PCM, 1, 8000, 12), vec![0; 4]).is_err());
+        assert!(decode(format_chunk(2, 1, 8000, 16), vec![0; 4]).is_err());
+        assert!(decode(format_chunk(WAVE_FORMAT_EXTENSIBLE, 1, 8000, 16), vec![0; 4]).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::offline_render::DEFAULT_ENCODER_COMMAND;
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about = "Customizable audio visualizer written in Rust using Bevy")]
+pub struct CommandLineArgs {
+    /// Preset file or name of a saved preset to load into the visualizer on startup
+    #[arg(long)]
+    pub preset: Option<String>,
+    /// Name of the audio device to capture (uses the default device when not found)
+    #[arg(long)]
+    pub device: Option<String>,
+    /// Whether to capture an output (what is playing) or an input (microphone) device
+    #[arg(long, value_enum, default_value_t = AudioSourceType::Output)]
+    pub source: AudioSourceType,
+    /// Window width in pixels
+    #[arg(long)]
+    pub width: Option<f32>,
+    /// Window height in pixels
+    #[arg(long)]
+    pub height: Option<f32>,
+    /// Horizontal window position in pixels
+    #[arg(long)]
+    pub x: Option<i32>,
+    /// Vertical window position in pixels
+    #[arg(long)]
+    pub y: Option<i32>,
+    /// Start in borderless fullscreen
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Start with the settings window hidden
+    #[arg(long)]
+    pub hide_ui: bool,
+    /// Limit the frame rate of the application
+    #[arg(long)]
+    pub fps_cap: Option<f64>,
+    /// Override the VSync setting from the settings file
+    #[arg(long)]
+    pub vsync: Option<bool>,
+    /// Render a WAV file offline to a numbered PNG image sequence instead of visualizing live audio
+    #[arg(long, value_name = "AUDIO_FILE")]
+    pub render: Option<PathBuf>,
+    /// Directory the rendered frames are written to
+    #[arg(long, value_name = "DIRECTORY", default_value = "render")]
+    pub render_output: PathBuf,
+    /// Frame rate of the offline render
+    #[arg(long, value_name = "FPS", default_value_t = 60.0)]
+    pub render_fps: f64,
+    /// Only save the frame at this time offset in seconds of the offline render (can be repeated), e.g. for preset thumbnails
+    #[arg(long, value_name = "SECONDS", requires = "render")]
+    pub snapshot: Vec<f64>,
+    /// Encode the offline render into a video file with the audio through an external encoder instead of writing PNG images
+    #[arg(long, value_name = "VIDEO_FILE", requires = "render", conflicts_with = "snapshot")]
+    pub render_video: Option<PathBuf>,
+    /// Encoder command that reads raw RGBA frames from stdin, {width}, {height}, {fps}, {audio} and {output} are replaced
+    #[arg(long, value_name = "COMMAND", default_value = DEFAULT_ENCODER_COMMAND)]
+    pub encoder_command: String,
+    /// Run without a window and write the analyzed column values of every update instead of visualizing them
+    #[arg(long, conflicts_with = "render")]
+    pub headless: bool,
+    /// Format of the headless analysis output
+    #[arg(long, value_enum, default_value_t = AnalysisFormat::Csv)]
+    pub analysis_format: AnalysisFormat,
+    /// File the headless analysis output is written to (writes to stdout when omitted)
+    #[arg(long, value_name = "FILE", requires = "headless")]
+    pub analysis_output: Option<PathBuf>,
+    /// Start a WebSocket server on this address that broadcasts every analysis frame, e.g. 127.0.0.1:9001
+    #[arg(long, value_name = "ADDRESS")]
+    pub websocket: Option<String>,
+    /// Message format of the WebSocket server
+    #[arg(long, value_enum, default_value_t = WebSocketFormat::Json)]
+    pub websocket_format: WebSocketFormat,
+    /// Start an HTTP server on this address that exposes the settings, presets, devices and status as JSON, e.g. 127.0.0.1:8080
+    #[arg(long, value_name = "ADDRESS")]
+    pub http_api: Option<String>
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioSourceType {
+    #[default]
+    Output,
+    Input
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnalysisFormat {
+    #[default]
+    Csv,
+    JsonLines,
+    Binary
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WebSocketFormat {
+    #[default]
+    Json,
+    Binary
+}
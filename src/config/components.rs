@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use crate::{config::*, keybindings::components::KeyBindings, midi::components::MidiSettings, osc::components::{OscInputSettings, OscOutputSettings}, presets::components::PresetTransitionSettings, screenshot::components::ScreenshotSettings, visualizer::components::AudioVisualizerSettings, AdvancedSettings};
+use notify::RecommendedWatcher;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::{Arc, Mutex}};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioVisualizerConfig {
+    pub visualizers: Vec<AudioVisualizerSettings>,
+    pub advanced_settings: AdvancedSettings,
+    pub preset_transition: PresetTransitionSettings,
+    pub key_bindings: KeyBindings,
+    pub screenshot: ScreenshotSettings,
+    pub osc_output: OscOutputSettings,
+    pub osc_input: OscInputSettings,
+    pub midi: MidiSettings,
+    pub background_color: Color
+}
+
+impl Default for AudioVisualizerConfig {
+    fn default() -> Self {
+        Self {
+            visualizers: Vec::new(),
+            advanced_settings: AdvancedSettings::default(),
+            preset_transition: PresetTransitionSettings::default(),
+            key_bindings: KeyBindings::default(),
+            screenshot: ScreenshotSettings::default(),
+            osc_output: OscOutputSettings::default(),
+            osc_input: OscInputSettings::default(),
+            midi: MidiSettings::default(),
+            background_color: ClearColor::default().0
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ConfigFile {
+    pub path: Option<PathBuf>,
+    pub last_saved_contents: String,
+    pub save_timer: Timer,
+    pub reload_error: Option<String>
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            path: dirs::config_dir().map(|config_dir| config_dir.join(CONFIG_DIRECTORY_NAME).join(CONFIG_FILE_NAME)),
+            last_saved_contents: String::new(),
+            save_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            reload_error: None
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ConfigWatcher {
+    pub _watcher: RecommendedWatcher,
+    pub changed_paths: Arc<Mutex<Vec<PathBuf>>>
+}
+
+#[derive(Event)]
+pub struct ConfigFileChangedEvent(pub PathBuf);
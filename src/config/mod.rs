@@ -0,0 +1,33 @@
+use bevy::{app::AppExit, prelude::*};
+use crate::{keybindings::components::KeyBindings, midi::components::MidiSettings, osc::components::{OscInputSettings, OscOutputSettings}, screenshot::components::ScreenshotSettings};
+
+pub mod components;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub use systems::{parse_config, parse_ron};
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<ConfigFile>()
+        .init_resource::<KeyBindings>()
+        .init_resource::<ScreenshotSettings>()
+        .init_resource::<OscOutputSettings>()
+        .init_resource::<OscInputSettings>()
+        .init_resource::<MidiSettings>()
+        .add_event::<ConfigFileChangedEvent>()
+        .add_systems(PreStartup, load_config)
+        .add_systems(Startup, setup_config_watcher)
+        .add_systems(Update, (watch_config_files.run_if(resource_exists::<ConfigWatcher>), hot_reload_config.run_if(on_event::<ConfigFileChangedEvent>())).chain())
+        .add_systems(Update, (tick_config_save_timer, save_config.run_if(|config_file: Res<ConfigFile>| config_file.save_timer.just_finished())).chain())
+        .add_systems(Last, save_config.run_if(on_event::<AppExit>()));
+    }
+}
+
+pub const CONFIG_DIRECTORY_NAME: &str = "customizable-bevy-audio-visualizer";
+pub const CONFIG_FILE_NAME: &str = "settings.ron";
@@ -0,0 +1,269 @@
+use bevy::prelude::*;
+use notify::{RecursiveMode, Watcher};
+use ron::ser::PrettyConfig;
+use serde::de::DeserializeOwned;
+use std::{fs, io::ErrorKind, sync::{Arc, Mutex}};
+use crate::{keybindings::components::KeyBindings, midi::components::MidiSettings, osc::components::{OscInputSettings, OscOutputSettings}, presets::components::PresetTransitionSettings, screenshot::components::ScreenshotSettings, visualizer::components::{AudioVisualizerContainer, AudioVisualizerFade, AudioVisualizerRestoreEvent, AudioVisualizerSettings, AudioVisualizerSpawnEvent, AudioVisualizerTransition}, AdvancedSettings};
+use super::components::*;
+
+#[allow(clippy::too_many_arguments)]
+pub fn load_config(
+    mut config_file: ResMut<ConfigFile>,
+    mut audio_visualizer_spawn_event_writer: EventWriter<AudioVisualizerSpawnEvent>,
+    mut advanced_settings: ResMut<AdvancedSettings>,
+    mut preset_transition_settings: ResMut<PresetTransitionSettings>,
+    mut key_bindings: ResMut<KeyBindings>,
+    mut screenshot_settings: ResMut<ScreenshotSettings>,
+    mut osc_output_settings: ResMut<OscOutputSettings>,
+    mut osc_input_settings: ResMut<OscInputSettings>,
+    mut midi_settings: ResMut<MidiSettings>,
+    mut clear_color: ResMut<ClearColor>
+) {
+    let Some(path) = config_file.path.clone() else {
+        warn!("No config directory found, settings will not be saved");
+        return;
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            info!("No config file at {}, using default settings", path.display());
+            return;
+        },
+        Err(error) => {
+            error!("Failed to read config file {}: {}", path.display(), error);
+            config_file.path = None;
+            return;
+        }
+    };
+
+    match parse_config(&contents) {
+        Ok(config) => {
+            config_file.last_saved_contents = contents;
+
+            *advanced_settings = config.advanced_settings;
+            *preset_transition_settings = config.preset_transition;
+            *key_bindings = config.key_bindings;
+            *screenshot_settings = config.screenshot;
+            *osc_output_settings = config.osc_output;
+            *osc_input_settings = config.osc_input;
+            *midi_settings = config.midi;
+            clear_color.0 = config.background_color;
+            for audio_visualizer_settings in config.visualizers {
+                audio_visualizer_spawn_event_writer.send(AudioVisualizerSpawnEvent(audio_visualizer_settings));
+            }
+        },
+        Err(error) => {
+            let mut backup_path = path.clone().into_os_string();
+            backup_path.push(".invalid");
+            error!("Invalid config file {}: {}, using default settings and moving it to {}", path.display(), error, backup_path.to_string_lossy());
+            if let Err(error) = fs::rename(&path, &backup_path) {
+                error!("Failed to move invalid config file: {}, settings will not be saved", error);
+                config_file.path = None;
+            }
+        }
+    }
+}
+
+pub fn parse_ron<T: DeserializeOwned>(
+    contents: &str
+) -> Result<T, ron::error::SpannedError> {
+    let mut deserializer = ron::Deserializer::from_str(contents)?;
+    let value = serde_ignored::deserialize(&mut deserializer, |path| {
+        warn!("Ignoring unknown field `{}`", path);
+    }).map_err(|error| deserializer.span_error(error))?;
+    deserializer.end().map_err(|error| deserializer.span_error(error))?;
+
+    Ok(value)
+}
+
+pub fn parse_config(
+    contents: &str
+) -> Result<AudioVisualizerConfig, ron::error::SpannedError> {
+    let mut config: AudioVisualizerConfig = parse_ron(contents)?;
+
+    for (i, audio_visualizer_settings) in config.visualizers.iter_mut().enumerate() {
+        for correction in audio_visualizer_settings.sanitize() {
+            warn!("Invalid config value in visualizers[{}]: {}", i, correction);
+        }
+    }
+
+    Ok(config)
+}
+
+pub fn setup_config_watcher(
+    mut commands: Commands,
+    config_file: Res<ConfigFile>
+) {
+    let Some(config_directory) = config_file.path.as_ref().and_then(|path| path.parent()) else {
+        return;
+    };
+
+    if let Err(error) = fs::create_dir_all(config_directory) {
+        warn!("Failed to create config directory {}: {}, changes to the settings file will not be reloaded", config_directory.display(), error);
+        return;
+    }
+
+    let changed_paths = Arc::new(Mutex::new(Vec::new()));
+    let watcher_changed_paths = changed_paths.clone();
+    let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        match result {
+            Ok(event) if !event.kind.is_access() => watcher_changed_paths.lock().unwrap().extend(event.paths),
+            Ok(_) => {},
+            Err(error) => warn!("Config watcher error: {}", error)
+        }
+    });
+
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            warn!("Failed to create config watcher: {}, changes to the settings file will not be reloaded", error);
+            return;
+        }
+    };
+    if let Err(error) = watcher.watch(config_directory, RecursiveMode::Recursive) {
+        warn!("Failed to watch config directory {}: {}, changes to the settings file will not be reloaded", config_directory.display(), error);
+        return;
+    }
+
+    commands.insert_resource(ConfigWatcher { _watcher: watcher, changed_paths });
+}
+
+pub fn watch_config_files(
+    config_watcher: Res<ConfigWatcher>,
+    mut config_file_changed_event_writer: EventWriter<ConfigFileChangedEvent>
+) {
+    let mut changed_paths = std::mem::take(&mut *config_watcher.changed_paths.lock().unwrap());
+    changed_paths.sort();
+    changed_paths.dedup();
+
+    for path in changed_paths {
+        config_file_changed_event_writer.send(ConfigFileChangedEvent(path));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn hot_reload_config(
+    mut config_file_changed_event_reader: EventReader<ConfigFileChangedEvent>,
+    mut config_file: ResMut<ConfigFile>,
+    mut audio_visualizer_restore_event_writer: EventWriter<AudioVisualizerRestoreEvent>,
+    mut advanced_settings: ResMut<AdvancedSettings>,
+    mut preset_transition_settings: ResMut<PresetTransitionSettings>,
+    mut key_bindings: ResMut<KeyBindings>,
+    mut screenshot_settings: ResMut<ScreenshotSettings>,
+    mut osc_output_settings: ResMut<OscOutputSettings>,
+    mut osc_input_settings: ResMut<OscInputSettings>,
+    mut midi_settings: ResMut<MidiSettings>,
+    mut clear_color: ResMut<ClearColor>
+) {
+    let Some(path) = config_file.path.clone() else {
+        return;
+    };
+    if !config_file_changed_event_reader.read().any(|ConfigFileChangedEvent(changed_path)| *changed_path == path) {
+        return;
+    }
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return,
+        Err(error) => {
+            error!("Failed to read config file {}: {}", path.display(), error);
+            config_file.reload_error = Some(format!("Failed to read config file {}: {}", path.display(), error));
+            return;
+        }
+    };
+    if contents == config_file.last_saved_contents {
+        return;
+    }
+
+    let config = match parse_config(&contents) {
+        Ok(config) => config,
+        Err(error) => {
+            error!("Invalid config file {}: {}, keeping the current settings", path.display(), error);
+            config_file.reload_error = Some(format!("Invalid config file {}: {}", path.display(), error));
+            return;
+        }
+    };
+
+    config_file.reload_error = None;
+    config_file.last_saved_contents = ron::ser::to_string_pretty(&config, PrettyConfig::default()).unwrap_or(contents);
+    info!("Reloaded config file {}", path.display());
+
+    *advanced_settings = config.advanced_settings;
+    *preset_transition_settings = config.preset_transition;
+    *key_bindings = config.key_bindings;
+    *screenshot_settings = config.screenshot;
+    *osc_output_settings = config.osc_output;
+    *osc_input_settings = config.osc_input;
+    *midi_settings = config.midi;
+    clear_color.0 = config.background_color;
+
+    audio_visualizer_restore_event_writer.send(AudioVisualizerRestoreEvent(config.visualizers));
+}
+
+pub fn tick_config_save_timer(
+    time: Res<Time>,
+    mut config_file: ResMut<ConfigFile>
+) {
+    config_file.save_timer.tick(time.delta());
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn save_config(
+    mut config_file: ResMut<ConfigFile>,
+    audio_visualizer_container_query: Query<(&AudioVisualizerSettings, Option<&AudioVisualizerTransition>, Option<&AudioVisualizerFade>), With<AudioVisualizerContainer>>,
+    advanced_settings: Res<AdvancedSettings>,
+    preset_transition_settings: Res<PresetTransitionSettings>,
+    key_bindings: Res<KeyBindings>,
+    screenshot_settings: Res<ScreenshotSettings>,
+    osc_output_settings: Res<OscOutputSettings>,
+    osc_input_settings: Res<OscInputSettings>,
+    midi_settings: Res<MidiSettings>,
+    clear_color: Res<ClearColor>
+) {
+    let Some(path) = config_file.path.clone() else {
+        return;
+    };
+    if config_file.reload_error.is_some() {
+        return;
+    }
+
+    let config = AudioVisualizerConfig {
+        visualizers: audio_visualizer_container_query.iter()
+            .filter(|(_, _, audio_visualizer_fade)| !audio_visualizer_fade.is_some_and(AudioVisualizerFade::is_fading_out))
+            .map(|(audio_visualizer_settings, audio_visualizer_transition, _)| audio_visualizer_transition.map_or(audio_visualizer_settings, |audio_visualizer_transition| &audio_visualizer_transition.to).clone())
+            .collect(),
+        advanced_settings: advanced_settings.clone(),
+        preset_transition: preset_transition_settings.clone(),
+        key_bindings: key_bindings.clone(),
+        screenshot: screenshot_settings.clone(),
+        osc_output: osc_output_settings.clone(),
+        osc_input: osc_input_settings.clone(),
+        midi: midi_settings.clone(),
+        background_color: clear_color.0
+    };
+
+    let contents = match ron::ser::to_string_pretty(&config, PrettyConfig::default()) {
+        Ok(contents) => contents,
+        Err(error) => {
+            error!("Failed to serialize settings: {}", error);
+            return;
+        }
+    };
+
+    if contents == config_file.last_saved_contents {
+        return;
+    }
+
+    if let Some(config_directory) = path.parent() {
+        if let Err(error) = fs::create_dir_all(config_directory) {
+            error!("Failed to create config directory {}: {}", config_directory.display(), error);
+            return;
+        }
+    }
+
+    match fs::write(&path, &contents) {
+        Ok(()) => config_file.last_saved_contents = contents,
+        Err(error) => error!("Failed to save config file {}: {}", path.display(), error)
+    }
+}
@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+use std::{io::Write, path::PathBuf};
+use crate::cli::AnalysisFormat;
+
+#[derive(Resource, Clone, Default)]
+pub struct HeadlessAnalysisSettings {
+    pub format: AnalysisFormat,
+    pub output_path: Option<PathBuf>,
+    pub preset: Option<String>
+}
+
+#[derive(Resource)]
+pub struct HeadlessAnalysis {
+    pub format: AnalysisFormat,
+    pub output: Box<dyn Write + Send + Sync>
+}
@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+use crate::audio_analysis::AudioAnalysisSet;
+
+pub mod components;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct HeadlessAnalysisPlugin;
+
+impl Plugin for HeadlessAnalysisPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<HeadlessAnalysisSettings>()
+        .add_systems(Startup, setup_headless_analysis)
+        .add_systems(PostUpdate, write_headless_analysis.run_if(resource_exists::<HeadlessAnalysis>).after(AudioAnalysisSet));
+    }
+}
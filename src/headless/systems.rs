@@ -0,0 +1,162 @@
+use bevy::{app::AppExit, prelude::*};
+use serde_json::json;
+use std::{fs::{self, File}, io::{self, BufWriter, ErrorKind, Write}, path::Path};
+use crate::{audio_analysis::components::{AudioAnalysis, AudioAnalysisEvent}, cli::AnalysisFormat, config::{components::{AudioVisualizerConfig, ConfigFile}, parse_config}, osc::components::OscOutputSettings, presets::{components::PresetLibrary, load_presets, parse_preset}, visualizer::{analysis::AudioAnalysisFrame, components::AudioVisualizerSettings}};
+use super::components::*;
+
+pub fn setup_headless_analysis(
+    mut commands: Commands,
+    mut audio_analysis: ResMut<AudioAnalysis>,
+    mut osc_output_settings: ResMut<OscOutputSettings>,
+    headless_analysis_settings: Res<HeadlessAnalysisSettings>,
+    mut app_exit_event_writer: EventWriter<AppExit>
+) {
+    let config = ConfigFile::default().path
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| parse_config(&contents).ok());
+    if let Some(config) = &config {
+        *osc_output_settings = config.osc_output.clone();
+    }
+
+    audio_analysis.audio_visualizer_settings = load_headless_audio_visualizer_settings(headless_analysis_settings.preset.as_deref(), config);
+
+    let output: Box<dyn Write + Send + Sync> = match &headless_analysis_settings.output_path {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(error) => {
+                error!("Failed to create analysis output {}: {}", path.display(), error);
+                app_exit_event_writer.send(AppExit);
+                return;
+            }
+        },
+        None => Box::new(io::stdout())
+    };
+
+    let mut headless_analysis = HeadlessAnalysis {
+        format: headless_analysis_settings.format,
+        output
+    };
+
+    if headless_analysis.format == AnalysisFormat::Csv {
+        let column_headers: Vec<String> = (0..audio_analysis.audio_visualizer_settings.column_count).map(|i| format!("column_{}", i)).collect();
+        if let Err(error) = writeln!(headless_analysis.output, "frame,time,average,{}", column_headers.join(",")) {
+            error!("Failed to write analysis output: {}", error);
+            app_exit_event_writer.send(AppExit);
+            return;
+        }
+    }
+
+    info!("Writing {:?} analysis of {} columns", headless_analysis.format, audio_analysis.audio_visualizer_settings.column_count);
+    commands.insert_resource(headless_analysis);
+}
+
+pub fn write_headless_analysis(
+    mut audio_analysis_event_reader: EventReader<AudioAnalysisEvent>,
+    mut headless_analysis: ResMut<HeadlessAnalysis>,
+    mut app_exit_event_writer: EventWriter<AppExit>
+) {
+    for audio_analysis_event in audio_analysis_event_reader.read() {
+        let format = headless_analysis.format;
+        let result = write_analysis_record(&mut headless_analysis.output, format, audio_analysis_event.frame, audio_analysis_event.time, &audio_analysis_event.columns)
+            .and_then(|_| headless_analysis.output.flush());
+
+        if let Err(error) = result {
+            if error.kind() != ErrorKind::BrokenPipe {
+                error!("Failed to write analysis output: {}", error);
+            }
+            app_exit_event_writer.send(AppExit);
+            return;
+        }
+    }
+}
+
+fn write_analysis_record(
+    output: &mut impl Write,
+    format: AnalysisFormat,
+    frame: u64,
+    time: f64,
+    audio_analysis_frame: &AudioAnalysisFrame
+) -> io::Result<()> {
+    match format {
+        AnalysisFormat::Csv => {
+            let values: Vec<String> = audio_analysis_frame.values.iter().map(f32::to_string).collect();
+            writeln!(output, "{},{},{},{}", frame, time, audio_analysis_frame.average_value, values.join(","))
+        },
+        AnalysisFormat::JsonLines => {
+            let record = json!({
+                "frame": frame,
+                "time": time,
+                "average": audio_analysis_frame.average_value,
+                "values": audio_analysis_frame.values,
+                "highlighted": audio_analysis_frame.highlighted
+            });
+            writeln!(output, "{}", record)
+        },
+        AnalysisFormat::Binary => {
+            output.write_all(&frame.to_le_bytes())?;
+            output.write_all(&time.to_le_bytes())?;
+            output.write_all(&audio_analysis_frame.average_value.to_le_bytes())?;
+            output.write_all(&(audio_analysis_frame.values.len() as u32).to_le_bytes())?;
+            for value in &audio_analysis_frame.values {
+                output.write_all(&value.to_le_bytes())?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn load_headless_audio_visualizer_settings(
+    preset: Option<&str>,
+    config: Option<AudioVisualizerConfig>
+) -> AudioVisualizerSettings {
+    if let Some(preset) = preset {
+        let result = if Path::new(preset).is_file() {
+            fs::read_to_string(preset)
+                .map_err(|error| error.to_string())
+                .and_then(|contents| parse_preset(&contents).map_err(|error| error.to_string()))
+                .map_err(|error| format!("Invalid preset {}: {}", preset, error))
+        } else {
+            let mut preset_library = PresetLibrary::default();
+            load_presets(&mut preset_library);
+            preset_library.get(preset)
+                .map(|preset| preset.settings.clone())
+                .ok_or_else(|| format!("Preset `{}` does not exist", preset))
+        };
+
+        match result {
+            Ok(audio_visualizer_settings) => return audio_visualizer_settings,
+            Err(error) => error!("{}, using default settings", error)
+        }
+    }
+
+    config
+        .and_then(|config| config.visualizers.into_iter().next())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_lines_records_are_valid_json_even_with_non_finite_values() {
+        let audio_analysis_frame = AudioAnalysisFrame {
+            values: vec![0.5, f32::NAN, f32::INFINITY],
+            highlighted: vec![false, true, false],
+            average_value: f32::NAN
+        };
+        let mut output = Vec::new();
+        write_analysis_record(&mut output, AnalysisFormat::JsonLines, 3, 0.25, &audio_analysis_frame).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with('\n'));
+        let record: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(record, json!({
+            "frame": 3,
+            "time": 0.25,
+            "average": null,
+            "values": [0.5, null, null],
+            "highlighted": [false, true, false]
+        }));
+    }
+}
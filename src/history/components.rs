@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use crate::visualizer::components::AudioVisualizerSettings;
+use super::HISTORY_LIMIT;
+
+#[derive(Clone, PartialEq)]
+pub struct SettingsSnapshot {
+    pub visualizers: Vec<AudioVisualizerSettings>,
+    pub background_color: Color
+}
+
+#[derive(Resource, Default)]
+pub struct SettingsHistory {
+    pub undo_stack: VecDeque<SettingsSnapshot>,
+    pub redo_stack: Vec<SettingsSnapshot>,
+    pub current: Option<SettingsSnapshot>
+}
+
+impl SettingsHistory {
+    pub fn record(&mut self, snapshot: SettingsSnapshot) {
+        if self.current.as_ref() == Some(&snapshot) {
+            return;
+        }
+
+        if let Some(previous) = self.current.replace(snapshot) {
+            self.undo_stack.push_back(previous);
+            if self.undo_stack.len() > HISTORY_LIMIT {
+                self.undo_stack.pop_front();
+            }
+            self.redo_stack.clear();
+        }
+    }
+
+    pub fn undo(&mut self) -> Option<SettingsSnapshot> {
+        let snapshot = self.undo_stack.pop_back()?;
+        self.redo_stack.extend(self.current.replace(snapshot.clone()));
+        Some(snapshot)
+    }
+
+    pub fn redo(&mut self) -> Option<SettingsSnapshot> {
+        let snapshot = self.redo_stack.pop()?;
+        self.undo_stack.extend(self.current.replace(snapshot.clone()));
+        Some(snapshot)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+#[derive(Event, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryEvent {
+    Undo,
+    Redo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(radius: f32) -> SettingsSnapshot {
+        SettingsSnapshot {
+            visualizers: vec![AudioVisualizerSettings { radius, ..default() }],
+            background_color: Color::BLACK
+        }
+    }
+
+    fn radius(snapshot: Option<SettingsSnapshot>) -> Option<f32> {
+        snapshot.map(|snapshot| snapshot.visualizers[0].radius)
+    }
+
+    #[test]
+    fn first_snapshot_is_not_undoable() {
+        let mut settings_history = SettingsHistory::default();
+        settings_history.record(snapshot(1.0));
+
+        assert!(!settings_history.can_undo());
+        assert!(settings_history.undo().is_none());
+    }
+
+    #[test]
+    fn identical_snapshots_are_coalesced() {
+        let mut settings_history = SettingsHistory::default();
+        settings_history.record(snapshot(1.0));
+        settings_history.record(snapshot(2.0));
+        settings_history.record(snapshot(2.0));
+        settings_history.record(snapshot(2.0));
+
+        assert_eq!(settings_history.undo_stack.len(), 1);
+        assert_eq!(radius(settings_history.undo()), Some(1.0));
+        assert!(!settings_history.can_undo());
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_history_in_order() {
+        let mut settings_history = SettingsHistory::default();
+        for i in 1..=3 {
+            settings_history.record(snapshot(i as f32));
+        }
+
+        assert_eq!(radius(settings_history.undo()), Some(2.0));
+        assert_eq!(radius(settings_history.undo()), Some(1.0));
+        assert_eq!(radius(settings_history.undo()), None);
+        assert_eq!(radius(settings_history.redo()), Some(2.0));
+        assert_eq!(radius(settings_history.redo()), Some(3.0));
+        assert_eq!(radius(settings_history.redo()), None);
+        assert_eq!(radius(settings_history.current.clone()), Some(3.0));
+    }
+
+    #[test]
+    fn restored_snapshot_is_not_recorded_again() {
+        let mut settings_history = SettingsHistory::default();
+        settings_history.record(snapshot(1.0));
+        settings_history.record(snapshot(2.0));
+
+        let restored_snapshot = settings_history.undo().unwrap();
+        settings_history.record(restored_snapshot);
+
+        assert!(settings_history.can_redo());
+        assert!(!settings_history.can_undo());
+    }
+
+    #[test]
+    fn new_snapshot_clears_redo() {
+        let mut settings_history = SettingsHistory::default();
+        settings_history.record(snapshot(1.0));
+        settings_history.record(snapshot(2.0));
+        settings_history.undo();
+        settings_history.record(snapshot(3.0));
+
+        assert!(!settings_history.can_redo());
+        assert_eq!(radius(settings_history.undo()), Some(1.0));
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut settings_history = SettingsHistory::default();
+        for i in 0..HISTORY_LIMIT + 10 {
+            settings_history.record(snapshot(i as f32));
+        }
+
+        assert_eq!(settings_history.undo_stack.len(), HISTORY_LIMIT);
+        let mut oldest_snapshot = None;
+        while let Some(snapshot) = settings_history.undo() {
+            oldest_snapshot = Some(snapshot);
+        }
+        assert_eq!(radius(oldest_snapshot), Some(9.0));
+    }
+}
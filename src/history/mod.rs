@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+use crate::visualizer::AudioVisualizerSpawnSet;
+
+pub mod components;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<SettingsHistory>()
+        .add_event::<HistoryEvent>()
+        .add_systems(Update, handle_history_events.run_if(on_event::<HistoryEvent>()).before(AudioVisualizerSpawnSet))
+        .add_systems(PostUpdate, record_settings_history);
+    }
+}
+
+pub const HISTORY_LIMIT: usize = 100;
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use crate::{ui::components::UiInputState, visualizer::components::{AudioVisualizerContainer, AudioVisualizerFade, AudioVisualizerRestoreEvent, AudioVisualizerSettings, AudioVisualizerTransition}};
+use super::components::*;
+
+#[allow(clippy::type_complexity)]
+pub fn record_settings_history(
+    ui_input_state: Option<Res<UiInputState>>,
+    audio_visualizer_container_query: Query<&AudioVisualizerSettings, With<AudioVisualizerContainer>>,
+    animated_audio_visualizer_query: Query<(), Or<(With<AudioVisualizerTransition>, With<AudioVisualizerFade>)>>,
+    mut settings_history: ResMut<SettingsHistory>,
+    clear_color: Res<ClearColor>
+) {
+    // Waits until drags, text edits and animations settle so they end up as a single entry
+    if ui_input_state.is_some_and(|ui_input_state| ui_input_state.wants_keyboard_input || ui_input_state.pointer_busy) {
+        return;
+    }
+    if !animated_audio_visualizer_query.is_empty() {
+        return;
+    }
+
+    settings_history.record(SettingsSnapshot {
+        visualizers: audio_visualizer_container_query.iter().map(history_settings).collect(),
+        background_color: clear_color.0
+    });
+}
+
+pub fn handle_history_events(
+    mut history_event_reader: EventReader<HistoryEvent>,
+    mut audio_visualizer_restore_event_writer: EventWriter<AudioVisualizerRestoreEvent>,
+    mut settings_history: ResMut<SettingsHistory>,
+    mut clear_color: ResMut<ClearColor>
+) {
+    let mut restored_snapshot = None;
+    for history_event in history_event_reader.read() {
+        let snapshot = match history_event {
+            HistoryEvent::Undo => settings_history.undo(),
+            HistoryEvent::Redo => settings_history.redo()
+        };
+        restored_snapshot = snapshot.or(restored_snapshot);
+    }
+
+    if let Some(snapshot) = restored_snapshot {
+        clear_color.0 = snapshot.background_color;
+        audio_visualizer_restore_event_writer.send(AudioVisualizerRestoreEvent(snapshot.visualizers));
+    }
+}
+
+fn history_settings(audio_visualizer_settings: &AudioVisualizerSettings) -> AudioVisualizerSettings {
+    let default_settings = AudioVisualizerSettings::default();
+    let mut audio_visualizer_settings = audio_visualizer_settings.clone();
+    audio_visualizer_settings.normal_current_color = default_settings.normal_current_color;
+    audio_visualizer_settings.normal_color_transition_progress = default_settings.normal_color_transition_progress;
+    audio_visualizer_settings.normal_color_transition_speed = audio_visualizer_settings.normal_color_transition_speed.abs();
+    audio_visualizer_settings.highlight_current_color = default_settings.highlight_current_color;
+    audio_visualizer_settings.highlight_color_transition_progress = default_settings.highlight_color_transition_progress;
+    audio_visualizer_settings.highlight_color_transition_speed = audio_visualizer_settings.highlight_color_transition_speed.abs();
+    audio_visualizer_settings
+}
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::{mpsc::{Receiver, Sender}, Mutex};
+use crate::{cli::AudioSourceType, visualizer::components::AudioVisualizerSettings, AdvancedSettings};
+
+#[derive(Resource, Clone)]
+pub struct HttpApiServerSettings {
+    pub address: String
+}
+
+#[derive(Resource)]
+pub struct HttpApiServer {
+    pub request_receiver: Mutex<Receiver<HttpApiRequest>>
+}
+
+pub struct HttpApiRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Vec<u8>,
+    pub audio_devices: Option<HttpApiAudioDevices>,
+    pub response_sender: Sender<HttpApiResponse>
+}
+
+#[derive(Default)]
+pub struct HttpApiAudioDevices {
+    pub output: Vec<String>,
+    pub input: Vec<String>
+}
+
+impl HttpApiAudioDevices {
+    pub fn names(&self, source: AudioSourceType) -> &[String] {
+        match source {
+            AudioSourceType::Output => &self.output,
+            AudioSourceType::Input => &self.input
+        }
+    }
+}
+
+pub struct HttpApiResponse {
+    pub status: u16,
+    pub body: serde_json::Value
+}
+
+impl HttpApiResponse {
+    pub fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message })
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HttpApiSettings {
+    pub visualizers: Vec<AudioVisualizerSettings>,
+    pub background_color: Color,
+    pub advanced_settings: AdvancedSettings
+}
+
+#[derive(Deserialize)]
+pub struct HttpApiPresetSelection {
+    pub name: String
+}
+
+#[derive(Deserialize)]
+pub struct HttpApiDeviceSelection {
+    pub name: Option<String>,
+    pub source: Option<AudioSourceType>
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+use std::time::Duration;
+use crate::visualizer::AudioVisualizerSpawnSet;
+
+pub mod components;
+pub mod protocol;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct HttpApiPlugin;
+
+impl Plugin for HttpApiPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_systems(Startup, setup_http_api_server)
+        .add_systems(Update, handle_http_api_requests.run_if(resource_exists::<HttpApiServer>).before(AudioVisualizerSpawnSet));
+    }
+}
+
+pub const HTTP_API_MAX_HEAD_LENGTH: usize = 8192;
+pub const HTTP_API_MAX_BODY_LENGTH: usize = 1024 * 1024;
+pub const HTTP_API_READ_TIMEOUT: Duration = Duration::from_secs(10);
+pub const HTTP_API_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
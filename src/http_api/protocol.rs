@@ -0,0 +1,148 @@
+use std::io::{self, BufRead, ErrorKind, Write};
+use crate::http_api::*;
+
+pub fn read_http_request(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write
+) -> io::Result<(String, String, Vec<u8>)> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut request_parts = request_line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (request_parts.next(), request_parts.next(), request_parts.next()) else {
+        return Err(io::Error::new(ErrorKind::InvalidData, "malformed request line"));
+    };
+    let path = target.split('?').next().unwrap_or(target);
+    let path = if path.len() > 1 { path.trim_end_matches('/') } else { path };
+
+    let mut content_length = 0;
+    let mut expects_continue = false;
+    let mut head_length = request_line.len();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "connection closed during request"));
+        }
+        head_length += header.len();
+        if head_length > HTTP_API_MAX_HEAD_LENGTH {
+            return Err(io::Error::new(ErrorKind::InvalidData, "request head is too long"));
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let (name, value) = (name.trim(), value.trim());
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().map_err(|_| io::Error::new(ErrorKind::InvalidData, "invalid Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("transfer-encoding") && value.to_ascii_lowercase().contains("chunked") {
+            return Err(io::Error::new(ErrorKind::InvalidData, "chunked request bodies are not supported"));
+        } else if name.eq_ignore_ascii_case("expect") && value.eq_ignore_ascii_case("100-continue") {
+            expects_continue = true;
+        }
+    }
+
+    if content_length > HTTP_API_MAX_BODY_LENGTH {
+        return Err(io::Error::new(ErrorKind::InvalidData, "request body is too large"));
+    }
+    if expects_continue && content_length > 0 {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        writer.flush()?;
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok((method.to_uppercase(), path.to_string(), body))
+}
+
+pub fn write_http_response(
+    writer: &mut impl Write,
+    status: u16,
+    body: &str
+) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        http_status_reason(status),
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+fn http_status_reason(
+    status: u16
+) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read(request: &str) -> (io::Result<(String, String, Vec<u8>)>, String) {
+        let mut written = Vec::new();
+        let result = read_http_request(&mut Cursor::new(request.as_bytes().to_vec()), &mut written);
+        (result, String::from_utf8(written).unwrap())
+    }
+
+    #[test]
+    fn reads_requests_without_a_body() {
+        let (result, written) = read("get /settings/?pretty=true HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+        assert_eq!(result.unwrap(), ("GET".to_string(), "/settings".to_string(), Vec::new()));
+        assert!(written.is_empty());
+        assert_eq!(read("GET / HTTP/1.1\r\n\r\n").0.unwrap().1, "/");
+    }
+
+    #[test]
+    fn reads_the_body_by_content_length() {
+        let (result, _) = read("PUT /presets/active HTTP/1.1\r\ncontent-length: 17\r\n\r\n{\"name\":\"Pulse\"}\nextra");
+
+        assert_eq!(result.unwrap().2, b"{\"name\":\"Pulse\"}\n");
+    }
+
+    #[test]
+    fn answers_expect_continue_before_reading_the_body() {
+        let (result, written) = read("PUT /settings HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 2\r\n\r\n{}");
+
+        assert_eq!(result.unwrap().2, b"{}");
+        assert_eq!(written, "HTTP/1.1 100 Continue\r\n\r\n");
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let error_kind = |request: &str| read(request).0.unwrap_err().kind();
+
+        assert_eq!(error_kind("GET /settings\r\n\r\n"), ErrorKind::InvalidData);
+        assert_eq!(error_kind("PUT /settings HTTP/1.1\r\nContent-Length: ten\r\n\r\n"), ErrorKind::InvalidData);
+        assert_eq!(error_kind("PUT /settings HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"), ErrorKind::InvalidData);
+        assert_eq!(error_kind(&format!("PUT /settings HTTP/1.1\r\nContent-Length: {}\r\n\r\n", HTTP_API_MAX_BODY_LENGTH + 1)), ErrorKind::InvalidData);
+        assert_eq!(error_kind(&format!("GET /settings HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(HTTP_API_MAX_HEAD_LENGTH))), ErrorKind::InvalidData);
+        assert_eq!(error_kind("GET /settings HTTP/1.1\r\nHost: localhost\r\n"), ErrorKind::UnexpectedEof);
+        assert_eq!(error_kind("PUT /settings HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}"), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn writes_json_responses() {
+        let mut written = Vec::new();
+        write_http_response(&mut written, 404, "{\"error\":\"x\"}").unwrap();
+
+        assert_eq!(String::from_utf8(written).unwrap(), "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 13\r\nConnection: close\r\n\r\n{\"error\":\"x\"}");
+    }
+}
@@ -0,0 +1,350 @@
+use bevy::{diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin}, prelude::*};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{io::BufReader, net::{TcpListener, TcpStream}, sync::{mpsc::{self, Sender}, Mutex}, thread};
+use crate::{audio_data::{audio_device_names, components::{AudioCaptureSettings, AudioData, AudioDeviceSelectEvent}}, cli::AudioSourceType, http_api::*, presets::components::{PresetEvent, PresetLibrary}, visualizer::components::{AudioVisualizerContainer, AudioVisualizerFade, AudioVisualizerSettings, AudioVisualizerSpawnEvent, AudioVisualizerTransition, SelectedAudioVisualizer}, AdvancedSettings};
+use super::{components::*, protocol::*};
+
+type HttpApiResult = Result<Value, (u16, String)>;
+
+pub fn setup_http_api_server(
+    mut commands: Commands,
+    http_api_server_settings: Option<Res<HttpApiServerSettings>>
+) {
+    let Some(http_api_server_settings) = http_api_server_settings else {
+        return;
+    };
+    let address = &http_api_server_settings.address;
+
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(error) => {
+            error!("Failed to start HTTP API server on {}: {}", address, error);
+            return;
+        }
+    };
+
+    info!("HTTP API server listening on {}", listener.local_addr().map_or(address.clone(), |local_address| local_address.to_string()));
+
+    let (request_sender, request_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let request_sender = request_sender.clone();
+            thread::spawn(move || handle_http_api_connection(stream, request_sender));
+        }
+    });
+
+    commands.insert_resource(HttpApiServer {
+        request_receiver: Mutex::new(request_receiver)
+    });
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn handle_http_api_requests(
+    mut commands: Commands,
+    http_api_server: Res<HttpApiServer>,
+    mut audio_visualizer_container_query: Query<(Entity, &mut AudioVisualizerSettings, Option<&mut AudioVisualizerTransition>, Option<&AudioVisualizerFade>), With<AudioVisualizerContainer>>,
+    mut audio_visualizer_spawn_event_writer: EventWriter<AudioVisualizerSpawnEvent>,
+    mut selected_audio_visualizer: ResMut<SelectedAudioVisualizer>,
+    mut preset_event_writer: EventWriter<PresetEvent>,
+    mut audio_device_select_event_writer: EventWriter<AudioDeviceSelectEvent>,
+    preset_library: Res<PresetLibrary>,
+    mut advanced_settings: ResMut<AdvancedSettings>,
+    mut clear_color: ResMut<ClearColor>,
+    audio_data: NonSend<AudioData>,
+    diagnostics: Res<DiagnosticsStore>,
+    audio_capture_settings: Res<AudioCaptureSettings>
+) {
+    let requests: Vec<HttpApiRequest> = http_api_server.request_receiver.lock().unwrap().try_iter().collect();
+    if requests.is_empty() {
+        return;
+    }
+
+    // Requests handled in the same frame see each other's changes before they are applied to the visualizers
+    let (mut audio_visualizers, visualizers): (Vec<Entity>, Vec<AudioVisualizerSettings>) = audio_visualizer_container_query.iter()
+        .filter(|(_, _, _, audio_visualizer_fade)| !audio_visualizer_fade.is_some_and(AudioVisualizerFade::is_fading_out))
+        .map(|(entity, audio_visualizer_settings, audio_visualizer_transition, _)| (entity, audio_visualizer_transition.map_or(audio_visualizer_settings, |audio_visualizer_transition| &audio_visualizer_transition.to).clone()))
+        .unzip();
+    let mut settings = HttpApiSettings {
+        visualizers,
+        background_color: clear_color.0,
+        advanced_settings: advanced_settings.clone()
+    };
+    let mut visualizers_changed = false;
+
+    for request in requests {
+        let result: HttpApiResult = match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/status") => Ok(json!({
+                "fps": diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS).and_then(|fps| fps.smoothed()),
+                "stream": {
+                    "running": audio_data.stream.is_some(),
+                    "device": audio_data.device_name,
+                    "source": audio_data.source,
+                    "sample_rate": audio_data.sample_rate
+                },
+                "visualizers": settings.visualizers.len(),
+                "active_preset": preset_library.active_preset
+            })),
+            ("GET", "/settings") => serde_json::to_value(&settings).map_err(|error| (500, error.to_string())),
+            ("PUT", "/settings") => parse_json_body::<Value>(&request.body).and_then(|patch| {
+                let (updated_settings, corrections) = merge_http_api_settings(&settings, &patch)?;
+
+                visualizers_changed |= patch.get("visualizers").is_some();
+                clear_color.reborrow().map_unchanged(|clear_color| &mut clear_color.0).set_if_neq(updated_settings.background_color);
+                advanced_settings.set_if_neq(updated_settings.advanced_settings.clone());
+
+                settings = updated_settings;
+                Ok(json!({
+                    "settings": settings,
+                    "corrections": corrections
+                }))
+            }),
+            ("DELETE", path) if path.starts_with("/visualizers/") => {
+                match path["/visualizers/".len()..].parse::<usize>().ok().filter(|i| *i < settings.visualizers.len()) {
+                    Some(i) => {
+                        settings.visualizers.remove(i);
+                        // Visualizers added by an earlier request of this frame aren't spawned yet
+                        if i < audio_visualizers.len() {
+                            let audio_visualizer = audio_visualizers.remove(i);
+                            commands.entity(audio_visualizer).despawn_recursive();
+                            if selected_audio_visualizer.0 == Some(audio_visualizer) {
+                                selected_audio_visualizer.0 = None;
+                            }
+                        }
+                        Ok(json!({ "settings": settings }))
+                    },
+                    None => Err((404, format!("visualizer {} not found", &path["/visualizers/".len()..])))
+                }
+            },
+            ("GET", "/presets") => Ok(json!({
+                "active": preset_library.active_preset,
+                "presets": preset_library.presets.iter()
+                    .map(|preset| json!({ "name": preset.name, "built_in": preset.is_built_in() }))
+                    .collect::<Vec<Value>>()
+            })),
+            ("PUT", "/presets/active") => parse_json_body::<HttpApiPresetSelection>(&request.body).and_then(|preset_selection| {
+                if preset_library.get(&preset_selection.name).is_none() {
+                    return Err((404, format!("preset `{}` not found", preset_selection.name)));
+                }
+
+                preset_event_writer.send(PresetEvent::Load(preset_selection.name.clone()));
+                Ok(json!({ "active": preset_selection.name }))
+            }),
+            ("GET", "/devices") => {
+                let audio_devices = request.audio_devices.unwrap_or_default();
+                Ok(json!({
+                    "active": {
+                        "name": audio_data.device_name,
+                        "source": audio_data.source
+                    },
+                    "output": audio_devices.output,
+                    "input": audio_devices.input
+                }))
+            },
+            ("PUT", "/devices/active") => parse_json_body::<HttpApiDeviceSelection>(&request.body).and_then(|device_selection| {
+                if !audio_capture_settings.enabled {
+                    return Err((409, "audio capture is disabled".to_string()));
+                }
+
+                let source = device_selection.source.unwrap_or(audio_data.source);
+                if let Some(name) = &device_selection.name {
+                    if !request.audio_devices.as_ref().is_some_and(|audio_devices| audio_devices.names(source).contains(name)) {
+                        return Err((404, format!("audio device `{}` not found", name)));
+                    }
+                }
+
+                audio_device_select_event_writer.send(AudioDeviceSelectEvent {
+                    device: device_selection.name.clone(),
+                    source
+                });
+                Ok(json!({
+                    "name": device_selection.name,
+                    "source": source
+                }))
+            }),
+            (_, "/status" | "/settings" | "/presets" | "/presets/active" | "/devices" | "/devices/active") => Err((405, format!("method {} is not allowed on {}", request.method, request.path))),
+            (_, path) if path.starts_with("/visualizers/") => Err((405, format!("method {} is not allowed on {}", request.method, request.path))),
+            _ => Err((404, format!("unknown endpoint {}", request.path)))
+        };
+
+        let _ = request.response_sender.send(match result {
+            Ok(body) => HttpApiResponse { status: 200, body },
+            Err((status, message)) => HttpApiResponse::error(status, &message)
+        });
+    }
+
+    // Applied like edits in the settings window, so a changed structure rebuilds the visualizer and added ones are spawned like with the Add button
+    if visualizers_changed {
+        for (audio_visualizer, updated_audio_visualizer_settings) in audio_visualizers.iter().zip(&settings.visualizers) {
+            let Ok((_, mut audio_visualizer_settings, audio_visualizer_transition, _)) = audio_visualizer_container_query.get_mut(*audio_visualizer) else {
+                continue;
+            };
+            // Changes during a transition go to its target so they aren't overwritten by the interpolation
+            match audio_visualizer_transition {
+                Some(audio_visualizer_transition) => audio_visualizer_transition.map_unchanged(|audio_visualizer_transition| &mut audio_visualizer_transition.to).set_if_neq(updated_audio_visualizer_settings.clone()),
+                None => audio_visualizer_settings.set_if_neq(updated_audio_visualizer_settings.clone())
+            };
+        }
+        for audio_visualizer_settings in &settings.visualizers[audio_visualizers.len()..] {
+            audio_visualizer_spawn_event_writer.send(AudioVisualizerSpawnEvent(audio_visualizer_settings.clone()));
+        }
+    }
+}
+
+fn handle_http_api_connection(
+    stream: TcpStream,
+    request_sender: Sender<HttpApiRequest>
+) {
+    let _ = stream.set_read_timeout(Some(HTTP_API_READ_TIMEOUT));
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+
+    let response = match read_http_request(&mut reader, &mut writer) {
+        Ok((method, path, body)) => {
+            // Devices are enumerated here so a slow audio backend doesn't stall a frame
+            let audio_devices = matches!(path.as_str(), "/devices" | "/devices/active").then(|| HttpApiAudioDevices {
+                output: audio_device_names(AudioSourceType::Output),
+                input: audio_device_names(AudioSourceType::Input)
+            });
+            let (response_sender, response_receiver) = mpsc::channel();
+            if request_sender.send(HttpApiRequest { method, path, body, audio_devices, response_sender }).is_err() {
+                return;
+            }
+            response_receiver.recv_timeout(HTTP_API_RESPONSE_TIMEOUT)
+                .unwrap_or_else(|_| HttpApiResponse::error(503, "the visualizer did not respond in time"))
+        },
+        Err(error) => HttpApiResponse::error(400, &error.to_string())
+    };
+
+    let _ = write_http_response(&mut writer, response.status, &response.body.to_string());
+}
+
+fn parse_json_body<T: DeserializeOwned>(
+    body: &[u8]
+) -> Result<T, (u16, String)> {
+    serde_json::from_slice(body).map_err(|error| (400, format!("invalid request body: {}", error)))
+}
+
+fn merge_http_api_settings(
+    settings: &HttpApiSettings,
+    patch: &Value
+) -> Result<(HttpApiSettings, Vec<String>), (u16, String)> {
+    if !patch.is_object() {
+        return Err((400, "invalid request body: expected a JSON object".to_string()));
+    }
+
+    let mut merged_settings = serde_json::to_value(settings).map_err(|error| (500, error.to_string()))?;
+    merge_json(&mut merged_settings, patch.clone());
+
+    let mut updated_settings: HttpApiSettings = serde_json::from_value(merged_settings)
+        .map_err(|error| (400, format!("invalid settings: {}", error)))?;
+
+    let mut corrections = Vec::new();
+    for (i, audio_visualizer_settings) in updated_settings.visualizers.iter_mut().enumerate() {
+        for correction in audio_visualizer_settings.sanitize() {
+            warn!("Invalid HTTP API value in visualizers[{}]: {}", i, correction);
+            corrections.push(format!("visualizers[{}]: {}", i, correction));
+        }
+    }
+
+    Ok((updated_settings, corrections))
+}
+
+// Objects are merged key by key and arrays element by element, so a patch only needs the values it changes and elements past its end are kept
+fn merge_json(
+    target: &mut Value,
+    patch: Value
+) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(target_value) => merge_json(target_value, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        },
+        (Value::Array(target), Value::Array(patch)) => {
+            for (i, value) in patch.into_iter().enumerate() {
+                match target.get_mut(i) {
+                    Some(target_value) => merge_json(target_value, value),
+                    None => target.push(value)
+                }
+            }
+        },
+        (target, patch) => *target = patch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http_api_settings() -> HttpApiSettings {
+        HttpApiSettings {
+            visualizers: vec![AudioVisualizerSettings::default(), AudioVisualizerSettings { radius: 300.0, ..default() }],
+            background_color: Color::BLACK,
+            advanced_settings: AdvancedSettings::default()
+        }
+    }
+
+    #[test]
+    fn objects_are_merged_key_by_key() {
+        let mut target = json!({ "a": 1, "b": { "c": 2, "d": 3 } });
+        merge_json(&mut target, json!({ "b": { "d": 4 }, "e": 5 }));
+
+        assert_eq!(target, json!({ "a": 1, "b": { "c": 2, "d": 4 }, "e": 5 }));
+    }
+
+    #[test]
+    fn arrays_are_merged_by_index_without_truncating() {
+        let mut target = json!([{ "a": 1, "b": 1 }, { "a": 2 }, { "a": 3 }]);
+        merge_json(&mut target, json!([{ "b": 2 }]));
+        assert_eq!(target, json!([{ "a": 1, "b": 2 }, { "a": 2 }, { "a": 3 }]));
+
+        merge_json(&mut target, json!([{}, {}, {}, { "a": 4 }]));
+        assert_eq!(target, json!([{ "a": 1, "b": 2 }, { "a": 2 }, { "a": 3 }, { "a": 4 }]));
+    }
+
+    #[test]
+    fn mismatched_types_are_replaced() {
+        let mut target = json!({ "a": [1, 2], "b": { "c": 1 } });
+        merge_json(&mut target, json!({ "a": 3, "b": null }));
+
+        assert_eq!(target, json!({ "a": 3, "b": null }));
+    }
+
+    #[test]
+    fn partial_settings_patch_keeps_other_visualizers_and_gradient_stops() {
+        let settings = http_api_settings();
+        let patch = json!({ "visualizers": [{ "max_height": 500.0, "frequency_gradient": { "stops": [{ "position": 0.1 }] } }] });
+        let (updated_settings, corrections) = merge_http_api_settings(&settings, &patch).unwrap();
+
+        assert!(corrections.is_empty());
+        assert_eq!(updated_settings.visualizers.len(), 2);
+        assert_eq!(updated_settings.visualizers[0].max_height, 500.0);
+        assert_eq!(updated_settings.visualizers[1].radius, 300.0);
+
+        let stops = &updated_settings.visualizers[0].frequency_gradient.stops;
+        assert_eq!(stops.len(), settings.visualizers[0].frequency_gradient.stops.len());
+        assert_eq!(stops[0].position, 0.1);
+        assert!(stops[1..] == settings.visualizers[0].frequency_gradient.stops[1..]);
+    }
+
+    #[test]
+    fn invalid_settings_patches_are_rejected_or_corrected() {
+        let settings = http_api_settings();
+
+        assert_eq!(merge_http_api_settings(&settings, &json!([])).err().unwrap().0, 400);
+        assert_eq!(merge_http_api_settings(&settings, &json!({ "visualizers": [{ "radius": "large" }] })).err().unwrap().0, 400);
+
+        let (updated_settings, corrections) = merge_http_api_settings(&settings, &json!({ "visualizers": [{ "column_count": 0 }] })).unwrap();
+        assert_eq!(updated_settings.visualizers[0].column_count, 2);
+        assert!(!corrections.is_empty());
+        assert!(corrections.iter().all(|correction| correction.starts_with("visualizers[0]: ")));
+    }
+}
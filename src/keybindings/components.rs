@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool
+}
+
+impl KeyBinding {
+    pub const fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false
+        }
+    }
+
+    pub const fn with_ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+
+    pub const fn with_shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    pub fn just_pressed(&self, keyboard_input: &ButtonInput<KeyCode>) -> bool {
+        keyboard_input.just_pressed(self.key)
+            && self.ctrl == keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && self.shift == keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+            && self.alt == keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub toggle_ui: KeyBinding,
+    pub toggle_fullscreen: KeyBinding,
+    pub toggle_freeze: KeyBinding,
+    pub next_visualizer_type: KeyBinding,
+    pub next_preset: KeyBinding,
+    pub previous_preset: KeyBinding,
+    pub undo: KeyBinding,
+    pub redo: KeyBinding,
+    pub screenshot: KeyBinding,
+    pub screenshot_without_ui: KeyBinding,
+    pub increase_radius: KeyBinding,
+    pub decrease_radius: KeyBinding,
+    pub increase_max_height: KeyBinding,
+    pub decrease_max_height: KeyBinding,
+    pub increase_rotation_speed: KeyBinding,
+    pub decrease_rotation_speed: KeyBinding
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            toggle_ui: KeyBinding::new(KeyCode::F1),
+            toggle_fullscreen: KeyBinding::new(KeyCode::F11),
+            toggle_freeze: KeyBinding::new(KeyCode::Space),
+            next_visualizer_type: KeyBinding::new(KeyCode::KeyT),
+            next_preset: KeyBinding::new(KeyCode::PageDown),
+            previous_preset: KeyBinding::new(KeyCode::PageUp),
+            undo: KeyBinding::new(KeyCode::KeyZ).with_ctrl(),
+            redo: KeyBinding::new(KeyCode::KeyZ).with_ctrl().with_shift(),
+            screenshot: KeyBinding::new(KeyCode::F12),
+            screenshot_without_ui: KeyBinding::new(KeyCode::F12).with_shift(),
+            increase_radius: KeyBinding::new(KeyCode::ArrowUp),
+            decrease_radius: KeyBinding::new(KeyCode::ArrowDown),
+            increase_max_height: KeyBinding::new(KeyCode::ArrowUp).with_shift(),
+            decrease_max_height: KeyBinding::new(KeyCode::ArrowDown).with_shift(),
+            increase_rotation_speed: KeyBinding::new(KeyCode::ArrowRight),
+            decrease_rotation_speed: KeyBinding::new(KeyCode::ArrowLeft)
+        }
+    }
+}
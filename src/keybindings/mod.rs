@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+pub mod components;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct KeyBindingPlugin;
+
+impl Plugin for KeyBindingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<KeyBindings>()
+        .add_systems(Update, handle_key_bindings);
+    }
+}
+
+pub const RADIUS_STEP: f32 = 10.0;
+pub const MAX_HEIGHT_STEP: f32 = 10.0;
+pub const ROTATION_SPEED_STEP: f32 = 0.001;
@@ -0,0 +1,126 @@
+use bevy::{prelude::*, window::{PrimaryWindow, WindowMode}};
+use crate::{history::components::HistoryEvent, presets::components::{PresetEvent, PresetLibrary, PresetTransitionSettings}, screenshot::components::ScreenshotEvent, ui::components::{UiInputState, UiState}, visualizer::{components::{AudioVisualizerContainer, AudioVisualizerSettings, AudioVisualizerTransition, AudioVisualizerTransitionEvent, AudioVisualizerUpdateTimer, SelectedAudioVisualizer, VisualilzerType}, MAX_HEIGHT_RANGE, RADIUS_RANGE, ROTATION_SPEED_RANGE}};
+use crate::keybindings::*;
+use super::components::*;
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_key_bindings(
+    ui_input_state: Option<Res<UiInputState>>,
+    mut history_event_writer: EventWriter<HistoryEvent>,
+    mut preset_event_writer: EventWriter<PresetEvent>,
+    mut screenshot_event_writer: EventWriter<ScreenshotEvent>,
+    mut audio_visualizer_transition_event_writer: EventWriter<AudioVisualizerTransitionEvent>,
+    mut audio_visualizer_container_query: Query<(&mut AudioVisualizerSettings, Option<&AudioVisualizerTransition>), With<AudioVisualizerContainer>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut audio_visualizer_update_timer: ResMut<AudioVisualizerUpdateTimer>,
+    ui_state: Option<ResMut<UiState>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    selected_audio_visualizer: Res<SelectedAudioVisualizer>,
+    preset_library: Res<PresetLibrary>,
+    preset_transition_settings: Res<PresetTransitionSettings>
+) {
+    if ui_input_state.is_some_and(|ui_input_state| ui_input_state.wants_keyboard_input) {
+        return;
+    }
+
+    if key_bindings.toggle_ui.just_pressed(&keyboard_input) {
+        if let Some(mut ui_state) = ui_state {
+            ui_state.visible = !ui_state.visible;
+        }
+    }
+
+    if key_bindings.toggle_fullscreen.just_pressed(&keyboard_input) {
+        if let Ok(mut window) = window_query.get_single_mut() {
+            window.mode = match window.mode {
+                WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+                _ => WindowMode::Windowed
+            };
+        }
+    }
+
+    if key_bindings.toggle_freeze.just_pressed(&keyboard_input) {
+        let timer = &mut audio_visualizer_update_timer.timer;
+        if timer.paused() {
+            timer.unpause();
+        } else {
+            timer.pause();
+        }
+    }
+
+    if key_bindings.undo.just_pressed(&keyboard_input) {
+        history_event_writer.send(HistoryEvent::Undo);
+    }
+    if key_bindings.redo.just_pressed(&keyboard_input) {
+        history_event_writer.send(HistoryEvent::Redo);
+    }
+
+    if key_bindings.screenshot.just_pressed(&keyboard_input) {
+        screenshot_event_writer.send(ScreenshotEvent { include_ui: true });
+    }
+    if key_bindings.screenshot_without_ui.just_pressed(&keyboard_input) {
+        screenshot_event_writer.send(ScreenshotEvent { include_ui: false });
+    }
+
+    let preset_offset = if key_bindings.next_preset.just_pressed(&keyboard_input) {
+        Some(1)
+    } else if key_bindings.previous_preset.just_pressed(&keyboard_input) {
+        Some(preset_library.presets.len().saturating_sub(1))
+    } else {
+        None
+    };
+    if let Some(preset_offset) = preset_offset.filter(|_| !preset_library.presets.is_empty()) {
+        let active_index = preset_library.active_preset.as_ref()
+            .and_then(|name| preset_library.presets.iter().position(|preset| preset.name == *name));
+        let index = match active_index {
+            Some(active_index) => (active_index + preset_offset) % preset_library.presets.len(),
+            None if preset_offset == 1 => 0,
+            None => preset_library.presets.len() - 1
+        };
+        preset_event_writer.send(PresetEvent::Load(preset_library.presets[index].name.clone()));
+    }
+
+    let Some(container) = selected_audio_visualizer.0 else {
+        return;
+    };
+    let Ok((mut audio_visualizer_settings, audio_visualizer_transition)) = audio_visualizer_container_query.get_mut(container) else {
+        return;
+    };
+
+    if key_bindings.next_visualizer_type.just_pressed(&keyboard_input) {
+        let mut next_audio_visualizer_settings = audio_visualizer_transition.map_or(&*audio_visualizer_settings, |audio_visualizer_transition| &audio_visualizer_transition.to).clone();
+        next_audio_visualizer_settings.visualizer_type = match next_audio_visualizer_settings.visualizer_type {
+            VisualilzerType::FrequencyVisualizer => VisualilzerType::SpectrumVisualizer,
+            VisualilzerType::SpectrumVisualizer => VisualilzerType::FrequencyVisualizer
+        };
+        audio_visualizer_transition_event_writer.send(AudioVisualizerTransitionEvent {
+            container,
+            settings: next_audio_visualizer_settings,
+            duration: preset_transition_settings.duration,
+            easing: preset_transition_settings.easing
+        });
+        return;
+    }
+
+    if audio_visualizer_transition.is_some() {
+        return;
+    }
+    if key_bindings.increase_radius.just_pressed(&keyboard_input) {
+        audio_visualizer_settings.radius = (audio_visualizer_settings.radius + RADIUS_STEP).min(*RADIUS_RANGE.end());
+    }
+    if key_bindings.decrease_radius.just_pressed(&keyboard_input) {
+        audio_visualizer_settings.radius = (audio_visualizer_settings.radius - RADIUS_STEP).max(*RADIUS_RANGE.start());
+    }
+    if key_bindings.increase_max_height.just_pressed(&keyboard_input) {
+        audio_visualizer_settings.max_height = (audio_visualizer_settings.max_height + MAX_HEIGHT_STEP).min(*MAX_HEIGHT_RANGE.end());
+    }
+    if key_bindings.decrease_max_height.just_pressed(&keyboard_input) {
+        audio_visualizer_settings.max_height = (audio_visualizer_settings.max_height - MAX_HEIGHT_STEP).max(*MAX_HEIGHT_RANGE.start());
+    }
+    if key_bindings.increase_rotation_speed.just_pressed(&keyboard_input) {
+        audio_visualizer_settings.rotation_speed = (audio_visualizer_settings.rotation_speed + ROTATION_SPEED_STEP).min(*ROTATION_SPEED_RANGE.end());
+    }
+    if key_bindings.decrease_rotation_speed.just_pressed(&keyboard_input) {
+        audio_visualizer_settings.rotation_speed = (audio_visualizer_settings.rotation_speed - ROTATION_SPEED_STEP).max(*ROTATION_SPEED_RANGE.start());
+    }
+}
@@ -0,0 +1,309 @@
+//! Customizable audio visualizer written in Rust using Bevy.
+//!
+//! The visualizer captures the audio that is playing (or a microphone), analyzes it and draws it as
+//! circular frequency and spectrum visualizers. [`AudioVisualizerPlugins`] adds everything the standalone
+//! application runs besides the window and the camera, so it can be embedded in other Bevy apps, e.g. as
+//! the background of a game:
+//!
+//! ```no_run
+//! use bevy::{core_pipeline::bloom::BloomSettings, prelude::*};
+//! use customizable_bevy_audio_visualizer::{ui::UiPlugin, AudioVisualizerPlugins, AudioVisualizerSettings, AudioVisualizerSpawnEvent};
+//!
+//! fn main() {
+//!     App::new()
+//!         .add_plugins(DefaultPlugins)
+//!         .add_plugins(AudioVisualizerPlugins.build().disable::<UiPlugin>())
+//!         .add_systems(Startup, setup)
+//!         .run();
+//! }
+//!
+//! fn setup(
+//!     mut commands: Commands,
+//!     mut audio_visualizer_spawn_event_writer: EventWriter<AudioVisualizerSpawnEvent>
+//! ) {
+//!     // The glow of the columns needs an HDR camera with bloom
+//!     commands.spawn((Camera2dBundle { camera: Camera { hdr: true, ..default() }, ..default() }, BloomSettings::default()));
+//!     audio_visualizer_spawn_event_writer.send(AudioVisualizerSpawnEvent(AudioVisualizerSettings { radius: 300.0, ..default() }));
+//! }
+//! ```
+//!
+//! The analysis of the selected visualizer is sent as an [`AudioAnalysisEvent`] after every update, so game
+//! systems can react to the band energies and beats as well.
+
+use bevy::{app::PluginGroupBuilder, diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin}, prelude::*, window::{PresentMode, PrimaryWindow}};
+use serde::{Deserialize, Serialize};
+
+pub mod visualizer;
+pub mod audio_analysis;
+pub mod audio_data;
+pub mod cli;
+pub mod config;
+pub mod headless;
+pub mod history;
+pub mod http_api;
+pub mod keybindings;
+pub mod midi;
+pub mod offline_render;
+pub mod osc;
+pub mod presets;
+pub mod screenshot;
+pub mod ui;
+pub mod websocket;
+
+pub use audio_analysis::{components::{AudioAnalysis, AudioAnalysisEvent}, AudioAnalysisPlugin, AudioAnalysisSet};
+pub use audio_data::{components::{AudioCaptureSettings, AudioData, AudioDeviceSelectEvent}, AudioDataPlugin};
+pub use config::ConfigPlugin;
+pub use history::{components::HistoryEvent, HistoryPlugin};
+pub use keybindings::KeyBindingPlugin;
+pub use midi::MidiPlugin;
+pub use osc::{OscInputPlugin, OscPlugin};
+pub use presets::{components::{PresetEvent, PresetLibrary}, PresetPlugin};
+pub use screenshot::{components::ScreenshotEvent, ScreenshotPlugin};
+pub use ui::UiPlugin;
+pub use visualizer::{analysis::AudioAnalysisFrame, components::{AudioVisualizerRestoreEvent, AudioVisualizerSettings, AudioVisualizerSpawnEvent, AudioVisualizerTransitionEvent, SelectedAudioVisualizer, VisualilzerType}, AudioVisualizerPlugin, AudioVisualizerSpawnSet};
+
+/// All plugins of the visualizer application except for the window and the camera.
+///
+/// `UiPlugin` (the settings window), `ConfigPlugin` (loading and saving the settings file) and
+/// `KeyBindingPlugin` can be disabled when the visualizer is embedded in another app.
+pub struct AudioVisualizerPlugins;
+
+impl PluginGroup for AudioVisualizerPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(AdvancedSettingsPlugin)
+            .add(UiPlugin)
+            .add(AudioDataPlugin)
+            .add(AudioAnalysisPlugin)
+            .add(AudioVisualizerPlugin)
+            .add(ConfigPlugin)
+            .add(PresetPlugin)
+            .add(HistoryPlugin)
+            .add(KeyBindingPlugin)
+            .add(ScreenshotPlugin)
+            .add(OscPlugin)
+            .add(OscInputPlugin)
+            .add(MidiPlugin)
+    }
+}
+
+/// VSync and the FPS counter.
+pub struct AdvancedSettingsPlugin;
+
+impl Plugin for AdvancedSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+
+        app
+        .init_resource::<AdvancedSettings>()
+        .add_systems(Startup, setup_fps_counter)
+        .add_systems(Update, measure_fps.run_if(|advanced_settings: Res<AdvancedSettings>| advanced_settings.show_fps))
+        .add_systems(Update, update_advanced_settings.run_if(resource_changed::<AdvancedSettings>));
+    }
+}
+
+#[derive(Component)]
+struct FpsCounter;
+
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdvancedSettings {
+    pub vsync: bool,
+    pub show_fps: bool
+}
+
+impl Default for AdvancedSettings {
+    fn default() -> Self {
+        AdvancedSettings {
+            vsync: true,
+            show_fps: false
+        }
+    }
+}
+
+fn update_advanced_settings(
+    mut fps_counter_query: Query<&mut Visibility, With<FpsCounter>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    advanced_settings: Res<AdvancedSettings>,
+    mut previous_vsync: Local<Option<bool>>
+) {
+    // The window keeps the present mode it was created with until the VSync setting is changed
+    if previous_vsync.is_some_and(|previous_vsync| previous_vsync != advanced_settings.vsync) {
+        if let Ok(mut window) = window_query.get_single_mut() {
+            window.present_mode = vsync_present_mode(advanced_settings.vsync);
+        }
+    }
+    *previous_vsync = Some(advanced_settings.vsync);
+
+    if let Ok(mut fps_counter_visibility) = fps_counter_query.get_single_mut() {
+        if advanced_settings.show_fps {
+            *fps_counter_visibility = Visibility::Visible;
+        } else {
+            *fps_counter_visibility = Visibility::Hidden;
+        }
+    }
+}
+
+pub fn vsync_present_mode(vsync: bool) -> PresentMode {
+    if vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    }
+}
+
+fn setup_fps_counter(
+    mut commands: Commands
+) {
+    commands.spawn((
+        TextBundle{
+            text: Text::from_section(
+                "FPS: 0.0",
+                TextStyle {
+                    font: default(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                }
+            ),
+            style: Style {
+                right: Val::Px(0.0),
+                bottom: Val::Px(0.0),
+                position_type: PositionType::Absolute,
+               ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        FpsCounter
+    ));
+}
+
+fn measure_fps(
+    mut fps_query: Query<&mut Text, With<FpsCounter>>,
+    diagnostics: Res<DiagnosticsStore>
+) {
+    if let Some(fps) = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed()) {
+        if let Ok(mut fps_text) = fps_query.get_single_mut() {
+            fps_text.sections[0].value = format!("FPS: {}", fps);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{log::LogPlugin, render::{settings::{RenderCreation, WgpuSettings}, RenderPlugin}, window::ExitCondition, winit::WinitPlugin};
+    use bevy_inspector_egui::bevy_egui::EguiPlugin;
+    use std::sync::{mpsc, Mutex};
+    use crate::{config::components::ConfigFile, http_api::{components::{HttpApiRequest, HttpApiResponse, HttpApiServer}, HttpApiPlugin}, visualizer::components::AudioVisualizerContainer};
+    use super::*;
+
+    fn spawn_embedded_audio_visualizer(
+        mut audio_visualizer_spawn_event_writer: EventWriter<AudioVisualizerSpawnEvent>
+    ) {
+        audio_visualizer_spawn_event_writer.send(AudioVisualizerSpawnEvent(AudioVisualizerSettings { radius: 300.0, ..default() }));
+    }
+
+    fn embedded_app(audio_visualizer_plugins: PluginGroupBuilder) -> App {
+        let mut app = App::new();
+        app
+            // Keeps the tests away from the user's settings file and audio devices
+            .insert_resource(ConfigFile { path: None, ..default() })
+            .insert_resource(AudioCaptureSettings { enabled: false, ..default() })
+            .add_plugins(DefaultPlugins
+                .set(WindowPlugin { primary_window: None, exit_condition: ExitCondition::DontExit, ..default() })
+                .set(RenderPlugin { render_creation: RenderCreation::Automatic(WgpuSettings { backends: None, ..default() }), ..default() })
+                .disable::<WinitPlugin>()
+                .disable::<LogPlugin>())
+            .add_plugins(audio_visualizer_plugins)
+            .add_systems(Startup, spawn_embedded_audio_visualizer);
+        app
+    }
+
+    fn audio_visualizer_radii(app: &mut App) -> Vec<f32> {
+        app.world.query_filtered::<&AudioVisualizerSettings, With<AudioVisualizerContainer>>().iter(&app.world).map(|audio_visualizer_settings| audio_visualizer_settings.radius).collect()
+    }
+
+    #[test]
+    fn embedded_app_without_ui_spawns_only_the_requested_visualizer() {
+        let mut app = embedded_app(AudioVisualizerPlugins.build().disable::<UiPlugin>());
+        for _ in 0..3 {
+            app.update();
+        }
+
+        assert_eq!(audio_visualizer_radii(&mut app), vec![300.0]);
+    }
+
+    #[test]
+    fn embedded_app_runs_with_every_optional_plugin_disabled() {
+        let mut app = embedded_app(AudioVisualizerPlugins.build().disable::<UiPlugin>().disable::<ConfigPlugin>().disable::<KeyBindingPlugin>());
+        for _ in 0..3 {
+            app.update();
+        }
+
+        assert_eq!(audio_visualizer_radii(&mut app), vec![300.0]);
+    }
+
+    #[test]
+    fn embedded_app_without_ui_can_add_egui_itself() {
+        let mut app = embedded_app(AudioVisualizerPlugins.build().disable::<UiPlugin>());
+        app.add_plugins(EguiPlugin);
+        app.update();
+
+        assert_eq!(audio_visualizer_radii(&mut app), vec![300.0]);
+    }
+
+    fn http_api_request(app: &mut App, request_sender: &mpsc::Sender<HttpApiRequest>, method: &str, path: &str, body: &str) -> HttpApiResponse {
+        let (response_sender, response_receiver) = mpsc::channel();
+        request_sender.send(HttpApiRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.as_bytes().to_vec(),
+            audio_devices: None,
+            response_sender
+        }).unwrap();
+        app.update();
+        app.update();
+        response_receiver.try_recv().unwrap()
+    }
+
+    #[test]
+    fn http_api_changes_adds_and_removes_visualizers() {
+        let mut app = embedded_app(AudioVisualizerPlugins.build().disable::<UiPlugin>());
+        let (request_sender, request_receiver) = mpsc::channel();
+        app
+            .add_plugins(HttpApiPlugin)
+            .insert_resource(HttpApiServer { request_receiver: Mutex::new(request_receiver) });
+        app.update();
+        assert_eq!(audio_visualizer_radii(&mut app), vec![300.0]);
+
+        let response = http_api_request(&mut app, &request_sender, "PUT", "/settings", r#"{"visualizers": [{"radius": 120.0}, {"radius": 50.0}]}"#);
+        assert_eq!(response.status, 200);
+        assert_eq!(audio_visualizer_radii(&mut app), vec![120.0, 50.0]);
+
+        let response = http_api_request(&mut app, &request_sender, "DELETE", "/visualizers/0", "");
+        assert_eq!(response.status, 200);
+        assert_eq!(audio_visualizer_radii(&mut app), vec![50.0]);
+
+        let response = http_api_request(&mut app, &request_sender, "DELETE", "/visualizers/1", "");
+        assert_eq!(response.status, 404);
+        assert_eq!(audio_visualizer_radii(&mut app), vec![50.0]);
+    }
+
+    #[test]
+    fn host_window_present_mode_is_kept_until_vsync_is_changed() {
+        let mut app = embedded_app(AudioVisualizerPlugins.build().disable::<UiPlugin>());
+        let window = app.world.spawn((Window { present_mode: PresentMode::Mailbox, ..default() }, PrimaryWindow)).id();
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(app.world.get::<Window>(window).unwrap().present_mode, PresentMode::Mailbox);
+
+        app.world.resource_mut::<AdvancedSettings>().vsync = false;
+        app.update();
+        assert_eq!(app.world.get::<Window>(window).unwrap().present_mode, PresentMode::AutoNoVsync);
+    }
+}
@@ -1,74 +1,152 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use bevy::{core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping}, prelude::*, window::PresentMode};
-use bevy_inspector_egui::bevy_egui::EguiPlugin;
-use bevy::diagnostic::DiagnosticsStore;
-use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
-use visualizer::AudioVisualizerPlugin;
+use bevy::{app::ScheduleRunnerPlugin, core_pipeline::{bloom::BloomSettings, tonemapping::Tonemapping}, log::LogPlugin, prelude::*, render::pipelined_rendering::PipelinedRenderingPlugin, window::{PrimaryWindow, WindowMode, WindowPosition, WindowResolution}};
+use clap::Parser;
+use customizable_bevy_audio_visualizer::{cli::CommandLineArgs, headless::{components::HeadlessAnalysisSettings, HeadlessAnalysisPlugin}, http_api::{components::HttpApiServerSettings, HttpApiPlugin}, offline_render::{components::OfflineRenderSettings, OfflineRenderPlugin}, ui::components::UiState, visualizer::components::AudioVisualizerUpdateTimer, websocket::{components::WebSocketServerSettings, WebSocketPlugin}, AdvancedSettings, AudioAnalysisPlugin, AudioCaptureSettings, AudioDataPlugin, AudioVisualizerPlugins, OscPlugin, PresetEvent, vsync_present_mode};
+use std::{path::Path, time::{Duration, Instant}};
 
-mod visualizer;
-mod audio_data;
-mod ui;
-
-use audio_data::AudioDataPlugin;
-use ui::UiPlugin;
+// The options only the application itself reads, the plugins get their own settings resources
+#[derive(Resource, Deref)]
+struct CommandLine(CommandLineArgs);
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_plugins(EguiPlugin)
-        .add_plugins(FrameTimeDiagnosticsPlugin::default())
-        .add_plugins(UiPlugin)
-        .add_plugins(AudioDataPlugin)
-        .add_plugins(AudioVisualizerPlugin)
-        .init_resource::<AdvancedSettings>()
-        .add_event::<AdvancedSettingsChangeEvent>()
-        .add_systems(Startup, (setup_camera, setup_fps_counter))
-        .add_systems(Update, measure_fps.run_if(|advanced_settings: Res<AdvancedSettings>| advanced_settings.show_fps))
-        .add_systems(Update, update_advanced_settings.run_if(on_event::<AdvancedSettingsChangeEvent>()))
-        .run();
+    let command_line_args = CommandLineArgs::parse();
+
+    if command_line_args.headless {
+        let mut app = App::new();
+        app
+            .insert_resource(audio_capture_settings(&command_line_args))
+            .insert_resource(HeadlessAnalysisSettings {
+                format: command_line_args.analysis_format,
+                output_path: command_line_args.analysis_output.clone(),
+                preset: command_line_args.preset.clone()
+            })
+            .add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(AudioVisualizerUpdateTimer::default().timer.duration())))
+            .add_plugins(LogPlugin::default())
+            .add_plugins(AudioDataPlugin)
+            .add_plugins(AudioAnalysisPlugin)
+            .add_plugins(OscPlugin)
+            .add_plugins(HeadlessAnalysisPlugin);
+
+        if let Some(websocket_server_settings) = websocket_server_settings(&command_line_args) {
+            app.insert_resource(websocket_server_settings).add_plugins(WebSocketPlugin);
+        }
+
+        app.run();
+        return;
+    }
+
+    let mut window = Window::default();
+    if command_line_args.width.is_some() || command_line_args.height.is_some() {
+        window.resolution = WindowResolution::new(
+            command_line_args.width.unwrap_or(window.resolution.width()),
+            command_line_args.height.unwrap_or(window.resolution.height())
+        );
+    }
+    if let (Some(x), Some(y)) = (command_line_args.x, command_line_args.y) {
+        window.position = WindowPosition::At(IVec2::new(x, y));
+    }
+    if command_line_args.fullscreen {
+        window.mode = WindowMode::BorderlessFullscreen;
+    }
+
+    let mut default_plugins = DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(window),
+        ..default()
+    });
+    if command_line_args.render.is_some() {
+        default_plugins = default_plugins.disable::<PipelinedRenderingPlugin>();
+    }
+
+    let mut app = App::new();
+    app
+        .insert_resource(audio_capture_settings(&command_line_args))
+        .insert_resource(CommandLine(command_line_args.clone()))
+        .add_plugins(default_plugins)
+        .add_plugins(AudioVisualizerPlugins)
+        .add_systems(Startup, (setup_camera, apply_command_line_args))
+        .add_systems(Last, limit_frame_rate.run_if(|command_line_args: Res<CommandLine>| command_line_args.fps_cap.is_some()));
+
+    if let Some(audio_path) = &command_line_args.render {
+        app.insert_resource(OfflineRenderSettings {
+            audio_path: audio_path.clone(),
+            output_directory: command_line_args.render_output.clone(),
+            fps: command_line_args.render_fps,
+            snapshots: command_line_args.snapshot.clone(),
+            video_path: command_line_args.render_video.clone(),
+            encoder_command: command_line_args.encoder_command.clone()
+        }).add_plugins(OfflineRenderPlugin);
+    }
+    if let Some(websocket_server_settings) = websocket_server_settings(&command_line_args) {
+        app.insert_resource(websocket_server_settings).add_plugins(WebSocketPlugin);
+    }
+    if let Some(address) = &command_line_args.http_api {
+        app.insert_resource(HttpApiServerSettings { address: address.clone() }).add_plugins(HttpApiPlugin);
+    }
+
+    app.run();
 }
 
-#[derive(Component)]
-struct FpsCounter;
+fn audio_capture_settings(
+    command_line_args: &CommandLineArgs
+) -> AudioCaptureSettings {
+    // Offline renders read the audio from the file instead of capturing it
+    AudioCaptureSettings {
+        enabled: command_line_args.render.is_none(),
+        device: command_line_args.device.clone(),
+        source: command_line_args.source
+    }
+}
 
-#[derive(Resource)]
-pub struct AdvancedSettings {
-    pub vsync: bool,
-    pub show_fps: bool
+fn websocket_server_settings(
+    command_line_args: &CommandLineArgs
+) -> Option<WebSocketServerSettings> {
+    command_line_args.websocket.as_ref().map(|address| WebSocketServerSettings {
+        address: address.clone(),
+        format: command_line_args.websocket_format
+    })
 }
 
-impl Default for AdvancedSettings {
-    fn default() -> Self {
-        AdvancedSettings {
-            vsync: true,
-            show_fps: false
+fn apply_command_line_args(
+    command_line_args: Res<CommandLine>,
+    mut preset_event_writer: EventWriter<PresetEvent>,
+    mut advanced_settings: ResMut<AdvancedSettings>,
+    mut ui_state: ResMut<UiState>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>
+) {
+    if let Some(vsync) = command_line_args.vsync {
+        advanced_settings.vsync = vsync;
+    }
+    // Runs after the settings file is loaded, later changes are applied by the advanced settings plugin
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.present_mode = vsync_present_mode(advanced_settings.vsync && command_line_args.render.is_none());
+    }
+
+    if command_line_args.hide_ui || command_line_args.render.is_some() {
+        ui_state.visible = false;
+    }
+
+    if let Some(preset) = &command_line_args.preset {
+        if Path::new(preset).is_file() {
+            preset_event_writer.send(PresetEvent::LoadFile(preset.into()));
+        } else {
+            preset_event_writer.send(PresetEvent::Load(preset.clone()));
         }
     }
 }
 
-#[derive(Event)]
-pub struct AdvancedSettingsChangeEvent;
-
-fn update_advanced_settings(
-    mut fps_counter_query: Query<&mut Visibility, With<FpsCounter>>,
-    mut window_query: Query<&mut Window>,
-    advanced_settings: Res<AdvancedSettings>
+fn limit_frame_rate(
+    command_line_args: Res<CommandLine>,
+    mut last_frame_end: Local<Option<Instant>>
 ) {
-    let mut window = window_query.single_mut();
-    window.present_mode = if advanced_settings.vsync {
-        PresentMode::AutoVsync
-    } else {
-        PresentMode::AutoNoVsync
-    };
-
-    let mut fps_counter_visibility = fps_counter_query.get_single_mut().unwrap();
-
-    if advanced_settings.show_fps {
-        *fps_counter_visibility = Visibility::Visible;
-    } else {
-        *fps_counter_visibility = Visibility::Hidden;
+    let frame_duration = Duration::from_secs_f64(1.0 / command_line_args.fps_cap.unwrap().max(1.0));
+    if let Some(last_frame_end) = *last_frame_end {
+        let elapsed = last_frame_end.elapsed();
+        if elapsed < frame_duration {
+            std::thread::sleep(frame_duration - elapsed);
+        }
     }
+    *last_frame_end = Some(Instant::now());
 }
 
 fn setup_camera(
@@ -86,41 +164,3 @@ fn setup_camera(
         BloomSettings::default()
     ));
 }
-
-fn setup_fps_counter(
-    mut commands: Commands
-) {
-    commands.spawn((
-        TextBundle{
-            text: Text::from_section(
-                "FPS: 0.0",
-                TextStyle {
-                    font: default(),
-                    font_size: 20.0,
-                    color: Color::WHITE,
-                }
-            ),
-            style: Style {
-                right: Val::Px(0.0),
-                bottom: Val::Px(0.0),
-                position_type: PositionType::Absolute,
-               ..default()
-            },
-            visibility: Visibility::Hidden,
-            ..default()
-        },
-        FpsCounter
-    ));
-}
-
-fn measure_fps(
-    mut fps_query: Query<&mut Text, With<FpsCounter>>,
-    diagnostics: Res<DiagnosticsStore>
-) {
-    if let Some(fps) = diagnostics
-        .get(&FrameTimeDiagnosticsPlugin::FPS)
-        .and_then(|fps| fps.smoothed()) {
-        let mut fps_text = fps_query.get_single_mut().unwrap();
-        fps_text.sections[0].value = format!("FPS: {}", fps);
-    }
-}
\ No newline at end of file
@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use crate::{midi::MIDI_MAX_VALUE, visualizer::{components::AudioVisualizerSettings, COLUMN_BASE_INTENSITY_RANGE, COLUMN_GLOW_STRENGTH_RANGE, COLUMN_WIDTH_RANGE, MAX_HEIGHT_RANGE, RADIUS_RANGE, ROTATION_SPEED_RANGE, SCALE_STRENGTH_RANGE, SCALE_THRESHOLD_RANGE}};
+use super::sequencer::MidiSequencer;
+
+#[derive(Resource, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiSettings {
+    pub enabled: bool,
+    pub control_bindings: Vec<MidiControlBinding>,
+    pub note_bindings: Vec<MidiNoteBinding>
+}
+
+impl MidiSettings {
+    pub fn bind_control(&mut self, control_binding: MidiControlBinding) {
+        self.control_bindings.retain(|binding| binding.target != control_binding.target && (binding.channel, binding.controller) != (control_binding.channel, control_binding.controller));
+        self.control_bindings.push(control_binding);
+    }
+
+    pub fn bind_note(&mut self, note_binding: MidiNoteBinding) {
+        self.note_bindings.retain(|binding| (binding.channel, binding.note) != (note_binding.channel, note_binding.note));
+        self.note_bindings.push(note_binding);
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MidiControlBinding {
+    pub channel: u8,
+    pub controller: u8,
+    pub target: MidiControlTarget
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MidiNoteBinding {
+    pub channel: u8,
+    pub note: u8,
+    pub preset: String
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum MidiControlTarget {
+    Radius,
+    MaxHeight,
+    ColumnWidth,
+    RotationSpeed,
+    ScaleStrength,
+    ScaleThreshold,
+    GlowStrength,
+    BaseIntensity
+}
+
+impl MidiControlTarget {
+    pub const ALL: [MidiControlTarget; 8] = [
+        MidiControlTarget::Radius,
+        MidiControlTarget::MaxHeight,
+        MidiControlTarget::ColumnWidth,
+        MidiControlTarget::RotationSpeed,
+        MidiControlTarget::ScaleStrength,
+        MidiControlTarget::ScaleThreshold,
+        MidiControlTarget::GlowStrength,
+        MidiControlTarget::BaseIntensity
+    ];
+
+    pub fn range(&self) -> RangeInclusive<f32> {
+        match self {
+            MidiControlTarget::Radius => RADIUS_RANGE,
+            MidiControlTarget::MaxHeight => MAX_HEIGHT_RANGE,
+            MidiControlTarget::ColumnWidth => COLUMN_WIDTH_RANGE,
+            MidiControlTarget::RotationSpeed => ROTATION_SPEED_RANGE,
+            MidiControlTarget::ScaleStrength => SCALE_STRENGTH_RANGE,
+            MidiControlTarget::ScaleThreshold => SCALE_THRESHOLD_RANGE,
+            MidiControlTarget::GlowStrength => COLUMN_GLOW_STRENGTH_RANGE,
+            MidiControlTarget::BaseIntensity => COLUMN_BASE_INTENSITY_RANGE
+        }
+    }
+
+    pub fn scale(&self, value: u8) -> f32 {
+        let range = self.range();
+        range.start() + value.min(MIDI_MAX_VALUE) as f32 / MIDI_MAX_VALUE as f32 * (range.end() - range.start())
+    }
+
+    pub fn value_mut<'a>(&self, audio_visualizer_settings: &'a mut AudioVisualizerSettings) -> &'a mut f32 {
+        match self {
+            MidiControlTarget::Radius => &mut audio_visualizer_settings.radius,
+            MidiControlTarget::MaxHeight => &mut audio_visualizer_settings.max_height,
+            MidiControlTarget::ColumnWidth => &mut audio_visualizer_settings.column_width,
+            MidiControlTarget::RotationSpeed => &mut audio_visualizer_settings.rotation_speed,
+            MidiControlTarget::ScaleStrength => &mut audio_visualizer_settings.scale_strenght,
+            MidiControlTarget::ScaleThreshold => &mut audio_visualizer_settings.scale_threshold,
+            MidiControlTarget::GlowStrength => &mut audio_visualizer_settings.column_glow_strength,
+            MidiControlTarget::BaseIntensity => &mut audio_visualizer_settings.column_base_intensity
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MidiMessage {
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8
+    },
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum MidiLearnTarget {
+    Control(MidiControlTarget),
+    Note(String)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum MidiAction {
+    BindControl(MidiControlBinding),
+    BindNote(MidiNoteBinding),
+    SetControl {
+        target: MidiControlTarget,
+        value: f32
+    },
+    LoadPreset(String)
+}
+
+#[derive(Resource, Default)]
+pub struct MidiLearnState {
+    pub learning: Option<MidiLearnTarget>,
+    pub note_preset: Option<String>
+}
+
+#[derive(Default)]
+pub struct MidiReceiver {
+    pub sequencer: Option<MidiSequencer>
+}
+
+#[derive(Resource, Default)]
+pub struct MidiStatus {
+    pub connected_ports: Vec<String>,
+    pub error: Option<String>
+}
@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+use crate::visualizer::AudioVisualizerSpawnSet;
+
+pub mod components;
+pub mod sequencer;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct MidiPlugin;
+
+impl Plugin for MidiPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<MidiSettings>()
+        .init_resource::<MidiStatus>()
+        .init_resource::<MidiLearnState>()
+        .init_non_send_resource::<MidiReceiver>()
+        .add_systems(Update, (
+            update_midi_receiver.run_if(resource_changed::<MidiSettings>),
+            // Run conditions are evaluated off the main thread, so they can't read the non-send receiver
+            receive_midi_input.run_if(|midi_settings: Res<MidiSettings>| midi_settings.enabled)
+        ).chain().before(AudioVisualizerSpawnSet));
+    }
+}
+
+pub const MIDI_CLIENT_NAME: &str = "Customizable Bevy Audio Visualizer";
+pub const MIDI_PORT_NAME: &str = "MIDI In";
+pub const MIDI_MAX_VALUE: u8 = 127;
@@ -0,0 +1,94 @@
+use crate::midi::components::MidiMessage;
+
+#[cfg(target_os = "linux")]
+pub struct MidiSequencer {
+    seq: alsa::seq::Seq
+}
+
+#[cfg(target_os = "linux")]
+impl MidiSequencer {
+    // Opens an ALSA sequencer port and connects every readable port to it, more can be connected with `aconnect`
+    pub fn open() -> Result<(Self, Vec<String>), String> {
+        use alsa::seq::{Addr, ClientIter, PortCap, PortIter, PortSubscribe, PortType, Seq};
+        use std::ffi::CString;
+        use crate::midi::*;
+
+        let seq = Seq::open(None, Some(alsa::Direction::Capture), true).map_err(|error| error.to_string())?;
+        seq.set_client_name(&CString::new(MIDI_CLIENT_NAME).unwrap()).map_err(|error| error.to_string())?;
+        let port = seq.create_simple_port(
+            &CString::new(MIDI_PORT_NAME).unwrap(),
+            PortCap::WRITE | PortCap::SUBS_WRITE,
+            PortType::MIDI_GENERIC | PortType::APPLICATION
+        ).map_err(|error| error.to_string())?;
+        let client = seq.client_id().map_err(|error| error.to_string())?;
+
+        let mut connected_ports = Vec::new();
+        for client_info in ClientIter::new(&seq) {
+            // Client 0 is the ALSA system client with the timer and announcement ports
+            if client_info.get_client() == 0 || client_info.get_client() == client {
+                continue;
+            }
+
+            for port_info in PortIter::new(&seq, client_info.get_client()) {
+                let capability = port_info.get_capability();
+                if !capability.contains(PortCap::READ | PortCap::SUBS_READ) || capability.contains(PortCap::NO_EXPORT) {
+                    continue;
+                }
+
+                let port_name = format!("{}: {}", client_info.get_name().unwrap_or("Unknown"), port_info.get_name().unwrap_or("Unknown"));
+                let result = PortSubscribe::empty().and_then(|port_subscribe| {
+                    port_subscribe.set_sender(port_info.addr());
+                    port_subscribe.set_dest(Addr { client, port });
+                    seq.subscribe_port(&port_subscribe)
+                });
+                match result {
+                    Ok(()) => connected_ports.push(port_name),
+                    Err(error) => bevy::log::warn!("Failed to connect MIDI port {}: {}", port_name, error)
+                }
+            }
+        }
+
+        Ok((Self { seq }, connected_ports))
+    }
+
+    pub fn read_messages(&self, messages: &mut Vec<MidiMessage>) {
+        use alsa::seq::{EvCtrl, EvNote, EventType};
+
+        let mut input = self.seq.input();
+        while input.event_input_pending(true).is_ok_and(|pending| pending > 0) {
+            let Ok(event) = input.event_input() else {
+                break;
+            };
+
+            match event.get_type() {
+                EventType::Controller => if let Some(control) = event.get_data::<EvCtrl>() {
+                    messages.push(MidiMessage::ControlChange {
+                        channel: control.channel,
+                        controller: control.param as u8,
+                        value: control.value.clamp(0, 127) as u8
+                    });
+                },
+                EventType::Noteon => if let Some(note) = event.get_data::<EvNote>() {
+                    messages.push(MidiMessage::NoteOn {
+                        channel: note.channel,
+                        note: note.note,
+                        velocity: note.velocity
+                    });
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub struct MidiSequencer;
+
+#[cfg(not(target_os = "linux"))]
+impl MidiSequencer {
+    pub fn open() -> Result<(Self, Vec<String>), String> {
+        Err("MIDI input is only supported on Linux".to_string())
+    }
+
+    pub fn read_messages(&self, _messages: &mut Vec<MidiMessage>) {}
+}
@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use crate::{midi::*, presets::components::{PresetEvent, PresetLibrary}, visualizer::components::{AudioVisualizerContainer, AudioVisualizerSettings, AudioVisualizerTransition, SelectedAudioVisualizer}};
+use super::{components::*, sequencer::MidiSequencer};
+
+pub fn update_midi_receiver(
+    midi_settings: Res<MidiSettings>,
+    mut midi_receiver: NonSendMut<MidiReceiver>,
+    mut midi_status: ResMut<MidiStatus>
+) {
+    // Learned bindings also change the settings, the sequencer is only reopened when MIDI is toggled
+    if !midi_settings.enabled {
+        *midi_receiver = MidiReceiver::default();
+        *midi_status = MidiStatus::default();
+        return;
+    }
+    if midi_receiver.sequencer.is_some() {
+        return;
+    }
+
+    match MidiSequencer::open() {
+        Ok((sequencer, connected_ports)) => {
+            info!("Listening for MIDI on `{}` (connected: {})", MIDI_PORT_NAME, connected_ports.join(", "));
+            midi_receiver.sequencer = Some(sequencer);
+            *midi_status = MidiStatus {
+                connected_ports,
+                error: None
+            };
+        },
+        Err(error) => {
+            error!("Failed to open MIDI input: {}", error);
+            midi_status.error = Some(error);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn receive_midi_input(
+    midi_receiver: NonSend<MidiReceiver>,
+    mut midi_settings: ResMut<MidiSettings>,
+    mut midi_learn_state: ResMut<MidiLearnState>,
+    mut audio_visualizer_container_query: Query<(&mut AudioVisualizerSettings, Option<&mut AudioVisualizerTransition>), With<AudioVisualizerContainer>>,
+    mut preset_event_writer: EventWriter<PresetEvent>,
+    selected_audio_visualizer: Res<SelectedAudioVisualizer>,
+    preset_library: Res<PresetLibrary>,
+    mut messages: Local<Vec<MidiMessage>>
+) {
+    let Some(sequencer) = &midi_receiver.sequencer else {
+        return;
+    };
+    messages.clear();
+    sequencer.read_messages(&mut messages);
+
+    for message in messages.iter().copied() {
+        match midi_action(message, &midi_settings, &midi_learn_state) {
+            Some(MidiAction::BindControl(control_binding)) => {
+                info!("Bound MIDI controller {} on channel {} to {:?}", control_binding.controller, control_binding.channel + 1, control_binding.target);
+                midi_settings.bind_control(control_binding);
+                midi_learn_state.learning = None;
+            },
+            Some(MidiAction::BindNote(note_binding)) => {
+                info!("Bound MIDI note {} on channel {} to preset {}", note_binding.note, note_binding.channel + 1, note_binding.preset);
+                midi_settings.bind_note(note_binding);
+                midi_learn_state.learning = None;
+            },
+            Some(MidiAction::SetControl { target, value }) => {
+                let Some(Ok((audio_visualizer_settings, audio_visualizer_transition))) = selected_audio_visualizer.0.map(|entity| audio_visualizer_container_query.get_mut(entity)) else {
+                    continue;
+                };
+
+                // Changes during a transition go to its target so they aren't overwritten by the interpolation
+                let audio_visualizer_settings = match audio_visualizer_transition {
+                    Some(audio_visualizer_transition) => &mut audio_visualizer_transition.into_inner().to,
+                    None => audio_visualizer_settings.into_inner()
+                };
+                *target.value_mut(audio_visualizer_settings) = value;
+            },
+            Some(MidiAction::LoadPreset(preset)) => {
+                if preset_library.get(&preset).is_none() {
+                    warn!("Ignoring MIDI note: preset {} does not exist", preset);
+                    continue;
+                }
+                preset_event_writer.send(PresetEvent::Load(preset));
+            },
+            None => {}
+        }
+    }
+}
+
+fn midi_action(
+    message: MidiMessage,
+    midi_settings: &MidiSettings,
+    midi_learn_state: &MidiLearnState
+) -> Option<MidiAction> {
+    match (message, &midi_learn_state.learning) {
+        (MidiMessage::ControlChange { channel, controller, .. }, Some(MidiLearnTarget::Control(target))) => {
+            Some(MidiAction::BindControl(MidiControlBinding { channel, controller, target: *target }))
+        },
+        (MidiMessage::NoteOn { channel, note, velocity }, Some(MidiLearnTarget::Note(preset))) if velocity > 0 => {
+            Some(MidiAction::BindNote(MidiNoteBinding { channel, note, preset: preset.clone() }))
+        },
+        (MidiMessage::ControlChange { channel, controller, value }, _) => {
+            midi_settings.control_bindings.iter()
+                .find(|binding| (binding.channel, binding.controller) == (channel, controller))
+                .map(|binding| MidiAction::SetControl { target: binding.target, value: binding.target.scale(value) })
+        },
+        (MidiMessage::NoteOn { channel, note, velocity }, _) if velocity > 0 => {
+            midi_settings.note_bindings.iter()
+                .find(|binding| (binding.channel, binding.note) == (channel, note))
+                .map(|binding| MidiAction::LoadPreset(binding.preset.clone()))
+        },
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visualizer::{RADIUS_RANGE, ROTATION_SPEED_RANGE};
+
+    fn control_change(channel: u8, controller: u8, value: u8) -> MidiMessage {
+        MidiMessage::ControlChange { channel, controller, value }
+    }
+
+    fn note_on(channel: u8, note: u8, velocity: u8) -> MidiMessage {
+        MidiMessage::NoteOn { channel, note, velocity }
+    }
+
+    fn learning(midi_learn_target: MidiLearnTarget) -> MidiLearnState {
+        MidiLearnState { learning: Some(midi_learn_target), ..default() }
+    }
+
+    #[test]
+    fn control_values_are_scaled_to_the_target_range() {
+        for target in MidiControlTarget::ALL {
+            assert_eq!(target.scale(0), *target.range().start(), "{:?}", target);
+            assert_eq!(target.scale(MIDI_MAX_VALUE), *target.range().end(), "{:?}", target);
+            assert_eq!(target.scale(255), *target.range().end(), "{:?}", target);
+        }
+        assert!((MidiControlTarget::RotationSpeed.scale(64) - 0.5 / 127.0).abs() < 1e-6);
+        assert!((MidiControlTarget::Radius.scale(32) - 32.0 / 127.0 * RADIUS_RANGE.end()).abs() < 1e-3);
+        assert_eq!(*ROTATION_SPEED_RANGE.start(), MidiControlTarget::RotationSpeed.scale(0));
+    }
+
+    #[test]
+    fn bound_controller_sets_its_target() {
+        let midi_settings = MidiSettings {
+            control_bindings: vec![MidiControlBinding { channel: 0, controller: 7, target: MidiControlTarget::Radius }],
+            ..default()
+        };
+
+        assert_eq!(midi_action(control_change(0, 7, 127), &midi_settings, &MidiLearnState::default()), Some(MidiAction::SetControl { target: MidiControlTarget::Radius, value: *RADIUS_RANGE.end() }));
+        assert_eq!(midi_action(control_change(1, 7, 127), &midi_settings, &MidiLearnState::default()), None);
+        assert_eq!(midi_action(control_change(0, 8, 127), &midi_settings, &MidiLearnState::default()), None);
+    }
+
+    #[test]
+    fn bound_note_loads_its_preset() {
+        let midi_settings = MidiSettings {
+            note_bindings: vec![MidiNoteBinding { channel: 9, note: 36, preset: "Pulse".to_string() }],
+            ..default()
+        };
+
+        assert_eq!(midi_action(note_on(9, 36, 100), &midi_settings, &MidiLearnState::default()), Some(MidiAction::LoadPreset("Pulse".to_string())));
+        assert_eq!(midi_action(note_on(9, 36, 0), &midi_settings, &MidiLearnState::default()), None);
+        assert_eq!(midi_action(note_on(9, 37, 100), &midi_settings, &MidiLearnState::default()), None);
+    }
+
+    #[test]
+    fn learn_mode_binds_the_next_matching_message() {
+        let midi_settings = MidiSettings::default();
+        let control_learn_state = learning(MidiLearnTarget::Control(MidiControlTarget::MaxHeight));
+        let note_learn_state = learning(MidiLearnTarget::Note("Pulse".to_string()));
+
+        assert_eq!(midi_action(note_on(0, 60, 100), &midi_settings, &control_learn_state), None);
+        assert_eq!(midi_action(control_change(2, 21, 5), &midi_settings, &control_learn_state), Some(MidiAction::BindControl(MidiControlBinding { channel: 2, controller: 21, target: MidiControlTarget::MaxHeight })));
+
+        assert_eq!(midi_action(note_on(0, 60, 0), &midi_settings, &note_learn_state), None);
+        assert_eq!(midi_action(note_on(0, 60, 100), &midi_settings, &note_learn_state), Some(MidiAction::BindNote(MidiNoteBinding { channel: 0, note: 60, preset: "Pulse".to_string() })));
+    }
+
+    #[test]
+    fn learning_a_control_replaces_conflicting_bindings() {
+        let mut midi_settings = MidiSettings {
+            control_bindings: vec![
+                MidiControlBinding { channel: 0, controller: 1, target: MidiControlTarget::Radius },
+                MidiControlBinding { channel: 0, controller: 2, target: MidiControlTarget::MaxHeight },
+                MidiControlBinding { channel: 0, controller: 3, target: MidiControlTarget::ColumnWidth }
+            ],
+            ..default()
+        };
+
+        midi_settings.bind_control(MidiControlBinding { channel: 0, controller: 2, target: MidiControlTarget::Radius });
+
+        assert_eq!(midi_settings.control_bindings, vec![
+            MidiControlBinding { channel: 0, controller: 3, target: MidiControlTarget::ColumnWidth },
+            MidiControlBinding { channel: 0, controller: 2, target: MidiControlTarget::Radius }
+        ]);
+    }
+
+    #[test]
+    fn learning_a_note_replaces_its_previous_preset() {
+        let mut midi_settings = MidiSettings::default();
+        midi_settings.bind_note(MidiNoteBinding { channel: 0, note: 60, preset: "Pulse".to_string() });
+        midi_settings.bind_note(MidiNoteBinding { channel: 1, note: 60, preset: "Pulse".to_string() });
+        midi_settings.bind_note(MidiNoteBinding { channel: 0, note: 60, preset: "Calm".to_string() });
+
+        assert_eq!(midi_settings.note_bindings, vec![
+            MidiNoteBinding { channel: 1, note: 60, preset: "Pulse".to_string() },
+            MidiNoteBinding { channel: 0, note: 60, preset: "Calm".to_string() }
+        ]);
+    }
+}
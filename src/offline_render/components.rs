@@ -0,0 +1,73 @@
+use bevy::{prelude::*, render::{extract_resource::ExtractResource, render_graph::RenderLabel, render_resource::Buffer}};
+use std::{path::{Path, PathBuf}, process::Child, sync::{atomic::AtomicBool, mpsc::{Receiver, Sender}, Arc, Mutex}, time::Duration};
+use crate::audio_data::wav::AudioFile;
+
+#[derive(Resource, Clone)]
+pub struct OfflineRenderSettings {
+    pub audio_path: PathBuf,
+    pub output_directory: PathBuf,
+    pub fps: f64,
+    pub snapshots: Vec<f64>,
+    pub video_path: Option<PathBuf>,
+    pub encoder_command: String
+}
+
+#[derive(Resource)]
+pub struct OfflineRender {
+    pub audio_file: AudioFile,
+    pub frame_duration: Duration,
+    pub frame_count: u32,
+    pub output: OfflineRenderOutput,
+    pub state: OfflineRenderState,
+    pub rendered_frame_receiver: Mutex<Receiver<RenderedFrame>>
+}
+
+pub enum OfflineRenderOutput {
+    ImageSequence(PathBuf),
+    Snapshots {
+        directory: PathBuf,
+        frames: Vec<u32>
+    },
+    Encoder {
+        path: PathBuf,
+        process: Child
+    }
+}
+
+impl OfflineRenderOutput {
+    pub fn path(&self) -> &Path {
+        match self {
+            OfflineRenderOutput::ImageSequence(directory) => directory,
+            OfflineRenderOutput::Snapshots { directory, .. } => directory,
+            OfflineRenderOutput::Encoder { path, .. } => path
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OfflineRenderState {
+    WarmingUp,
+    Starting,
+    Rendering(u32)
+}
+
+#[derive(Resource, ExtractResource, Clone)]
+pub struct OfflineRenderCopy {
+    pub image: Handle<Image>,
+    pub buffer: Buffer,
+    pub size: UVec2,
+    pub padded_bytes_per_row: u32,
+    pub frame: Option<u32>,
+    pub copied: Arc<AtomicBool>,
+    pub rendered_frame_sender: Sender<RenderedFrame>
+}
+
+pub struct RenderedFrame {
+    pub frame: Option<u32>,
+    pub data: Vec<u8>
+}
+
+#[derive(RenderLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OfflineRenderCopyLabel;
+
+pub struct OfflineRenderCopyNode;
@@ -0,0 +1,31 @@
+use bevy::{prelude::*, render::{extract_resource::ExtractResourcePlugin, graph::CameraDriverLabel, render_graph::RenderGraph, Render, RenderApp, RenderSet}, time::TimeSystem};
+
+pub mod components;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct OfflineRenderPlugin;
+
+impl Plugin for OfflineRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_plugins(ExtractResourcePlugin::<OfflineRenderCopy>::default())
+        .add_systems(PostStartup, setup_offline_render)
+        .add_systems(First, advance_offline_render.after(TimeSystem).run_if(resource_exists::<OfflineRender>));
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.add_systems(Render, send_offline_render_frame.after(RenderSet::Render).run_if(resource_exists::<OfflineRenderCopy>));
+
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node(OfflineRenderCopyLabel, OfflineRenderCopyNode);
+        render_graph.add_node_edge(CameraDriverLabel, OfflineRenderCopyLabel);
+    }
+}
+
+pub const OFFLINE_RENDER_FRAME_FILE_PREFIX: &str = "frame_";
+pub const OFFLINE_RENDER_SNAPSHOT_FILE_PREFIX: &str = "snapshot_";
+pub const DEFAULT_ENCODER_COMMAND: &str = "ffmpeg -hide_banner -loglevel error -y -f rawvideo -pix_fmt rgba -s {width}x{height} -r {fps} -i - -i {audio} -map 0:v -map 1:a -vf pad=ceil(iw/2)*2:ceil(ih/2)*2 -c:v libx264 -pix_fmt yuv420p -c:a aac -shortest {output}";
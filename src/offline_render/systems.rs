@@ -0,0 +1,321 @@
+use bevy::{app::AppExit, prelude::*, render::{camera::RenderTarget, render_asset::{RenderAssetUsages, RenderAssets}, render_graph::{Node, NodeRunError, RenderGraphContext}, render_resource::{BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout, Maintain, MapMode, TextureDimension, TextureFormat, TextureUsages}, renderer::{RenderContext, RenderDevice}}, time::TimeUpdateStrategy, window::PrimaryWindow};
+use std::{fs, io::Write, path::Path, process::{Command, Stdio}, sync::{atomic::{AtomicBool, Ordering}, mpsc, Arc, Mutex}, time::Duration};
+use crate::{audio_data::{components::AudioData, wav::read_wav_file, SPECTRUM_DATA_LENGTH}, offline_render::*, visualizer::components::AudioVisualizerUpdateTimer};
+use super::components::*;
+
+#[allow(clippy::too_many_arguments)]
+pub fn setup_offline_render(
+    mut commands: Commands,
+    mut camera_query: Query<&mut Camera>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut audio_visualizer_update_timer: ResMut<AudioVisualizerUpdateTimer>,
+    mut app_exit_event_writer: EventWriter<AppExit>,
+    offline_render_settings: Option<Res<OfflineRenderSettings>>,
+    render_device: Res<RenderDevice>,
+    mut audio_data: NonSendMut<AudioData>
+) {
+    let Some(offline_render_settings) = offline_render_settings else {
+        return;
+    };
+    let audio_path = &offline_render_settings.audio_path;
+
+    let audio_file = match read_wav_file(audio_path) {
+        Ok(audio_file) => audio_file,
+        Err(error) => {
+            error!("Failed to read audio file {}: {}", audio_path.display(), error);
+            app_exit_event_writer.send(AppExit);
+            return;
+        }
+    };
+
+    audio_data.sample_rate = audio_file.sample_rate;
+
+    let window = window_query.single();
+    let size = UVec2::new(window.width() as u32, window.height() as u32).max(UVec2::ONE);
+    let frame_duration = Duration::from_secs_f64(1.0 / offline_render_settings.fps.max(1.0));
+
+    let mut output = match &offline_render_settings.video_path {
+        Some(video_path) => {
+            let arguments = encoder_arguments(&offline_render_settings.encoder_command, size, 1.0 / frame_duration.as_secs_f64(), audio_path, video_path);
+            let process = arguments.split_first()
+                .ok_or_else(|| "empty encoder command".to_string())
+                .and_then(|(program, arguments)| Command::new(program).args(arguments).stdin(Stdio::piped()).spawn().map_err(|error| format!("{}: {}", program, error)));
+            match process {
+                Ok(process) => OfflineRenderOutput::Encoder { path: video_path.clone(), process },
+                Err(error) => {
+                    error!("Failed to start the encoder: {}", error);
+                    app_exit_event_writer.send(AppExit);
+                    return;
+                }
+            }
+        },
+        None => {
+            if let Err(error) = fs::create_dir_all(&offline_render_settings.output_directory) {
+                error!("Failed to create output directory {}: {}", offline_render_settings.output_directory.display(), error);
+                app_exit_event_writer.send(AppExit);
+                return;
+            }
+            if offline_render_settings.snapshots.is_empty() {
+                OfflineRenderOutput::ImageSequence(offline_render_settings.output_directory.clone())
+            } else {
+                OfflineRenderOutput::Snapshots {
+                    directory: offline_render_settings.output_directory.clone(),
+                    frames: offline_render_settings.snapshots.iter().map(|time_offset| (time_offset.max(0.0) / frame_duration.as_secs_f64()).round() as u32).collect()
+                }
+            }
+        }
+    };
+    let extent = Extent3d {
+        width: size.x,
+        height: size.y,
+        depth_or_array_layers: 1
+    };
+    let mut image = Image::new_fill(extent, TextureDimension::D2, &[0, 0, 0, 255], TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    let image = images.add(image);
+
+    for mut camera in camera_query.iter_mut() {
+        camera.target = RenderTarget::Image(image.clone());
+    }
+
+    let padded_bytes_per_row = RenderDevice::align_copy_bytes_per_row(size.x as usize * 4) as u32;
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("offline_render_buffer"),
+        size: padded_bytes_per_row as u64 * size.y as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false
+    });
+
+    let mut frame_count = (audio_file.duration() / frame_duration.as_secs_f64()).ceil() as u32;
+    if let OfflineRenderOutput::Snapshots { frames, .. } = &mut output {
+        for frame in frames.iter_mut() {
+            *frame = (*frame).min(frame_count.saturating_sub(1));
+        }
+        frame_count = frames.iter().max().map_or(frame_count, |last_frame| last_frame + 1);
+    }
+    info!("Rendering {} frames of {} at {} FPS to {}", frame_count, audio_path.display(), offline_render_settings.fps, output.path().display());
+
+    // Time only starts once the first frame has made it through the render pipeline, so every render starts from the same state
+    commands.insert_resource(TimeUpdateStrategy::ManualDuration(frame_duration));
+    let max_delta = virtual_time.max_delta().max(frame_duration);
+    virtual_time.set_max_delta(max_delta);
+    virtual_time.pause();
+    audio_visualizer_update_timer.timer = Timer::new(frame_duration, TimerMode::Repeating);
+
+    let (rendered_frame_sender, rendered_frame_receiver) = mpsc::channel();
+    commands.insert_resource(OfflineRenderCopy {
+        image,
+        buffer,
+        size,
+        padded_bytes_per_row,
+        frame: None,
+        copied: Arc::new(AtomicBool::new(false)),
+        rendered_frame_sender
+    });
+    commands.insert_resource(OfflineRender {
+        audio_file,
+        frame_duration,
+        frame_count,
+        output,
+        state: OfflineRenderState::WarmingUp,
+        rendered_frame_receiver: Mutex::new(rendered_frame_receiver)
+    });
+}
+
+pub fn advance_offline_render(
+    mut offline_render: ResMut<OfflineRender>,
+    mut offline_render_copy: ResMut<OfflineRenderCopy>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut app_exit_event_writer: EventWriter<AppExit>,
+    audio_data: NonSend<AudioData>
+) {
+    let rendered_frames: Vec<RenderedFrame> = offline_render.rendered_frame_receiver.lock().unwrap().try_iter().collect();
+    for rendered_frame in rendered_frames {
+        match (offline_render.state, rendered_frame.frame) {
+            (OfflineRenderState::WarmingUp, None) => {
+                virtual_time.unpause();
+                offline_render.state = OfflineRenderState::Starting;
+            },
+            (OfflineRenderState::Rendering(frame), Some(rendered_frame_index)) if frame == rendered_frame_index => {
+                if let Err(error) = write_rendered_frame(&mut offline_render.output, frame, offline_render_copy.size, rendered_frame.data) {
+                    error!("Failed to write frame {}: {}", frame, error);
+                    app_exit_event_writer.send(AppExit);
+                    return;
+                }
+                offline_render.state = OfflineRenderState::Rendering(frame + 1);
+            },
+            _ => {}
+        }
+    }
+
+    let frame = match offline_render.state {
+        OfflineRenderState::WarmingUp => return,
+        OfflineRenderState::Starting if virtual_time.delta().is_zero() => return,
+        OfflineRenderState::Starting => 0,
+        OfflineRenderState::Rendering(frame) => frame
+    };
+    if frame >= offline_render.frame_count {
+        match finish_offline_render(&mut offline_render.output) {
+            Ok(()) => info!("Rendered {} frames to {}", offline_render.frame_count, offline_render.output.path().display()),
+            Err(error) => error!("Failed to finish {}: {}", offline_render.output.path().display(), error)
+        }
+        app_exit_event_writer.send(AppExit);
+        return;
+    }
+    offline_render.state = OfflineRenderState::Rendering(frame);
+    offline_render_copy.frame = Some(frame);
+
+    let audio_file = &offline_render.audio_file;
+    let end_sample = ((frame + 1) as f64 * offline_render.frame_duration.as_secs_f64() * audio_file.sample_rate as f64) as usize;
+    audio_data.latest_audio_data.lock().unwrap().extend((0..SPECTRUM_DATA_LENGTH).map(|i| {
+        (end_sample + i).checked_sub(SPECTRUM_DATA_LENGTH)
+            .and_then(|index| audio_file.samples.get(index))
+            .copied()
+            .unwrap_or(0.0)
+    }));
+
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.title = format!("Rendering frame {} of {}", frame + 1, offline_render.frame_count);
+    }
+}
+
+fn encoder_arguments(
+    encoder_command: &str,
+    size: UVec2,
+    fps: f64,
+    audio_path: &Path,
+    video_path: &Path
+) -> Vec<String> {
+    encoder_command.split_whitespace()
+        .map(|argument| argument
+            .replace("{width}", &size.x.to_string())
+            .replace("{height}", &size.y.to_string())
+            .replace("{fps}", &fps.to_string())
+            .replace("{audio}", &audio_path.to_string_lossy())
+            .replace("{output}", &video_path.to_string_lossy()))
+        .collect()
+}
+
+fn write_rendered_frame(
+    output: &mut OfflineRenderOutput,
+    frame: u32,
+    size: UVec2,
+    data: Vec<u8>
+) -> Result<(), String> {
+    match output {
+        OfflineRenderOutput::ImageSequence(directory) => save_png(&directory.join(format!("{}{:06}.png", OFFLINE_RENDER_FRAME_FILE_PREFIX, frame)), size, data),
+        OfflineRenderOutput::Snapshots { directory, frames } if frames.contains(&frame) => save_png(&directory.join(format!("{}{:06}.png", OFFLINE_RENDER_SNAPSHOT_FILE_PREFIX, frame)), size, data),
+        OfflineRenderOutput::Snapshots { .. } => Ok(()),
+        OfflineRenderOutput::Encoder { process, .. } => {
+            let Some(stdin) = process.stdin.as_mut() else {
+                return Err("the encoder input is closed".to_string());
+            };
+            stdin.write_all(&data).map_err(|error| format!("the encoder stopped accepting frames: {}", error))
+        }
+    }
+}
+
+fn save_png(
+    path: &Path,
+    size: UVec2,
+    data: Vec<u8>
+) -> Result<(), String> {
+    let image = Image::new(
+        Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default()
+    );
+    // The alpha channel holds brightness values of the HDR camera, so it is dropped like in window screenshots
+    image.try_into_dynamic()
+        .map_err(|error| error.to_string())
+        .and_then(|image| image.to_rgb8().save(path).map_err(|error| format!("{}: {}", path.display(), error)))
+}
+
+fn finish_offline_render(
+    output: &mut OfflineRenderOutput
+) -> Result<(), String> {
+    let OfflineRenderOutput::Encoder { process, .. } = output else {
+        return Ok(());
+    };
+
+    drop(process.stdin.take());
+    match process.wait() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("the encoder exited with {}", status)),
+        Err(error) => Err(error.to_string())
+    }
+}
+
+impl Node for OfflineRenderCopyNode {
+    fn run<'w>(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext<'w>,
+        world: &'w World
+    ) -> Result<(), NodeRunError> {
+        let Some(offline_render_copy) = world.get_resource::<OfflineRenderCopy>() else {
+            return Ok(());
+        };
+        let Some(gpu_image) = world.resource::<RenderAssets<Image>>().get(&offline_render_copy.image) else {
+            return Ok(());
+        };
+
+        render_context.command_encoder().copy_texture_to_buffer(
+            gpu_image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &offline_render_copy.buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(offline_render_copy.padded_bytes_per_row),
+                    rows_per_image: None
+                }
+            },
+            Extent3d {
+                width: offline_render_copy.size.x,
+                height: offline_render_copy.size.y,
+                depth_or_array_layers: 1
+            }
+        );
+        offline_render_copy.copied.store(true, Ordering::Release);
+
+        Ok(())
+    }
+}
+
+pub fn send_offline_render_frame(
+    offline_render_copy: Res<OfflineRenderCopy>,
+    render_device: Res<RenderDevice>
+) {
+    if !offline_render_copy.copied.swap(false, Ordering::AcqRel) {
+        return;
+    }
+
+    let buffer_slice = offline_render_copy.buffer.slice(..);
+    let (map_result_sender, map_result_receiver) = mpsc::channel();
+    buffer_slice.map_async(MapMode::Read, move |result| {
+        let _ = map_result_sender.send(result);
+    });
+    render_device.poll(Maintain::Wait);
+    if let Err(error) = map_result_receiver.recv().map_err(|error| error.to_string()).and_then(|result| result.map_err(|error| error.to_string())) {
+        error!("Failed to read rendered frame: {}", error);
+        return;
+    }
+
+    let row_length = offline_render_copy.size.x as usize * 4;
+    let data = buffer_slice.get_mapped_range()
+        .chunks(offline_render_copy.padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..row_length])
+        .copied()
+        .collect();
+    offline_render_copy.buffer.unmap();
+
+    let _ = offline_render_copy.rendered_frame_sender.send(RenderedFrame {
+        frame: offline_render_copy.frame,
+        data
+    });
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{net::{SocketAddr, UdpSocket}, ops::RangeInclusive};
+use crate::{osc::*, visualizer::{COLUMN_BASE_INTENSITY_RANGE, COLUMN_GLOW_STRENGTH_RANGE, COLUMN_WIDTH_RANGE, MAX_HEIGHT_RANGE, RADIUS_RANGE, ROTATION_SPEED_RANGE, SCALE_STRENGTH_RANGE, SCALE_THRESHOLD_RANGE}};
+
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OscOutputSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub max_rate: f32,
+    pub average_address: String,
+    pub columns_address: String,
+    pub bands_address: String,
+    pub beats_address: String
+}
+
+impl Default for OscOutputSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: DEFAULT_OSC_OUTPUT_PORT,
+            max_rate: 30.0,
+            average_address: "/audio/average".to_string(),
+            columns_address: "/audio/columns".to_string(),
+            bands_address: "/audio/bands".to_string(),
+            beats_address: "/audio/beat".to_string()
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct OscSender {
+    pub socket: Option<UdpSocket>,
+    pub target: Option<SocketAddr>,
+    pub next_send_time: f64,
+    pub pending_beats: Vec<bool>,
+    pub error: Option<String>
+}
+
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OscInputSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub mappings: Vec<OscInputMapping>
+}
+
+impl Default for OscInputSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "0.0.0.0".to_string(),
+            port: DEFAULT_OSC_INPUT_PORT,
+            mappings: vec![
+                OscInputMapping::new("/visualizer/radius", OscInputTarget::Radius),
+                OscInputMapping::new("/visualizer/max_height", OscInputTarget::MaxHeight),
+                OscInputMapping::new("/visualizer/column_width", OscInputTarget::ColumnWidth),
+                OscInputMapping::new("/visualizer/rotation_speed", OscInputTarget::RotationSpeed),
+                OscInputMapping::new("/visualizer/scale_strength", OscInputTarget::ScaleStrength),
+                OscInputMapping::new("/visualizer/scale_threshold", OscInputTarget::ScaleThreshold),
+                OscInputMapping::new("/visualizer/glow_strength", OscInputTarget::GlowStrength),
+                OscInputMapping::new("/visualizer/base_intensity", OscInputTarget::BaseIntensity),
+                OscInputMapping::new("/visualizer/color/normal_primary", OscInputTarget::NormalPrimaryColor),
+                OscInputMapping::new("/visualizer/color/normal_secondary", OscInputTarget::NormalSecondaryColor),
+                OscInputMapping::new("/visualizer/color/highlight_primary", OscInputTarget::HighlightPrimaryColor),
+                OscInputMapping::new("/visualizer/color/highlight_secondary", OscInputTarget::HighlightSecondaryColor),
+                OscInputMapping::new("/visualizer/type", OscInputTarget::VisualizerType),
+                OscInputMapping::new("/visualizer/preset", OscInputTarget::Preset)
+            ]
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct OscInputMapping {
+    pub address: String,
+    pub target: OscInputTarget,
+    #[serde(default = "default_normalized")]
+    pub normalized: bool
+}
+
+impl OscInputMapping {
+    pub fn new(address: &str, target: OscInputTarget) -> Self {
+        Self {
+            address: address.to_string(),
+            target,
+            normalized: default_normalized()
+        }
+    }
+}
+
+fn default_normalized() -> bool {
+    true
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum OscInputTarget {
+    Radius,
+    MaxHeight,
+    ColumnWidth,
+    RotationSpeed,
+    ScaleStrength,
+    ScaleThreshold,
+    GlowStrength,
+    BaseIntensity,
+    NormalPrimaryColor,
+    NormalSecondaryColor,
+    HighlightPrimaryColor,
+    HighlightSecondaryColor,
+    VisualizerType,
+    Preset
+}
+
+impl OscInputTarget {
+    pub fn range(&self) -> Option<RangeInclusive<f32>> {
+        match self {
+            OscInputTarget::Radius => Some(RADIUS_RANGE),
+            OscInputTarget::MaxHeight => Some(MAX_HEIGHT_RANGE),
+            OscInputTarget::ColumnWidth => Some(COLUMN_WIDTH_RANGE),
+            OscInputTarget::RotationSpeed => Some(ROTATION_SPEED_RANGE),
+            OscInputTarget::ScaleStrength => Some(SCALE_STRENGTH_RANGE),
+            OscInputTarget::ScaleThreshold => Some(SCALE_THRESHOLD_RANGE),
+            OscInputTarget::GlowStrength => Some(COLUMN_GLOW_STRENGTH_RANGE),
+            OscInputTarget::BaseIntensity => Some(COLUMN_BASE_INTENSITY_RANGE),
+            _ => None
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct OscReceiver {
+    pub socket: Option<UdpSocket>,
+    pub error: Option<String>
+}
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use crate::{audio_analysis::AudioAnalysisSet, visualizer::AudioVisualizerSpawnSet};
+
+pub mod components;
+pub mod protocol;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct OscPlugin;
+
+impl Plugin for OscPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<OscOutputSettings>()
+        .init_resource::<OscSender>()
+        .add_systems(PostUpdate, (
+            update_osc_sender.run_if(resource_changed::<OscOutputSettings>),
+            send_osc_analysis.run_if(|osc_output_settings: Res<OscOutputSettings>| osc_output_settings.enabled)
+        ).chain().after(AudioAnalysisSet));
+    }
+}
+
+pub struct OscInputPlugin;
+
+impl Plugin for OscInputPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<OscInputSettings>()
+        .init_resource::<OscReceiver>()
+        .add_systems(Update, (
+            update_osc_receiver.run_if(resource_changed::<OscInputSettings>),
+            receive_osc_input.run_if(|osc_receiver: Res<OscReceiver>| osc_receiver.socket.is_some())
+        ).chain().before(AudioVisualizerSpawnSet));
+    }
+}
+
+pub const DEFAULT_OSC_OUTPUT_PORT: u16 = 9000;
+pub const DEFAULT_OSC_INPUT_PORT: u16 = 8000;
+pub const OSC_MAX_PACKET_LENGTH: usize = 65536;
@@ -0,0 +1,262 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum OscArgument {
+    Int(i32),
+    Float(f32),
+    String(String),
+    Blob(Vec<u8>)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub arguments: Vec<OscArgument>
+}
+
+pub fn encode_osc_message(
+    address: &str,
+    arguments: &[OscArgument]
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(address.len() + arguments.len() * 5 + 8);
+    write_osc_string(&mut message, address);
+
+    let type_tags: String = std::iter::once(',').chain(arguments.iter().map(|argument| match argument {
+        OscArgument::Int(_) => 'i',
+        OscArgument::Float(_) => 'f',
+        OscArgument::String(_) => 's',
+        OscArgument::Blob(_) => 'b'
+    })).collect();
+    write_osc_string(&mut message, &type_tags);
+
+    for argument in arguments {
+        match argument {
+            OscArgument::Int(value) => message.extend_from_slice(&value.to_be_bytes()),
+            OscArgument::Float(value) => message.extend_from_slice(&value.to_be_bytes()),
+            OscArgument::String(value) => write_osc_string(&mut message, value),
+            OscArgument::Blob(value) => {
+                message.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                message.extend_from_slice(value);
+                message.resize(message.len().div_ceil(4) * 4, 0);
+            }
+        }
+    }
+
+    message
+}
+
+pub fn decode_osc_packet(
+    packet: &[u8]
+) -> Result<Vec<OscMessage>, String> {
+    let mut messages = Vec::new();
+    decode_osc_packet_into(packet, &mut messages)?;
+    Ok(messages)
+}
+
+fn decode_osc_packet_into(
+    packet: &[u8],
+    messages: &mut Vec<OscMessage>
+) -> Result<(), String> {
+    let mut offset = 0;
+
+    if packet.starts_with(b"#bundle\0") {
+        // Skip the time tag, bundle elements are applied immediately
+        offset = 16;
+        while offset < packet.len() {
+            let size = usize::try_from(i32::from_be_bytes(read_osc_bytes(packet, &mut offset)?)).map_err(|_| "bundle element size is negative".to_string())?;
+            let end = offset.checked_add(size).ok_or("bundle element is truncated")?;
+            let element = packet.get(offset..end).ok_or("bundle element is truncated")?;
+            decode_osc_packet_into(element, messages)?;
+            offset = end;
+        }
+        return Ok(());
+    }
+
+    let address = read_osc_string(packet, &mut offset)?;
+    if !address.starts_with('/') {
+        return Err(format!("invalid address `{}`", address));
+    }
+
+    let type_tags = if offset < packet.len() { read_osc_string(packet, &mut offset)? } else { ",".to_string() };
+    let Some(type_tags) = type_tags.strip_prefix(',') else {
+        return Err("missing type tags".to_string());
+    };
+
+    let mut arguments = Vec::new();
+    for type_tag in type_tags.chars() {
+        arguments.push(match type_tag {
+            'i' => OscArgument::Int(i32::from_be_bytes(read_osc_bytes(packet, &mut offset)?)),
+            'f' => OscArgument::Float(f32::from_be_bytes(read_osc_bytes(packet, &mut offset)?)),
+            'h' => OscArgument::Int(i64::from_be_bytes(read_osc_bytes(packet, &mut offset)?) as i32),
+            'd' => OscArgument::Float(f64::from_be_bytes(read_osc_bytes(packet, &mut offset)?) as f32),
+            's' | 'S' => OscArgument::String(read_osc_string(packet, &mut offset)?),
+            'b' => OscArgument::Blob(read_osc_blob(packet, &mut offset)?),
+            'T' => OscArgument::Int(1),
+            'F' => OscArgument::Int(0),
+            _ => return Err(format!("unsupported argument type `{}`", type_tag))
+        });
+    }
+
+    messages.push(OscMessage { address, arguments });
+    Ok(())
+}
+
+fn read_osc_bytes<const N: usize>(
+    packet: &[u8],
+    offset: &mut usize
+) -> Result<[u8; N], String> {
+    let bytes = packet.get(*offset..*offset + N).ok_or("message is truncated")?;
+    *offset += N;
+    Ok(bytes.try_into().unwrap())
+}
+
+fn read_osc_string(
+    packet: &[u8],
+    offset: &mut usize
+) -> Result<String, String> {
+    let remaining = packet.get(*offset..).ok_or("message is truncated")?;
+    let length = remaining.iter().position(|byte| *byte == 0).ok_or("string is not terminated")?;
+    let padded_length = (length + 4) / 4 * 4;
+    if padded_length > remaining.len() {
+        return Err("string padding is truncated".to_string());
+    }
+    let value = String::from_utf8(remaining[..length].to_vec()).map_err(|_| "string is not valid UTF-8".to_string())?;
+    *offset += padded_length;
+    Ok(value)
+}
+
+fn read_osc_blob(
+    packet: &[u8],
+    offset: &mut usize
+) -> Result<Vec<u8>, String> {
+    let size = usize::try_from(i32::from_be_bytes(read_osc_bytes(packet, offset)?)).map_err(|_| "blob size is negative".to_string())?;
+    let padded_end = offset.checked_add(size.div_ceil(4) * 4).filter(|padded_end| *padded_end <= packet.len()).ok_or("blob is truncated")?;
+    let value = packet[*offset..*offset + size].to_vec();
+    *offset = padded_end;
+    Ok(value)
+}
+
+fn write_osc_string(
+    message: &mut Vec<u8>,
+    value: &str
+) {
+    message.extend_from_slice(value.as_bytes());
+    message.resize(message.len() + 4 - value.len() % 4, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoded_messages_round_trip() {
+        let arguments = vec![
+            OscArgument::Float(0.25),
+            OscArgument::Float(-1.5e6),
+            OscArgument::Int(-42),
+            OscArgument::Int(i32::MAX),
+            OscArgument::String(String::new()),
+            OscArgument::String("abc".to_string()),
+            OscArgument::String("abcd".to_string()),
+            OscArgument::Blob(Vec::new()),
+            OscArgument::Blob(vec![1, 2, 3]),
+            OscArgument::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8])
+        ];
+        let packet = encode_osc_message("/visualizer/columns", &arguments);
+
+        assert_eq!(packet.len() % 4, 0);
+        assert_eq!(decode_osc_packet(&packet).unwrap(), vec![OscMessage { address: "/visualizer/columns".to_string(), arguments }]);
+    }
+
+    #[test]
+    fn each_argument_type_round_trips_on_its_own() {
+        for argument in [OscArgument::Float(1.0), OscArgument::Int(7), OscArgument::String("bass".to_string()), OscArgument::Blob(vec![0xff; 5])] {
+            let packet = encode_osc_message("/a", std::slice::from_ref(&argument));
+            assert_eq!(decode_osc_packet(&packet).unwrap()[0].arguments, vec![argument]);
+        }
+    }
+
+    #[test]
+    fn strings_and_blobs_are_padded_to_four_bytes() {
+        assert_eq!(encode_osc_message("/abc", &[]), b"/abc\0\0\0\0,\0\0\0");
+        assert_eq!(encode_osc_message("/ab", &[OscArgument::Blob(vec![9])]), b"/ab\0,b\0\0\0\0\0\x01\x09\0\0\0");
+    }
+
+    fn bundle(elements: &[Vec<u8>]) -> Vec<u8> {
+        let mut packet = b"#bundle\0".to_vec();
+        packet.extend_from_slice(&1u64.to_be_bytes());
+        for element in elements {
+            packet.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            packet.extend_from_slice(element);
+        }
+        packet
+    }
+
+    fn addresses(packet: &[u8]) -> Vec<String> {
+        decode_osc_packet(packet).unwrap().into_iter().map(|message| message.address).collect()
+    }
+
+    #[test]
+    fn bundles_are_flattened_in_order() {
+        let inner_bundle = bundle(&[encode_osc_message("/b", &[]), encode_osc_message("/c", &[OscArgument::Int(3)])]);
+        let packet = bundle(&[encode_osc_message("/a", &[]), inner_bundle, encode_osc_message("/d", &[])]);
+
+        assert_eq!(addresses(&packet), ["/a", "/b", "/c", "/d"]);
+        assert!(addresses(&bundle(&[])).is_empty());
+    }
+
+    #[test]
+    fn negative_bundle_element_sizes_are_rejected() {
+        let mut packet = bundle(&[encode_osc_message("/a", &[])]);
+        packet[16..20].copy_from_slice(&(-8i32).to_be_bytes());
+        assert!(decode_osc_packet(&packet).is_err());
+
+        packet[16..20].copy_from_slice(&i32::MIN.to_be_bytes());
+        assert!(decode_osc_packet(&packet).is_err());
+    }
+
+    #[test]
+    fn truncated_packets_are_rejected() {
+        let mut packet = bundle(&[encode_osc_message("/a", &[OscArgument::Float(1.0)])]);
+        packet[16..20].copy_from_slice(&i32::MAX.to_be_bytes());
+        assert!(decode_osc_packet(&packet).is_err());
+
+        let packet = bundle(&[encode_osc_message("/a", &[OscArgument::Float(1.0)])]);
+        assert!(decode_osc_packet(&packet[..packet.len() - 2]).is_err());
+        assert!(decode_osc_packet(&packet[..18]).is_err());
+
+        let message = encode_osc_message("/a", &[OscArgument::Int(1), OscArgument::String("abc".to_string())]);
+        // The first 4 bytes are a valid message without type tags
+        for length in (1..4).chain(5..message.len()) {
+            assert!(decode_osc_packet(&message[..length]).is_err(), "{} bytes", length);
+        }
+    }
+
+    #[test]
+    fn negative_and_oversized_blob_sizes_are_rejected() {
+        let mut packet = encode_osc_message("/a", &[OscArgument::Blob(vec![1, 2, 3, 4])]);
+        packet[8..12].copy_from_slice(&(-4i32).to_be_bytes());
+        assert!(decode_osc_packet(&packet).is_err());
+
+        packet[8..12].copy_from_slice(&i32::MAX.to_be_bytes());
+        assert!(decode_osc_packet(&packet).is_err());
+
+        let packet = encode_osc_message("/a", &[OscArgument::Blob(vec![1, 2, 3])]);
+        assert!(decode_osc_packet(&packet[..packet.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn string_padding_is_skipped() {
+        for address in ["/a", "/ab", "/abc", "/abcd"] {
+            let packet = encode_osc_message(address, &[OscArgument::String("xyz".to_string()), OscArgument::Int(5)]);
+            assert_eq!(decode_osc_packet(&packet).unwrap()[0].arguments, vec![OscArgument::String("xyz".to_string()), OscArgument::Int(5)]);
+        }
+    }
+
+    #[test]
+    fn invalid_messages_are_rejected() {
+        assert!(decode_osc_packet(b"abc\0,\0\0\0").is_err());
+        assert!(decode_osc_packet(b"/abc").is_err());
+        assert!(decode_osc_packet(b"/a\0\0i\0\0\0").is_err());
+        assert!(decode_osc_packet(b"/a\0\0,x\0\0").is_err());
+        assert_eq!(decode_osc_packet(b"/a\0\0").unwrap()[0].arguments, Vec::new());
+    }
+}
@@ -0,0 +1,390 @@
+use bevy::prelude::*;
+use std::{io::ErrorKind, net::{ToSocketAddrs, UdpSocket}};
+use crate::{audio_analysis::{components::AudioAnalysisEvent, AUDIO_BANDS}, osc::*, presets::components::{PresetEvent, PresetLibrary, PresetTransitionSettings}, visualizer::components::{AudioVisualizerContainer, AudioVisualizerSettings, AudioVisualizerTransition, AudioVisualizerTransitionEvent, SelectedAudioVisualizer, VisualilzerType}};
+use super::{components::*, protocol::*};
+
+pub fn update_osc_sender(
+    osc_output_settings: Res<OscOutputSettings>,
+    mut osc_sender: ResMut<OscSender>
+) {
+    *osc_sender = OscSender::default();
+    if !osc_output_settings.enabled {
+        return;
+    }
+
+    let result = (osc_output_settings.host.as_str(), osc_output_settings.port).to_socket_addrs()
+        .map_err(|error| error.to_string())
+        .and_then(|mut addresses| addresses.next().ok_or_else(|| "host has no address".to_string()))
+        .and_then(|target| {
+            UdpSocket::bind(if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" })
+                .map(|socket| (socket, target))
+                .map_err(|error| error.to_string())
+        });
+
+    match result {
+        Ok((socket, target)) => {
+            info!("Sending OSC to {}", target);
+            osc_sender.socket = Some(socket);
+            osc_sender.target = Some(target);
+        },
+        Err(error) => {
+            error!("Failed to set up OSC output to {}:{}: {}", osc_output_settings.host, osc_output_settings.port, error);
+            osc_sender.error = Some(error);
+        }
+    }
+}
+
+pub fn send_osc_analysis(
+    mut audio_analysis_event_reader: EventReader<AudioAnalysisEvent>,
+    osc_output_settings: Res<OscOutputSettings>,
+    mut osc_sender: ResMut<OscSender>
+) {
+    let osc_sender = &mut *osc_sender;
+    let (Some(socket), Some(target)) = (&osc_sender.socket, osc_sender.target) else {
+        return;
+    };
+
+    for audio_analysis_event in audio_analysis_event_reader.read() {
+        // Beats are kept until the next message so rate limiting doesn't drop them
+        osc_sender.pending_beats.resize(audio_analysis_event.beats.len(), false);
+        for (pending_beat, beat) in osc_sender.pending_beats.iter_mut().zip(&audio_analysis_event.beats) {
+            *pending_beat |= *beat;
+        }
+
+        if osc_output_settings.max_rate > 0.0 {
+            if audio_analysis_event.time < osc_sender.next_send_time {
+                continue;
+            }
+            osc_sender.next_send_time = (osc_sender.next_send_time + 1.0 / osc_output_settings.max_rate as f64).max(audio_analysis_event.time);
+        }
+
+        let mut messages = Vec::new();
+        if !osc_output_settings.average_address.is_empty() {
+            messages.push(encode_osc_message(&osc_output_settings.average_address, &[OscArgument::Float(audio_analysis_event.columns.average_value)]));
+        }
+        if !osc_output_settings.columns_address.is_empty() {
+            let values: Vec<OscArgument> = audio_analysis_event.columns.values.iter().map(|value| OscArgument::Float(*value)).collect();
+            messages.push(encode_osc_message(&osc_output_settings.columns_address, &values));
+        }
+        if !osc_output_settings.bands_address.is_empty() {
+            for ((name, _, _), energy) in AUDIO_BANDS.iter().zip(&audio_analysis_event.band_energies) {
+                messages.push(encode_osc_message(&format!("{}/{}", osc_output_settings.bands_address, name), &[OscArgument::Float(*energy)]));
+            }
+        }
+        if !osc_output_settings.beats_address.is_empty() {
+            for ((name, _, _), beat) in AUDIO_BANDS.iter().zip(&osc_sender.pending_beats) {
+                if *beat {
+                    messages.push(encode_osc_message(&format!("{}/{}", osc_output_settings.beats_address, name), &[OscArgument::Int(1)]));
+                }
+            }
+        }
+        osc_sender.pending_beats.fill(false);
+
+        let result = messages.iter().try_for_each(|message| socket.send_to(message, target).map(|_| ()));
+        match result {
+            Ok(()) => osc_sender.error = None,
+            Err(error) => {
+                if osc_sender.error.is_none() {
+                    warn!("Failed to send OSC to {}: {}", target, error);
+                }
+                osc_sender.error = Some(error.to_string());
+            }
+        }
+    }
+}
+
+pub fn update_osc_receiver(
+    osc_input_settings: Res<OscInputSettings>,
+    mut osc_receiver: ResMut<OscReceiver>
+) {
+    *osc_receiver = OscReceiver::default();
+    if !osc_input_settings.enabled {
+        return;
+    }
+
+    let result = UdpSocket::bind((osc_input_settings.host.as_str(), osc_input_settings.port))
+        .and_then(|socket| socket.set_nonblocking(true).map(|_| socket));
+
+    match result {
+        Ok(socket) => {
+            info!("Listening for OSC on {}:{}", osc_input_settings.host, osc_input_settings.port);
+            osc_receiver.socket = Some(socket);
+        },
+        Err(error) => {
+            error!("Failed to listen for OSC on {}:{}: {}", osc_input_settings.host, osc_input_settings.port, error);
+            osc_receiver.error = Some(error.to_string());
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn receive_osc_input(
+    osc_receiver: Res<OscReceiver>,
+    osc_input_settings: Res<OscInputSettings>,
+    mut audio_visualizer_container_query: Query<(&mut AudioVisualizerSettings, Option<&mut AudioVisualizerTransition>), With<AudioVisualizerContainer>>,
+    mut preset_event_writer: EventWriter<PresetEvent>,
+    mut audio_visualizer_transition_event_writer: EventWriter<AudioVisualizerTransitionEvent>,
+    selected_audio_visualizer: Res<SelectedAudioVisualizer>,
+    preset_library: Res<PresetLibrary>,
+    preset_transition_settings: Res<PresetTransitionSettings>,
+    mut buffer: Local<Vec<u8>>
+) {
+    let Some(socket) = &osc_receiver.socket else {
+        return;
+    };
+    buffer.resize(OSC_MAX_PACKET_LENGTH, 0);
+
+    let mut messages = Vec::new();
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok((length, sender)) => match decode_osc_packet(&buffer[..length]) {
+                Ok(packet_messages) => messages.extend(packet_messages),
+                Err(error) => warn!("Invalid OSC packet from {}: {}", sender, error)
+            },
+            Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+            Err(error) => {
+                warn!("Failed to receive OSC: {}", error);
+                break;
+            }
+        }
+    }
+
+    for message in messages {
+        let Some(mapping) = osc_input_settings.mappings.iter().find(|mapping| mapping.address == message.address) else {
+            continue;
+        };
+
+        let result = match mapping.target {
+            OscInputTarget::Preset => osc_preset_name(&message.arguments, &preset_library)
+                .map(|name| {
+                    preset_event_writer.send(PresetEvent::Load(name));
+                }),
+            target => {
+                let container = selected_audio_visualizer.0.ok_or_else(|| "no visualizer is selected".to_string());
+                container.and_then(|container| {
+                    let Ok((audio_visualizer_settings, audio_visualizer_transition)) = audio_visualizer_container_query.get_mut(container) else {
+                        return Err("the selected visualizer does not exist".to_string());
+                    };
+
+                    if target == OscInputTarget::VisualizerType {
+                        let mut next_audio_visualizer_settings = audio_visualizer_transition.as_ref().map_or(&*audio_visualizer_settings, |audio_visualizer_transition| &audio_visualizer_transition.to).clone();
+                        next_audio_visualizer_settings.visualizer_type = osc_visualizer_type(&message.arguments)?;
+                        if next_audio_visualizer_settings.visualizer_type != audio_visualizer_settings.visualizer_type {
+                            audio_visualizer_transition_event_writer.send(AudioVisualizerTransitionEvent {
+                                container,
+                                settings: next_audio_visualizer_settings,
+                                duration: preset_transition_settings.duration,
+                                easing: preset_transition_settings.easing
+                            });
+                        }
+                        return Ok(());
+                    }
+
+                    // Changes during a transition go to its target so they aren't overwritten by the interpolation
+                    let audio_visualizer_settings = match audio_visualizer_transition {
+                        Some(audio_visualizer_transition) => &mut audio_visualizer_transition.into_inner().to,
+                        None => audio_visualizer_settings.into_inner()
+                    };
+                    apply_osc_input(audio_visualizer_settings, mapping, &message.arguments)
+                })
+            }
+        };
+
+        if let Err(error) = result {
+            warn!("Ignoring OSC message {}: {}", message.address, error);
+        }
+    }
+}
+
+fn apply_osc_input(
+    audio_visualizer_settings: &mut AudioVisualizerSettings,
+    mapping: &OscInputMapping,
+    arguments: &[OscArgument]
+) -> Result<(), String> {
+    let color = match mapping.target {
+        OscInputTarget::NormalPrimaryColor => Some(&mut audio_visualizer_settings.normal_primary_color),
+        OscInputTarget::NormalSecondaryColor => Some(&mut audio_visualizer_settings.normal_secondary_color),
+        OscInputTarget::HighlightPrimaryColor => Some(&mut audio_visualizer_settings.highlight_primary_color),
+        OscInputTarget::HighlightSecondaryColor => Some(&mut audio_visualizer_settings.highlight_secondary_color),
+        _ => None
+    };
+    if let Some(color) = color {
+        if !(3..=4).contains(&arguments.len()) {
+            return Err(format!("expected 3 or 4 color components, got {}", arguments.len()));
+        }
+        let mut components = [1.0; 4];
+        for (component, argument) in components.iter_mut().zip(arguments) {
+            *component = osc_number(argument)?;
+            if !(0.0..=1.0).contains(component) {
+                return Err(format!("color component {} is outside of 0..1", component));
+            }
+        }
+        *color = Color::rgba_from_array(components);
+        return Ok(());
+    }
+
+    let Some(range) = mapping.target.range() else {
+        return Err(format!("{:?} can't be set from a number", mapping.target));
+    };
+    let value = osc_number(arguments.first().ok_or("missing value")?)?;
+    let value = if mapping.normalized {
+        if !(0.0..=1.0).contains(&value) {
+            return Err(format!("normalized value {} is outside of 0..1", value));
+        }
+        range.start() + value * (range.end() - range.start())
+    } else {
+        if !range.contains(&value) {
+            return Err(format!("value {} is outside of {}..{}", value, range.start(), range.end()));
+        }
+        value
+    };
+
+    let field = match mapping.target {
+        OscInputTarget::Radius => &mut audio_visualizer_settings.radius,
+        OscInputTarget::MaxHeight => &mut audio_visualizer_settings.max_height,
+        OscInputTarget::ColumnWidth => &mut audio_visualizer_settings.column_width,
+        OscInputTarget::RotationSpeed => &mut audio_visualizer_settings.rotation_speed,
+        OscInputTarget::ScaleStrength => &mut audio_visualizer_settings.scale_strenght,
+        OscInputTarget::ScaleThreshold => &mut audio_visualizer_settings.scale_threshold,
+        OscInputTarget::GlowStrength => &mut audio_visualizer_settings.column_glow_strength,
+        _ => &mut audio_visualizer_settings.column_base_intensity
+    };
+    *field = value;
+
+    Ok(())
+}
+
+fn osc_number(
+    argument: &OscArgument
+) -> Result<f32, String> {
+    match argument {
+        OscArgument::Int(value) => Ok(*value as f32),
+        OscArgument::Float(value) if value.is_finite() => Ok(*value),
+        _ => Err(format!("expected a number, got {:?}", argument))
+    }
+}
+
+fn osc_visualizer_type(
+    arguments: &[OscArgument]
+) -> Result<VisualilzerType, String> {
+    match arguments.first() {
+        Some(OscArgument::Int(0)) => Ok(VisualilzerType::FrequencyVisualizer),
+        Some(OscArgument::Int(1)) => Ok(VisualilzerType::SpectrumVisualizer),
+        Some(OscArgument::String(name)) if name.eq_ignore_ascii_case("frequency") => Ok(VisualilzerType::FrequencyVisualizer),
+        Some(OscArgument::String(name)) if name.eq_ignore_ascii_case("spectrum") => Ok(VisualilzerType::SpectrumVisualizer),
+        argument => Err(format!("expected 0, 1, \"frequency\" or \"spectrum\", got {:?}", argument))
+    }
+}
+
+fn osc_preset_name(
+    arguments: &[OscArgument],
+    preset_library: &PresetLibrary
+) -> Result<String, String> {
+    let preset = match arguments.first() {
+        Some(OscArgument::String(name)) => preset_library.get(name),
+        Some(OscArgument::Int(index)) => usize::try_from(*index).ok().and_then(|index| preset_library.presets.get(index)),
+        argument => return Err(format!("expected a preset name or index, got {:?}", argument))
+    };
+
+    preset.map(|preset| preset.name.clone()).ok_or_else(|| format!("preset {:?} does not exist", arguments[0]))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{presets::components::Preset, visualizer::{MAX_HEIGHT_RANGE, RADIUS_RANGE}};
+
+    fn mapping(target: OscInputTarget, normalized: bool) -> OscInputMapping {
+        OscInputMapping { normalized, ..OscInputMapping::new("/test", target) }
+    }
+
+    #[test]
+    fn normalized_values_are_scaled_to_the_range_and_rejected_outside_of_it() {
+        let mut audio_visualizer_settings = AudioVisualizerSettings::default();
+        let radius_mapping = mapping(OscInputTarget::Radius, true);
+
+        apply_osc_input(&mut audio_visualizer_settings, &radius_mapping, &[OscArgument::Float(0.5)]).unwrap();
+        assert_eq!(audio_visualizer_settings.radius, RADIUS_RANGE.start() + 0.5 * (RADIUS_RANGE.end() - RADIUS_RANGE.start()));
+
+        apply_osc_input(&mut audio_visualizer_settings, &radius_mapping, &[OscArgument::Int(1)]).unwrap();
+        assert_eq!(audio_visualizer_settings.radius, *RADIUS_RANGE.end());
+
+        for argument in [OscArgument::Float(1.5), OscArgument::Float(-0.1), OscArgument::Float(f32::NAN), OscArgument::Int(2)] {
+            assert!(apply_osc_input(&mut audio_visualizer_settings, &radius_mapping, &[argument]).is_err());
+        }
+        assert_eq!(audio_visualizer_settings.radius, *RADIUS_RANGE.end());
+    }
+
+    #[test]
+    fn absolute_values_are_applied_as_is_and_rejected_outside_of_the_range() {
+        let mut audio_visualizer_settings = AudioVisualizerSettings::default();
+        let max_height_mapping = mapping(OscInputTarget::MaxHeight, false);
+
+        apply_osc_input(&mut audio_visualizer_settings, &max_height_mapping, &[OscArgument::Int(300)]).unwrap();
+        assert_eq!(audio_visualizer_settings.max_height, 300.0);
+
+        for value in [MAX_HEIGHT_RANGE.start() - 1.0, MAX_HEIGHT_RANGE.end() + 1.0, f32::INFINITY] {
+            assert!(apply_osc_input(&mut audio_visualizer_settings, &max_height_mapping, &[OscArgument::Float(value)]).is_err());
+        }
+        assert!(apply_osc_input(&mut audio_visualizer_settings, &max_height_mapping, &[]).is_err());
+        assert!(apply_osc_input(&mut audio_visualizer_settings, &max_height_mapping, &[OscArgument::String("300".to_string())]).is_err());
+        assert_eq!(audio_visualizer_settings.max_height, 300.0);
+    }
+
+    #[test]
+    fn colors_need_three_or_four_components_inside_of_the_unit_range() {
+        let mut audio_visualizer_settings = AudioVisualizerSettings::default();
+        let color_mapping = mapping(OscInputTarget::NormalPrimaryColor, true);
+
+        apply_osc_input(&mut audio_visualizer_settings, &color_mapping, &[OscArgument::Float(1.0), OscArgument::Int(0), OscArgument::Float(0.5)]).unwrap();
+        assert_eq!(audio_visualizer_settings.normal_primary_color, Color::rgba(1.0, 0.0, 0.5, 1.0));
+
+        apply_osc_input(&mut audio_visualizer_settings, &color_mapping, &[OscArgument::Float(0.0), OscArgument::Float(0.25), OscArgument::Float(0.5), OscArgument::Float(0.75)]).unwrap();
+        assert_eq!(audio_visualizer_settings.normal_primary_color, Color::rgba(0.0, 0.25, 0.5, 0.75));
+
+        let float = OscArgument::Float(0.5);
+        for arguments in [vec![float.clone(); 2], vec![float.clone(); 5], vec![float.clone(), float.clone(), OscArgument::Float(1.5)], vec![float.clone(), float.clone(), OscArgument::Int(-1)]] {
+            assert!(apply_osc_input(&mut audio_visualizer_settings, &color_mapping, &arguments).is_err());
+        }
+        assert_eq!(audio_visualizer_settings.normal_primary_color, Color::rgba(0.0, 0.25, 0.5, 0.75));
+    }
+
+    #[test]
+    fn targets_without_a_range_are_rejected() {
+        let mut audio_visualizer_settings = AudioVisualizerSettings::default();
+
+        for target in [OscInputTarget::Preset, OscInputTarget::VisualizerType] {
+            assert!(apply_osc_input(&mut audio_visualizer_settings, &mapping(target, true), &[OscArgument::Float(0.5)]).is_err());
+        }
+    }
+
+    #[test]
+    fn visualizer_types_are_parsed_from_names_and_indices() {
+        assert_eq!(osc_visualizer_type(&[OscArgument::Int(0)]), Ok(VisualilzerType::FrequencyVisualizer));
+        assert_eq!(osc_visualizer_type(&[OscArgument::Int(1)]), Ok(VisualilzerType::SpectrumVisualizer));
+        assert_eq!(osc_visualizer_type(&[OscArgument::String("Frequency".to_string())]), Ok(VisualilzerType::FrequencyVisualizer));
+        assert_eq!(osc_visualizer_type(&[OscArgument::String("SPECTRUM".to_string())]), Ok(VisualilzerType::SpectrumVisualizer));
+
+        for arguments in [vec![], vec![OscArgument::Int(2)], vec![OscArgument::Float(0.0)], vec![OscArgument::String("bars".to_string())]] {
+            assert!(osc_visualizer_type(&arguments).is_err());
+        }
+    }
+
+    #[test]
+    fn presets_are_looked_up_by_name_or_index() {
+        let preset_library = PresetLibrary {
+            presets: ["Calm", "Loud"].into_iter()
+                .map(|name| Preset { name: name.to_string(), path: None, settings: AudioVisualizerSettings::default() })
+                .collect(),
+            ..default()
+        };
+
+        assert_eq!(osc_preset_name(&[OscArgument::String("Loud".to_string())], &preset_library), Ok("Loud".to_string()));
+        assert_eq!(osc_preset_name(&[OscArgument::Int(0)], &preset_library), Ok("Calm".to_string()));
+
+        for arguments in [vec![], vec![OscArgument::String("Quiet".to_string())], vec![OscArgument::Int(2)], vec![OscArgument::Int(-1)], vec![OscArgument::Float(0.0)]] {
+            assert!(osc_preset_name(&arguments, &preset_library).is_err());
+        }
+    }
+}
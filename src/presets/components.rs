@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::{config::CONFIG_DIRECTORY_NAME, presets::PRESETS_DIRECTORY_NAME, visualizer::components::{AudioVisualizerSettings, Easing}};
+
+pub struct Preset {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub settings: AudioVisualizerSettings
+}
+
+impl Preset {
+    pub fn is_built_in(&self) -> bool {
+        self.path.is_none()
+    }
+}
+
+#[derive(Resource)]
+pub struct PresetLibrary {
+    pub directory: Option<PathBuf>,
+    pub presets: Vec<Preset>,
+    pub active_preset: Option<String>,
+    pub error: Option<String>
+}
+
+impl Default for PresetLibrary {
+    fn default() -> Self {
+        Self {
+            directory: dirs::config_dir().map(|config_dir| config_dir.join(CONFIG_DIRECTORY_NAME).join(PRESETS_DIRECTORY_NAME)),
+            presets: Vec::new(),
+            active_preset: None,
+            error: None
+        }
+    }
+}
+
+impl PresetLibrary {
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetTransitionSettings {
+    pub duration: f32,
+    pub easing: Easing
+}
+
+impl Default for PresetTransitionSettings {
+    fn default() -> Self {
+        Self {
+            duration: 1.0,
+            easing: Easing::EaseInOut
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct RandomizerLocks {
+    pub frequencies: bool,
+    pub geometry: bool,
+    pub colors: bool,
+    pub column_style: bool
+}
+
+#[derive(Event)]
+pub enum PresetEvent {
+    Save(String),
+    Load(String),
+    LoadFile(PathBuf),
+    Duplicate(String),
+    Rename(String, String),
+    Delete(String),
+    Randomize,
+    Refresh
+}
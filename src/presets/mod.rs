@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+use crate::{config::components::ConfigFileChangedEvent, visualizer::AudioVisualizerSpawnSet};
+
+pub mod components;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub use systems::{load_presets, parse_preset};
+
+pub struct PresetPlugin;
+
+impl Plugin for PresetPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<PresetLibrary>()
+        .init_resource::<PresetTransitionSettings>()
+        .init_resource::<RandomizerLocks>()
+        .add_event::<PresetEvent>()
+        .add_event::<ConfigFileChangedEvent>()
+        .add_systems(Startup, refresh_preset_library)
+        .add_systems(Update, handle_preset_events.run_if(on_event::<PresetEvent>()).after(AudioVisualizerSpawnSet))
+        .add_systems(Update, hot_reload_presets.run_if(on_event::<ConfigFileChangedEvent>()));
+    }
+}
+
+pub const PRESETS_DIRECTORY_NAME: &str = "presets";
+pub const PRESET_FILE_EXTENSION: &str = "ron";
+
+pub const BUILT_IN_PRESETS: [(&str, &str); 4] = [
+    ("Classic", include_str!("built_in/classic.ron")),
+    ("Rainbow Ring", include_str!("built_in/rainbow_ring.ron")),
+    ("Heat Bars", include_str!("built_in/heat_bars.ron")),
+    ("Neon Glow", include_str!("built_in/neon_glow.ron"))
+];
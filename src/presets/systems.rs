@@ -0,0 +1,460 @@
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+use ron::ser::PrettyConfig;
+use std::{fs, io::ErrorKind, path::PathBuf};
+use crate::{config::{components::ConfigFileChangedEvent, parse_ron}, visualizer::components::{AudioVisualizerContainer, AudioVisualizerRestructureEvent, AudioVisualizerSettings, AudioVisualizerSpawnEvent, AudioVisualizerTransition, AudioVisualizerTransitionEvent, ColorGradient, ColorGradientStop, ColorMode, SelectedAudioVisualizer, WindowFunction}};
+use crate::presets::*;
+use super::components::*;
+
+pub fn refresh_preset_library(
+    mut preset_library: ResMut<PresetLibrary>
+) {
+    load_presets(&mut preset_library);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_preset_events(
+    mut preset_event_reader: EventReader<PresetEvent>,
+    mut audio_visualizer_transition_event_writer: EventWriter<AudioVisualizerTransitionEvent>,
+    mut audio_visualizer_spawn_event_writer: EventWriter<AudioVisualizerSpawnEvent>,
+    audio_visualizer_container_query: Query<(&AudioVisualizerSettings, Option<&AudioVisualizerTransition>), With<AudioVisualizerContainer>>,
+    selected_audio_visualizer: Res<SelectedAudioVisualizer>,
+    mut preset_library: ResMut<PresetLibrary>,
+    preset_transition_settings: Res<PresetTransitionSettings>,
+    randomizer_locks: Res<RandomizerLocks>
+) {
+    for preset_event in preset_event_reader.read() {
+        let result = match preset_event {
+            PresetEvent::Save(name) => {
+                match selected_audio_visualizer.0.and_then(|entity| audio_visualizer_container_query.get(entity).ok()) {
+                    Some((audio_visualizer_settings, audio_visualizer_transition)) => save_preset(&mut preset_library, name.trim(), audio_visualizer_transition.map_or(audio_visualizer_settings, |audio_visualizer_transition| &audio_visualizer_transition.to)),
+                    None => Err("No visualizer selected to save as a preset".to_string())
+                }
+            },
+            PresetEvent::Load(name) => {
+                match preset_library.get(name) {
+                    Some(preset) => {
+                        let selected_audio_visualizer_settings = selected_audio_visualizer.0.and_then(|entity| audio_visualizer_container_query.get(entity).ok().map(|(settings, _)| (entity, settings)));
+                        load_preset(preset.settings.clone(), selected_audio_visualizer_settings, &preset_transition_settings, &mut audio_visualizer_transition_event_writer, &mut audio_visualizer_spawn_event_writer);
+                        preset_library.active_preset = Some(name.clone());
+                        Ok(())
+                    },
+                    None => Err(format!("Preset `{}` does not exist", name))
+                }
+            },
+            PresetEvent::LoadFile(path) => {
+                let result = fs::read_to_string(path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|contents| parse_preset(&contents).map_err(|error| error.to_string()));
+                match result {
+                    Ok(audio_visualizer_settings) => {
+                        let selected_audio_visualizer_settings = selected_audio_visualizer.0.and_then(|entity| audio_visualizer_container_query.get(entity).ok().map(|(settings, _)| (entity, settings)));
+                        load_preset(audio_visualizer_settings, selected_audio_visualizer_settings, &preset_transition_settings, &mut audio_visualizer_transition_event_writer, &mut audio_visualizer_spawn_event_writer);
+                        preset_library.active_preset = None;
+                        Ok(())
+                    },
+                    Err(error) => Err(format!("Invalid preset {}: {}", path.display(), error))
+                }
+            },
+            PresetEvent::Duplicate(name) => duplicate_preset(&mut preset_library, name),
+            PresetEvent::Rename(name, new_name) => rename_preset(&mut preset_library, name, new_name.trim()),
+            PresetEvent::Delete(name) => delete_preset(&mut preset_library, name),
+            PresetEvent::Randomize => {
+                let selected_audio_visualizer_settings = selected_audio_visualizer.0.and_then(|entity| audio_visualizer_container_query.get(entity).ok().map(|(settings, audio_visualizer_transition)| (entity, audio_visualizer_transition.map_or(settings, |audio_visualizer_transition| &audio_visualizer_transition.to))));
+                let audio_visualizer_settings = randomize_settings(selected_audio_visualizer_settings.map_or(&AudioVisualizerSettings::default(), |(_, settings)| settings), &randomizer_locks, &mut rand::thread_rng());
+                load_preset(audio_visualizer_settings, selected_audio_visualizer_settings, &preset_transition_settings, &mut audio_visualizer_transition_event_writer, &mut audio_visualizer_spawn_event_writer);
+                preset_library.active_preset = None;
+                Ok(())
+            },
+            PresetEvent::Refresh => {
+                load_presets(&mut preset_library);
+                continue;
+            }
+        };
+
+        if let Err(error) = &result {
+            error!("{}", error);
+        }
+        preset_library.error = result.err();
+    }
+}
+
+pub fn hot_reload_presets(
+    mut commands: Commands,
+    mut config_file_changed_event_reader: EventReader<ConfigFileChangedEvent>,
+    mut audio_visualizer_restructure_event_writer: EventWriter<AudioVisualizerRestructureEvent>,
+    mut audio_visualizer_container_query: Query<&mut AudioVisualizerSettings, With<AudioVisualizerContainer>>,
+    selected_audio_visualizer: Res<SelectedAudioVisualizer>,
+    mut preset_library: ResMut<PresetLibrary>
+) {
+    let Some(directory) = preset_library.directory.clone() else {
+        return;
+    };
+    let changed_paths: Vec<PathBuf> = config_file_changed_event_reader.read()
+        .map(|ConfigFileChangedEvent(path)| path.clone())
+        .filter(|path| path.parent() == Some(directory.as_path()))
+        .collect();
+    if changed_paths.is_empty() {
+        return;
+    }
+
+    let active_preset = preset_library.active_preset.as_ref()
+        .and_then(|name| preset_library.get(name))
+        .filter(|preset| preset.path.as_ref().is_some_and(|path| changed_paths.contains(path)))
+        .map(|preset| (preset.name.clone(), ron::ser::to_string(&preset.settings).unwrap_or_default()));
+
+    load_presets(&mut preset_library);
+
+    let Some((active_preset_name, previous_contents)) = active_preset else {
+        return;
+    };
+    let Some(preset) = preset_library.get(&active_preset_name) else {
+        return;
+    };
+    if ron::ser::to_string(&preset.settings).unwrap_or_default() == previous_contents {
+        return;
+    }
+    let Some(container) = selected_audio_visualizer.0 else {
+        return;
+    };
+    let Ok(mut audio_visualizer_settings) = audio_visualizer_container_query.get_mut(container) else {
+        return;
+    };
+
+    let mut reloaded_audio_visualizer_settings = preset.settings.clone();
+    reloaded_audio_visualizer_settings.position = audio_visualizer_settings.position;
+    let structural_change = audio_visualizer_settings.column_count != reloaded_audio_visualizer_settings.column_count;
+    audio_visualizer_settings.apply_preserving_color_transition(reloaded_audio_visualizer_settings);
+    commands.entity(container).remove::<AudioVisualizerTransition>();
+    if structural_change {
+        audio_visualizer_restructure_event_writer.send(AudioVisualizerRestructureEvent(container));
+    }
+    info!("Reloaded preset `{}`", active_preset_name);
+}
+
+fn load_preset(
+    mut audio_visualizer_settings: AudioVisualizerSettings,
+    selected_audio_visualizer_settings: Option<(Entity, &AudioVisualizerSettings)>,
+    preset_transition_settings: &PresetTransitionSettings,
+    audio_visualizer_transition_event_writer: &mut EventWriter<AudioVisualizerTransitionEvent>,
+    audio_visualizer_spawn_event_writer: &mut EventWriter<AudioVisualizerSpawnEvent>
+) {
+    match selected_audio_visualizer_settings {
+        Some((entity, selected_audio_visualizer_settings)) => {
+            audio_visualizer_settings.position = selected_audio_visualizer_settings.position;
+            audio_visualizer_transition_event_writer.send(AudioVisualizerTransitionEvent {
+                container: entity,
+                settings: audio_visualizer_settings,
+                duration: preset_transition_settings.duration,
+                easing: preset_transition_settings.easing
+            });
+        },
+        None => {
+            audio_visualizer_spawn_event_writer.send(AudioVisualizerSpawnEvent(audio_visualizer_settings));
+        }
+    }
+}
+
+fn randomize_settings(
+    audio_visualizer_settings: &AudioVisualizerSettings,
+    randomizer_locks: &RandomizerLocks,
+    rng: &mut impl Rng
+) -> AudioVisualizerSettings {
+    let mut audio_visualizer_settings = audio_visualizer_settings.clone();
+
+    if !randomizer_locks.frequencies {
+        audio_visualizer_settings.lower_frequency_limit = rng.gen_range(0.0..=100.0);
+        audio_visualizer_settings.upper_frequency_limit = rng.gen_range(500.0..=8000.0);
+        audio_visualizer_settings.sampling_rate = rng.gen_range((audio_visualizer_settings.upper_frequency_limit * 2.0 + 1.0) as u32..=44100);
+        audio_visualizer_settings.window_function = *[WindowFunction::None, WindowFunction::Hann, WindowFunction::Hamming].choose(rng).unwrap();
+    }
+
+    if !randomizer_locks.geometry {
+        audio_visualizer_settings.column_count = rng.gen_range(32..=720);
+        audio_visualizer_settings.section_count = rng.gen_range(1..=6);
+        audio_visualizer_settings.radius = rng.gen_range(100.0..=350.0);
+        audio_visualizer_settings.rotation_speed = rng.gen_range(-0.05..=0.05);
+        audio_visualizer_settings.column_width = rng.gen_range(1.0..=(2000.0 / audio_visualizer_settings.column_count as f32).clamp(1.5, 10.0));
+        audio_visualizer_settings.max_height = rng.gen_range(100.0..=500.0);
+        audio_visualizer_settings.smoothing_range = rng.gen_range(1..=(audio_visualizer_settings.column_count / 2).min(12));
+        audio_visualizer_settings.scale_strenght = rng.gen_range(0.0..=3000.0);
+        audio_visualizer_settings.scale_threshold = rng.gen_range(1.0..=4.0);
+    }
+
+    if !randomizer_locks.colors {
+        audio_visualizer_settings.color_mode = *[ColorMode::Solid, ColorMode::Frequency, ColorMode::Height, ColorMode::FrequencyAndHeight].choose(rng).unwrap();
+        audio_visualizer_settings.normal_color_transition_enabled = rng.gen_bool(0.5);
+        audio_visualizer_settings.normal_color_transition_speed = rng.gen_range(0.001..=0.05);
+        audio_visualizer_settings.normal_primary_color = random_color(rng);
+        audio_visualizer_settings.normal_secondary_color = random_color(rng);
+        audio_visualizer_settings.normal_primary_color_hdr_multiplier = rng.gen_range(1.0..=4.0);
+        audio_visualizer_settings.normal_secondary_color_hdr_multiplier = rng.gen_range(1.0..=4.0);
+        audio_visualizer_settings.highlight_color_transition_enabled = rng.gen_bool(0.5);
+        audio_visualizer_settings.highlight_color_transition_speed = rng.gen_range(0.001..=0.05);
+        audio_visualizer_settings.highlight_primary_color = random_color(rng);
+        audio_visualizer_settings.highlight_secondary_color = random_color(rng);
+        audio_visualizer_settings.highlight_primary_color_hdr_multiplier = rng.gen_range(1.0..=6.0);
+        audio_visualizer_settings.highlight_secondary_color_hdr_multiplier = rng.gen_range(1.0..=6.0);
+        audio_visualizer_settings.frequency_gradient = random_color_gradient(rng);
+        audio_visualizer_settings.height_gradient = random_color_gradient(rng);
+        audio_visualizer_settings.height_gradient_along_column = rng.gen_bool(0.5);
+    }
+
+    if !randomizer_locks.column_style {
+        audio_visualizer_settings.column_cap_roundness = rng.gen_range(0.0..=1.0);
+        audio_visualizer_settings.column_edge_softness = rng.gen_range(0.0..=2.0);
+        audio_visualizer_settings.column_glow_radius = rng.gen_range(0.0..=20.0);
+        audio_visualizer_settings.column_glow_strength = rng.gen_range(0.0..=0.6);
+        audio_visualizer_settings.column_base_intensity = rng.gen_range(0.2..=1.0);
+        audio_visualizer_settings.amplitude_intensity_strength = rng.gen_range(0.0..=20.0);
+    }
+
+    for correction in audio_visualizer_settings.sanitize() {
+        warn!("Randomized settings: {}", correction);
+    }
+    audio_visualizer_settings
+}
+
+fn random_color(
+    rng: &mut impl Rng
+) -> Color {
+    Color::hsl(rng.gen_range(0.0..360.0), rng.gen_range(0.6..=1.0), rng.gen_range(0.4..=0.6))
+}
+
+fn random_color_gradient(
+    rng: &mut impl Rng
+) -> ColorGradient {
+    let stop_count = rng.gen_range(2..=5);
+    ColorGradient {
+        stops: (0..stop_count)
+            .map(|i| ColorGradientStop {
+                position: i as f32 / (stop_count - 1) as f32,
+                color: random_color(rng),
+                hdr_multiplier: rng.gen_range(1.0..=4.0)
+            })
+            .collect()
+    }
+}
+
+pub fn load_presets(
+    preset_library: &mut PresetLibrary
+) {
+    preset_library.presets.clear();
+    preset_library.error = None;
+
+    for (name, contents) in BUILT_IN_PRESETS {
+        match parse_preset(contents) {
+            Ok(settings) => preset_library.presets.push(Preset { name: name.to_string(), path: None, settings }),
+            Err(error) => error!("Invalid built-in preset {}: {}", name, error)
+        }
+    }
+
+    let Some(directory) = preset_library.directory.clone() else {
+        return;
+    };
+
+    let entries = match fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return,
+        Err(error) => {
+            preset_library.error = Some(format!("Failed to read presets directory {}: {}", directory.display(), error));
+            return;
+        }
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == PRESET_FILE_EXTENSION))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let Some(name) = path.file_stem().map(|name| name.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        if preset_library.get(&name).is_some() {
+            warn!("Ignoring preset {} because a built-in preset has the same name", path.display());
+            continue;
+        }
+
+        let result = fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| parse_preset(&contents).map_err(|error| error.to_string()));
+        match result {
+            Ok(settings) => preset_library.presets.push(Preset { name, path: Some(path), settings }),
+            Err(error) => {
+                error!("Invalid preset {}: {}", path.display(), error);
+                preset_library.error = Some(format!("Invalid preset {}: {}", path.display(), error));
+            }
+        }
+    }
+}
+
+pub fn parse_preset(
+    contents: &str
+) -> Result<AudioVisualizerSettings, ron::error::SpannedError> {
+    let mut audio_visualizer_settings: AudioVisualizerSettings = parse_ron(contents)?;
+
+    for correction in audio_visualizer_settings.sanitize() {
+        warn!("Invalid preset value: {}", correction);
+    }
+
+    Ok(audio_visualizer_settings)
+}
+
+fn save_preset(
+    preset_library: &mut PresetLibrary,
+    name: &str,
+    audio_visualizer_settings: &AudioVisualizerSettings
+) -> Result<(), String> {
+    validate_preset_name(name)?;
+    if preset_library.get(name).is_some_and(Preset::is_built_in) {
+        return Err(format!("Cannot overwrite built-in preset `{}`", name));
+    }
+
+    write_preset(preset_library, name, audio_visualizer_settings)?;
+    preset_library.active_preset = Some(name.to_string());
+
+    Ok(())
+}
+
+fn duplicate_preset(
+    preset_library: &mut PresetLibrary,
+    name: &str
+) -> Result<(), String> {
+    let Some(preset) = preset_library.get(name) else {
+        return Err(format!("Preset `{}` does not exist", name));
+    };
+    let audio_visualizer_settings = preset.settings.clone();
+
+    let mut new_name = format!("{} Copy", name);
+    let mut copy_number = 2;
+    while preset_library.get(&new_name).is_some() {
+        new_name = format!("{} Copy {}", name, copy_number);
+        copy_number += 1;
+    }
+
+    write_preset(preset_library, &new_name, &audio_visualizer_settings)
+}
+
+fn rename_preset(
+    preset_library: &mut PresetLibrary,
+    name: &str,
+    new_name: &str
+) -> Result<(), String> {
+    validate_preset_name(new_name)?;
+    let Some(preset) = preset_library.get(name) else {
+        return Err(format!("Preset `{}` does not exist", name));
+    };
+    let Some(path) = preset.path.clone() else {
+        return Err(format!("Cannot rename built-in preset `{}`", name));
+    };
+    if preset_library.get(new_name).is_some() {
+        return Err(format!("Preset `{}` already exists", new_name));
+    }
+
+    fs::rename(&path, path.with_file_name(preset_file_name(new_name)))
+        .map_err(|error| format!("Failed to rename preset {}: {}", path.display(), error))?;
+
+    if preset_library.active_preset.as_deref() == Some(name) {
+        preset_library.active_preset = Some(new_name.to_string());
+    }
+    load_presets(preset_library);
+
+    Ok(())
+}
+
+fn delete_preset(
+    preset_library: &mut PresetLibrary,
+    name: &str
+) -> Result<(), String> {
+    let Some(preset) = preset_library.get(name) else {
+        return Err(format!("Preset `{}` does not exist", name));
+    };
+    let Some(path) = preset.path.clone() else {
+        return Err(format!("Cannot delete built-in preset `{}`", name));
+    };
+
+    fs::remove_file(&path)
+        .map_err(|error| format!("Failed to delete preset {}: {}", path.display(), error))?;
+
+    if preset_library.active_preset.as_deref() == Some(name) {
+        preset_library.active_preset = None;
+    }
+    load_presets(preset_library);
+
+    Ok(())
+}
+
+fn write_preset(
+    preset_library: &mut PresetLibrary,
+    name: &str,
+    audio_visualizer_settings: &AudioVisualizerSettings
+) -> Result<(), String> {
+    let Some(directory) = preset_library.directory.clone() else {
+        return Err("No config directory found, presets cannot be saved".to_string());
+    };
+
+    let contents = ron::ser::to_string_pretty(audio_visualizer_settings, PrettyConfig::default())
+        .map_err(|error| format!("Failed to serialize preset `{}`: {}", name, error))?;
+
+    fs::create_dir_all(&directory)
+        .map_err(|error| format!("Failed to create presets directory {}: {}", directory.display(), error))?;
+
+    let path = directory.join(preset_file_name(name));
+    fs::write(&path, &contents)
+        .map_err(|error| format!("Failed to save preset {}: {}", path.display(), error))?;
+
+    load_presets(preset_library);
+
+    Ok(())
+}
+
+fn preset_file_name(
+    name: &str
+) -> String {
+    format!("{}.{}", name, PRESET_FILE_EXTENSION)
+}
+
+fn validate_preset_name(
+    name: &str
+) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+    if name.starts_with('.') || name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']) {
+        return Err(format!("Preset name `{}` contains invalid characters", name));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{audio_data::SPECTRUM_DATA_LENGTH, visualizer::{analysis::analyze_audio, minimum_frequency_range}};
+
+    #[test]
+    fn randomized_settings_satisfy_sanitize_invariants() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let samples: Vec<f32> = (0..SPECTRUM_DATA_LENGTH).map(|i| (i as f32 * 0.37).sin()).collect();
+        let mut audio_visualizer_settings = AudioVisualizerSettings::default();
+
+        for _ in 0..500 {
+            let randomizer_locks = RandomizerLocks {
+                frequencies: rng.gen_bool(0.2),
+                geometry: rng.gen_bool(0.2),
+                colors: rng.gen_bool(0.2),
+                column_style: rng.gen_bool(0.2)
+            };
+            audio_visualizer_settings = randomize_settings(&audio_visualizer_settings, &randomizer_locks, &mut rng);
+
+            assert!(audio_visualizer_settings.clone().sanitize().is_empty());
+            assert!(audio_visualizer_settings.upper_frequency_limit <= audio_visualizer_settings.sampling_rate as f32 / 2.0);
+            assert!(audio_visualizer_settings.upper_frequency_limit - audio_visualizer_settings.lower_frequency_limit >= minimum_frequency_range(audio_visualizer_settings.sampling_rate));
+            assert!(audio_visualizer_settings.smoothing_range <= audio_visualizer_settings.column_count / 2);
+            assert!(analyze_audio(&samples, &audio_visualizer_settings, audio_visualizer_settings.column_count).is_some());
+        }
+    }
+}
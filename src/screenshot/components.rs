@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::screenshot::SCREENSHOTS_DIRECTORY_NAME;
+
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotSettings {
+    pub directory: Option<PathBuf>
+}
+
+impl ScreenshotSettings {
+    pub fn directory(&self) -> PathBuf {
+        self.directory.clone()
+            .or_else(|| dirs::picture_dir().map(|picture_dir| picture_dir.join(SCREENSHOTS_DIRECTORY_NAME)))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Resource, Default)]
+pub enum PendingScreenshot {
+    #[default]
+    None,
+    Capture {
+        path: PathBuf,
+        ui_visible: bool
+    },
+    RestoreUi {
+        ui_visible: bool
+    }
+}
+
+#[derive(Event)]
+pub struct ScreenshotEvent {
+    pub include_ui: bool
+}
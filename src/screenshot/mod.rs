@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+pub mod components;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<ScreenshotSettings>()
+        .init_resource::<PendingScreenshot>()
+        .add_event::<ScreenshotEvent>()
+        .add_systems(Update, (update_pending_screenshot, handle_screenshot_events.run_if(on_event::<ScreenshotEvent>())).chain());
+    }
+}
+
+pub const SCREENSHOTS_DIRECTORY_NAME: &str = "customizable-bevy-audio-visualizer";
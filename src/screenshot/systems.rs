@@ -0,0 +1,120 @@
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+use std::{fs, path::Path, time::{SystemTime, UNIX_EPOCH}};
+use crate::ui::components::UiState;
+use super::components::*;
+
+pub fn handle_screenshot_events(
+    mut screenshot_event_reader: EventReader<ScreenshotEvent>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut pending_screenshot: ResMut<PendingScreenshot>,
+    ui_state: Option<ResMut<UiState>>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    screenshot_settings: Res<ScreenshotSettings>
+) {
+    let Some(ScreenshotEvent { include_ui }) = screenshot_event_reader.read().last() else {
+        return;
+    };
+    if !matches!(*pending_screenshot, PendingScreenshot::None) {
+        return;
+    }
+
+    let directory = screenshot_settings.directory();
+    if let Err(error) = fs::create_dir_all(&directory) {
+        error!("Failed to create screenshot directory {}: {}", directory.display(), error);
+        return;
+    }
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let path = directory.join(format!("screenshot_{}.png", timestamp));
+
+    match ui_state {
+        // The settings window is hidden for one frame so the screenshot only shows the visualizers, its previous visibility is restored afterwards
+        Some(mut ui_state) if !*include_ui => {
+            *pending_screenshot = PendingScreenshot::Capture { path, ui_visible: ui_state.visible };
+            ui_state.visible = false;
+        },
+        _ => save_screenshot(&mut screenshot_manager, &window_query, &path)
+    }
+}
+
+pub fn update_pending_screenshot(
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut pending_screenshot: ResMut<PendingScreenshot>,
+    ui_state: Option<ResMut<UiState>>,
+    window_query: Query<Entity, With<PrimaryWindow>>
+) {
+    match &*pending_screenshot {
+        PendingScreenshot::None => {},
+        PendingScreenshot::Capture { path, ui_visible } => {
+            save_screenshot(&mut screenshot_manager, &window_query, path);
+            *pending_screenshot = PendingScreenshot::RestoreUi { ui_visible: *ui_visible };
+        },
+        PendingScreenshot::RestoreUi { ui_visible } => {
+            if let Some(mut ui_state) = ui_state {
+                ui_state.visible = *ui_visible;
+            }
+            *pending_screenshot = PendingScreenshot::None;
+        }
+    }
+}
+
+fn save_screenshot(
+    screenshot_manager: &mut ScreenshotManager,
+    window_query: &Query<Entity, With<PrimaryWindow>>,
+    path: &Path
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    if let Err(error) = screenshot_manager.save_screenshot_to_disk(window, path) {
+        error!("Failed to take screenshot: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn take_screenshot_without_ui(ui_visible: bool) -> Vec<bool> {
+        let mut world = World::new();
+        world.init_resource::<ScreenshotManager>();
+        world.init_resource::<PendingScreenshot>();
+        world.init_resource::<Events<ScreenshotEvent>>();
+        world.insert_resource(UiState { visible: ui_visible, ..default() });
+        world.insert_resource(ScreenshotSettings { directory: Some(std::env::temp_dir()) });
+        world.send_event(ScreenshotEvent { include_ui: false });
+
+        world.run_system_once(handle_screenshot_events);
+        let mut ui_visibility = vec![world.resource::<UiState>().visible];
+        for _ in 0..2 {
+            world.run_system_once(update_pending_screenshot);
+            ui_visibility.push(world.resource::<UiState>().visible);
+        }
+        assert!(matches!(world.resource::<PendingScreenshot>(), PendingScreenshot::None));
+        ui_visibility
+    }
+
+    #[test]
+    fn visible_ui_is_hidden_for_the_capture_and_shown_again() {
+        assert_eq!(take_screenshot_without_ui(true), [false, false, true]);
+    }
+
+    #[test]
+    fn hidden_ui_stays_hidden() {
+        assert_eq!(take_screenshot_without_ui(false), [false, false, false]);
+    }
+
+    #[test]
+    fn screenshot_is_taken_immediately_without_the_ui_plugin() {
+        let mut world = World::new();
+        world.init_resource::<ScreenshotManager>();
+        world.init_resource::<PendingScreenshot>();
+        world.init_resource::<Events<ScreenshotEvent>>();
+        world.insert_resource(ScreenshotSettings { directory: Some(std::env::temp_dir()) });
+        world.send_event(ScreenshotEvent { include_ui: false });
+
+        world.run_system_once(handle_screenshot_events);
+        assert!(matches!(world.resource::<PendingScreenshot>(), PendingScreenshot::None));
+        assert!(!world.contains_resource::<UiState>());
+    }
+}
@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+
+#[derive(Resource)]
+pub struct UiState {
+    pub visible: bool,
+    pub show_inspector: bool
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            show_inspector: false
+        }
+    }
+}
+
+/// Whether the settings window is using the keyboard or the pointer, so other plugins don't react to the same input.
+#[derive(Resource, Default, PartialEq)]
+pub struct UiInputState {
+    pub wants_keyboard_input: bool,
+    pub pointer_busy: bool
+}
+
+#[derive(Default)]
+pub struct PresetBrowserState {
+    pub selected_preset: Option<String>,
+    pub preset_name: String
+}
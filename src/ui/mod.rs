@@ -1,14 +1,34 @@
 use bevy::prelude::*;
+use bevy_inspector_egui::{bevy_egui::{EguiPlugin, EguiSet}, quick::WorldInspectorPlugin};
+use crate::{midi::components::{MidiLearnState, MidiSettings, MidiStatus}, osc::components::{OscInputSettings, OscOutputSettings, OscReceiver, OscSender}};
 
+pub mod components;
 mod systems;
 
 use systems::*;
+use components::*;
 
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin);
+        }
+
         app
-        .add_systems(Update, settings_ui);
+        .init_resource::<UiState>()
+        .init_resource::<UiInputState>()
+        .init_resource::<OscOutputSettings>()
+        .init_resource::<OscSender>()
+        .init_resource::<OscInputSettings>()
+        .init_resource::<OscReceiver>()
+        .init_resource::<MidiSettings>()
+        .init_resource::<MidiStatus>()
+        .init_resource::<MidiLearnState>()
+        .add_plugins(WorldInspectorPlugin::new().run_if(|ui_state: Res<UiState>| ui_state.visible && ui_state.show_inspector))
+        .add_systems(PreUpdate, update_ui_input_state.after(EguiSet::BeginFrame))
+        .add_systems(Update, settings_ui.run_if(|ui_state: Res<UiState>| ui_state.visible))
+        .add_systems(Update, config_error_overlay);
     }
 }
\ No newline at end of file
@@ -1,159 +1,574 @@
-use bevy::prelude::*;
-use bevy_inspector_egui::{bevy_egui::EguiContexts, egui::{self, widgets, Color32, RichText}};
-use crate::{visualizer::components::{AudioVisualizerRestructureEvent, AudioVisualizerSettings, VisualilzerType, WindowFunction}, AdvancedSettings, AdvancedSettingsChangeEvent};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_inspector_egui::{bevy_egui::EguiContexts, egui::{self, widgets, RichText}, reflect_inspector};
+use crate::{config::components::ConfigFile, history::components::{HistoryEvent, SettingsHistory}, midi::components::{MidiControlBinding, MidiControlTarget, MidiLearnState, MidiLearnTarget, MidiSettings, MidiStatus}, osc::components::{OscInputSettings, OscOutputSettings, OscReceiver, OscSender}, presets::components::{PresetEvent, PresetLibrary, PresetTransitionSettings, RandomizerLocks}, visualizer::{components::{AudioVisualizerContainer, AudioVisualizerSettings, AudioVisualizerSpawnEvent, ColorGradient, ColorGradientStop, ColorMode, Easing, SelectedAudioVisualizer, VisualilzerType, WindowFunction}, minimum_frequency_range, AMPLITUDE_INTENSITY_STRENGTH_RANGE, COLOR_TRANSITION_SPEED_RANGE, COLUMN_BASE_INTENSITY_RANGE, COLUMN_CAP_ROUNDNESS_RANGE, COLUMN_COUNT_RANGE, COLUMN_EDGE_SOFTNESS_RANGE, COLUMN_GLOW_RADIUS_RANGE, COLUMN_GLOW_STRENGTH_RANGE, COLUMN_WIDTH_RANGE, FREQUENCY_LIMIT_RANGE, GRADIENT_STOP_POSITION_RANGE, HDR_MULTIPLIER_RANGE, MAX_HEIGHT_RANGE, RADIUS_RANGE, ROTATION_SPEED_RANGE, SAMPLING_RATE_RANGE, SCALE_STRENGTH_RANGE, SCALE_THRESHOLD_RANGE, SECTION_COUNT_RANGE, SMOOTHING_RANGE_RANGE}, AdvancedSettings};
+use super::components::*;
 
+#[derive(SystemParam)]
+pub struct SettingsUiEventWriters<'w> {
+    audio_visualizer_spawn_event_writer: EventWriter<'w, AudioVisualizerSpawnEvent>,
+    preset_event_writer: EventWriter<'w, PresetEvent>,
+    history_event_writer: EventWriter<'w, HistoryEvent>
+}
+
+#[derive(SystemParam)]
+pub struct SettingsUiConnections<'w> {
+    osc_output_settings: ResMut<'w, OscOutputSettings>,
+    osc_sender: Res<'w, OscSender>,
+    osc_input_settings: ResMut<'w, OscInputSettings>,
+    osc_receiver: Res<'w, OscReceiver>,
+    midi_settings: ResMut<'w, MidiSettings>,
+    midi_status: Res<'w, MidiStatus>,
+    midi_learn_state: ResMut<'w, MidiLearnState>
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn settings_ui(
+    mut commands: Commands,
     mut contexts: EguiContexts,
-    mut audio_visualizer_restructure_event_writer: EventWriter<AudioVisualizerRestructureEvent>,
-    mut advanced_settings_change_event_writer: EventWriter<AdvancedSettingsChangeEvent>,
-    mut audio_visualizer_settings: ResMut<AudioVisualizerSettings>,
+    mut event_writers: SettingsUiEventWriters,
+    mut audio_visualizer_container_query: Query<(Entity, &mut AudioVisualizerSettings), With<AudioVisualizerContainer>>,
+    mut selected_audio_visualizer: ResMut<SelectedAudioVisualizer>,
     mut advanced_settings: ResMut<AdvancedSettings>,
     mut clear_color: ResMut<ClearColor>,
-    visualizer_type_state: Res<State<VisualilzerType>>,
-    mut visualizer_type_next_state: ResMut<NextState<VisualilzerType>>
+    preset_library: Res<PresetLibrary>,
+    mut preset_transition_settings: ResMut<PresetTransitionSettings>,
+    mut randomizer_locks: ResMut<RandomizerLocks>,
+    mut ui_state: ResMut<UiState>,
+    settings_history: Res<SettingsHistory>,
+    mut connections: SettingsUiConnections,
+    type_registry: Res<AppTypeRegistry>,
+    mut preset_browser_state: Local<PresetBrowserState>
 ) {
     egui::Window::new("Audio Visualizer Settings")
         .resizable(true)
         .vscroll(true)
         .max_width(265.0)
         .show(contexts.ctx_mut(), |ui| {
-            ui.label(RichText::new("Changes to values that are red need to be manually applied!").color(egui::Color32::RED).heading());
-            ui.add(widgets::Separator::default());
-
-            let mut visualizer_type_selection = visualizer_type_state.get();
             ui.horizontal(|ui| {
-                ui.label(RichText::new("Select visualizer type:").color(egui::Color32::YELLOW));
-                egui::ComboBox::from_id_source("visualizer-type")
-                    .selected_text(format!("{:?}", visualizer_type_selection))
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut visualizer_type_selection, &VisualilzerType::FrequencyVisualizer, "Frequency Visualizer");
-                        ui.selectable_value(&mut visualizer_type_selection, &VisualilzerType::SpectrumVisualizer, "Spectrum Visualizer");
-                    });
+                if ui.add_enabled(settings_history.can_undo(), egui::Button::new("Undo")).on_hover_text("Ctrl+Z").clicked() {
+                    event_writers.history_event_writer.send(HistoryEvent::Undo);
+                }
+                if ui.add_enabled(settings_history.can_redo(), egui::Button::new("Redo")).on_hover_text("Ctrl+Shift+Z").clicked() {
+                    event_writers.history_event_writer.send(HistoryEvent::Redo);
+                }
             });
-            visualizer_type_next_state.set(visualizer_type_selection.clone());
             ui.add(widgets::Separator::default());
 
-            ui.label(RichText::new("Frequencies Settings").color(egui::Color32::YELLOW).heading());
-            ui.label("Lower Frequency Limit:");
-            let upper_freq_limit = audio_visualizer_settings.upper_frequency_limit - audio_visualizer_settings.column_count_power_of_two as f32;
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.lower_frequency_limit, 0.0..=upper_freq_limit));
-            
-            ui.label("Upper Frequency Limit:");
-            let lower_freq_limit = audio_visualizer_settings.lower_frequency_limit + audio_visualizer_settings.column_count_power_of_two as f32;
-            let upper_freq_limit_max = (audio_visualizer_settings.sampling_rate / 2) as f32;
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.upper_frequency_limit, lower_freq_limit..=upper_freq_limit_max));
-            
-            ui.label("Sampling rate:");
-            let upper_freq_limit = (audio_visualizer_settings.upper_frequency_limit * 2.0 + 1.0) as u32;
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.sampling_rate, upper_freq_limit..=44100));
-
-            let window_selection = audio_visualizer_settings.window_function;
+            let selected_index = audio_visualizer_container_query.iter()
+                .position(|(entity, _)| Some(entity) == selected_audio_visualizer.0);
             ui.horizontal(|ui| {
-                ui.label("Select window function:");
-                egui::ComboBox::from_id_source("window-selection")
-                    .selected_text(format!("{:?}", window_selection))
+                ui.label(RichText::new("Select visualizer:").color(egui::Color32::YELLOW));
+                egui::ComboBox::from_id_source("visualizer-instance")
+                    .selected_text(selected_index.map_or("None".to_string(), |i| format!("Visualizer {}", i + 1)))
                     .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut audio_visualizer_settings.window_function, WindowFunction::None, "None");
-                        ui.selectable_value(&mut audio_visualizer_settings.window_function, WindowFunction::Hann, "Hann Window");
-                        ui.selectable_value(&mut audio_visualizer_settings.window_function, WindowFunction::Hamming, "Hamming Window");
+                        for (i, (entity, _)) in audio_visualizer_container_query.iter().enumerate() {
+                            ui.selectable_value(&mut selected_audio_visualizer.0, Some(entity), format!("Visualizer {}", i + 1));
+                        }
                     });
             });
+            ui.horizontal(|ui| {
+                if ui.button("Add").clicked() {
+                    let audio_visualizer_settings = selected_audio_visualizer.0
+                        .and_then(|entity| audio_visualizer_container_query.get(entity).ok())
+                        .map_or_else(AudioVisualizerSettings::default, |(_, audio_visualizer_settings)| audio_visualizer_settings.clone());
+                    event_writers.audio_visualizer_spawn_event_writer.send(AudioVisualizerSpawnEvent(audio_visualizer_settings));
+                }
+                if ui.add_enabled(selected_index.is_some(), egui::Button::new("Remove")).clicked() {
+                    commands.entity(selected_audio_visualizer.0.unwrap()).despawn_recursive();
+                    selected_audio_visualizer.0 = None;
+                }
+            });
             ui.add(widgets::Separator::default());
 
-            ui.label(RichText::new("Wheel Settings").color(egui::Color32::YELLOW).heading());
-            ui.label("Radius");
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.radius, 0.0..=1000.0));
+            preset_browser_ui(ui, &preset_library, &mut preset_transition_settings, &mut randomizer_locks, &mut preset_browser_state, &mut event_writers.preset_event_writer);
 
-            ui.label(RichText::new("Column Count (2 to the power of):").color(egui::Color32::RED));
-            ui.horizontal(|ui| {
-                ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_count_power_of_two, 5..=10));
-                ui.label(format!("= {}", 2_u32.pow(audio_visualizer_settings.column_count_power_of_two as u32) as usize));
+            if let Some(Ok((_, mut audio_visualizer_settings))) = selected_audio_visualizer.0.map(|entity| audio_visualizer_container_query.get_mut(entity)) {
+                // Only marks the settings as changed when they were edited, so systems filtering on changes don't run every frame the window is open
+                let previous_audio_visualizer_settings = audio_visualizer_settings.clone();
+                audio_visualizer_settings_ui(ui, audio_visualizer_settings.bypass_change_detection());
+
+                egui::CollapsingHeader::new(RichText::new("All Settings").strong()).show(ui, |ui| {
+                    reflect_inspector::ui_for_value(audio_visualizer_settings.bypass_change_detection(), ui, &type_registry.read());
+                });
+                if *audio_visualizer_settings != previous_audio_visualizer_settings {
+                    audio_visualizer_settings.set_changed();
+                }
+                ui.add(widgets::Separator::default());
+            }
+
+            ui.label(RichText::new("Background Color").strong());
+            let mut clear_color_rgba = clear_color.as_rgba_f32();
+            ui.color_edit_button_rgba_unmultiplied(&mut clear_color_rgba);
+            clear_color.reborrow().map_unchanged(|clear_color| &mut clear_color.0).set_if_neq(Color::rgba_from_array(clear_color_rgba));
+
+            ui.add(widgets::Separator::default());
+
+            ui.label(RichText::new("Advanced Settings").color(egui::Color32::YELLOW).heading());
+
+            if ui.checkbox(&mut advanced_settings.bypass_change_detection().show_fps, "Show FPS").changed()
+                | ui.checkbox(&mut advanced_settings.bypass_change_detection().vsync, "VSync").changed() {
+                advanced_settings.set_changed();
+            }
+            ui.checkbox(&mut ui_state.show_inspector, "Show Inspector");
+
+            egui::CollapsingHeader::new(RichText::new("OSC Output").strong()).show(ui, |ui| {
+                osc_output_ui(ui, &mut connections.osc_output_settings, &connections.osc_sender);
+            });
+            egui::CollapsingHeader::new(RichText::new("OSC Input").strong()).show(ui, |ui| {
+                osc_input_ui(ui, &mut connections.osc_input_settings, &connections.osc_receiver);
+            });
+            egui::CollapsingHeader::new(RichText::new("MIDI").strong()).show(ui, |ui| {
+                midi_ui(ui, &mut connections.midi_settings, &connections.midi_status, &mut connections.midi_learn_state, &preset_library);
             });
 
-            ui.label(RichText::new("Column Width:").color(egui::Color32::RED));
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_width, 1.0..=20.0));
+        });
+}
 
-            ui.label("Max Height");
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.max_height, 2.0..=1000.0));
+pub fn update_ui_input_state(
+    mut contexts: EguiContexts,
+    mut ui_input_state: ResMut<UiInputState>
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
 
-            ui.label("Section Count");
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.section_count, 1..=10));
+    ui_input_state.set_if_neq(UiInputState {
+        wants_keyboard_input: ctx.wants_keyboard_input(),
+        pointer_busy: ctx.dragged_id().is_some() || ctx.input(|input| input.pointer.any_down())
+    });
+}
 
-            ui.label("Rotation Speed");
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.rotation_speed, -0.500..=0.500));
+pub fn config_error_overlay(
+    mut contexts: EguiContexts,
+    config_file: Option<Res<ConfigFile>>,
+    preset_library: Res<PresetLibrary>
+) {
+    let config_file_error = config_file.and_then(|config_file| config_file.reload_error.clone());
+    if config_file_error.is_none() && preset_library.error.is_none() {
+        return;
+    }
 
-            ui.label("Scale Strength");
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.scale_strenght, 0.0..=20000.0));
+    egui::Area::new(egui::Id::new("config-error-overlay"))
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+        .interactable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                for error in [&config_file_error, &preset_library.error].into_iter().flatten() {
+                    ui.label(RichText::new(error).color(egui::Color32::RED));
+                }
+            });
+        });
+}
 
-            ui.label("Scale Threshold");
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.scale_threshold, 0.55..=10.0));
+fn audio_visualizer_settings_ui(
+    ui: &mut egui::Ui,
+    audio_visualizer_settings: &mut AudioVisualizerSettings
+) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Select visualizer type:").color(egui::Color32::YELLOW));
+        egui::ComboBox::from_id_source("visualizer-type")
+            .selected_text(format!("{:?}", audio_visualizer_settings.visualizer_type))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut audio_visualizer_settings.visualizer_type, VisualilzerType::FrequencyVisualizer, "Frequency Visualizer");
+                ui.selectable_value(&mut audio_visualizer_settings.visualizer_type, VisualilzerType::SpectrumVisualizer, "Spectrum Visualizer");
+            });
+    });
+    ui.add(widgets::Separator::default());
 
-            ui.label("Smoothing Range");
-            let half_of_column_count = audio_visualizer_settings.column_count / 2;
-            ui.add(widgets::Slider::new(&mut audio_visualizer_settings.smoothing_range, 1..=half_of_column_count));
+    ui.label(RichText::new("Frequencies Settings").color(egui::Color32::YELLOW).heading());
+    ui.label("Lower Frequency Limit:");
+    let minimum_frequency_range = minimum_frequency_range(audio_visualizer_settings.sampling_rate);
+    let upper_freq_limit = audio_visualizer_settings.upper_frequency_limit - minimum_frequency_range;
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.lower_frequency_limit, *FREQUENCY_LIMIT_RANGE.start()..=upper_freq_limit));
+    
+    ui.label("Upper Frequency Limit:");
+    let lower_freq_limit = audio_visualizer_settings.lower_frequency_limit + minimum_frequency_range;
+    let upper_freq_limit_max = (audio_visualizer_settings.sampling_rate / 2) as f32;
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.upper_frequency_limit, lower_freq_limit..=upper_freq_limit_max));
+    
+    ui.label("Sampling rate:");
+    let upper_freq_limit = (audio_visualizer_settings.upper_frequency_limit * 2.0 + 1.0) as u32;
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.sampling_rate, upper_freq_limit..=*SAMPLING_RATE_RANGE.end()));
 
-            ui.add(widgets::Separator::default());
+    let window_selection = audio_visualizer_settings.window_function;
+    ui.horizontal(|ui| {
+        ui.label("Select window function:");
+        egui::ComboBox::from_id_source("window-selection")
+            .selected_text(format!("{:?}", window_selection))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut audio_visualizer_settings.window_function, WindowFunction::None, "None");
+                ui.selectable_value(&mut audio_visualizer_settings.window_function, WindowFunction::Hann, "Hann Window");
+                ui.selectable_value(&mut audio_visualizer_settings.window_function, WindowFunction::Hamming, "Hamming Window");
+            });
+    });
+    ui.add(widgets::Separator::default());
+
+    ui.label(RichText::new("Wheel Settings").color(egui::Color32::YELLOW).heading());
+    ui.label("Position");
+    ui.horizontal(|ui| {
+        ui.add(widgets::DragValue::new(&mut audio_visualizer_settings.position.x).prefix("x: "));
+        ui.add(widgets::DragValue::new(&mut audio_visualizer_settings.position.y).prefix("y: "));
+    });
+
+    ui.label("Radius");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.radius, RADIUS_RANGE));
+
+    ui.label("Column Count:");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_count, COLUMN_COUNT_RANGE).logarithmic(true));
+
+    ui.label("Column Width:");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_width, COLUMN_WIDTH_RANGE));
+
+    ui.label("Max Height");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.max_height, MAX_HEIGHT_RANGE));
+
+    ui.label("Section Count");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.section_count, SECTION_COUNT_RANGE));
+
+    ui.label("Rotation Speed");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.rotation_speed, ROTATION_SPEED_RANGE));
+
+    ui.label("Scale Strength");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.scale_strenght, SCALE_STRENGTH_RANGE));
+
+    ui.label("Scale Threshold");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.scale_threshold, SCALE_THRESHOLD_RANGE));
+
+    ui.label("Smoothing Range");
+    let half_of_column_count = audio_visualizer_settings.column_count / 2;
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.smoothing_range, *SMOOTHING_RANGE_RANGE.start()..=half_of_column_count));
+
+    ui.add(widgets::Separator::default());
 
-            ui.label(RichText::new("Color Settings").color(egui::Color32::YELLOW).heading());
+    ui.label(RichText::new("Color Settings").color(egui::Color32::YELLOW).heading());
 
-            ui.label(RichText::new("Normal Color").strong());
-            (audio_visualizer_settings.normal_primary_color, 
-            audio_visualizer_settings.normal_secondary_color, 
+    let color_mode_selection = audio_visualizer_settings.color_mode;
+    ui.horizontal(|ui| {
+        ui.label("Select color mode:");
+        egui::ComboBox::from_id_source("color-mode-selection")
+            .selected_text(format!("{:?}", color_mode_selection))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut audio_visualizer_settings.color_mode, ColorMode::Solid, "Solid");
+                ui.selectable_value(&mut audio_visualizer_settings.color_mode, ColorMode::Frequency, "Frequency Gradient");
+                ui.selectable_value(&mut audio_visualizer_settings.color_mode, ColorMode::Height, "Height Gradient");
+                ui.selectable_value(&mut audio_visualizer_settings.color_mode, ColorMode::FrequencyAndHeight, "Frequency and Height Gradient");
+            });
+    });
+
+    if audio_visualizer_settings.color_mode == ColorMode::Solid {
+        ui.label(RichText::new("Normal Color").strong());
+        (audio_visualizer_settings.normal_primary_color, 
+        audio_visualizer_settings.normal_secondary_color, 
+        audio_visualizer_settings.normal_primary_color_hdr_multiplier,
+        audio_visualizer_settings.normal_secondary_color_hdr_multiplier,
+        audio_visualizer_settings.normal_color_transition_enabled,
+        audio_visualizer_settings.normal_color_transition_speed) = update_color_material(
+            ui,
+            audio_visualizer_settings.normal_primary_color,
+            audio_visualizer_settings.normal_secondary_color,
             audio_visualizer_settings.normal_primary_color_hdr_multiplier,
             audio_visualizer_settings.normal_secondary_color_hdr_multiplier,
             audio_visualizer_settings.normal_color_transition_enabled,
-            audio_visualizer_settings.normal_color_transition_speed) = update_color_material(
-                ui,
-                audio_visualizer_settings.normal_primary_color,
-                audio_visualizer_settings.normal_secondary_color,
-                audio_visualizer_settings.normal_primary_color_hdr_multiplier,
-                audio_visualizer_settings.normal_secondary_color_hdr_multiplier,
-                audio_visualizer_settings.normal_color_transition_enabled,
-                audio_visualizer_settings.normal_color_transition_speed
-            );
+            audio_visualizer_settings.normal_color_transition_speed
+        );
 
-            ui.add(widgets::Separator::default());
+        ui.add(widgets::Separator::default());
 
-            ui.label(RichText::new("Highlight Color").strong());
-            ui.vertical(|ui| {
-                (audio_visualizer_settings.highlight_primary_color, 
-                audio_visualizer_settings.highlight_secondary_color, 
+        ui.label(RichText::new("Highlight Color").strong());
+        ui.vertical(|ui| {
+            (audio_visualizer_settings.highlight_primary_color, 
+            audio_visualizer_settings.highlight_secondary_color, 
+            audio_visualizer_settings.highlight_primary_color_hdr_multiplier,
+            audio_visualizer_settings.highlight_secondary_color_hdr_multiplier,
+            audio_visualizer_settings.highlight_color_transition_enabled,
+            audio_visualizer_settings.highlight_color_transition_speed) = update_color_material(
+                ui,
+                audio_visualizer_settings.highlight_primary_color,
+                audio_visualizer_settings.highlight_secondary_color,
                 audio_visualizer_settings.highlight_primary_color_hdr_multiplier,
                 audio_visualizer_settings.highlight_secondary_color_hdr_multiplier,
                 audio_visualizer_settings.highlight_color_transition_enabled,
-                audio_visualizer_settings.highlight_color_transition_speed) = update_color_material(
-                    ui,
-                    audio_visualizer_settings.highlight_primary_color,
-                    audio_visualizer_settings.highlight_secondary_color,
-                    audio_visualizer_settings.highlight_primary_color_hdr_multiplier,
-                    audio_visualizer_settings.highlight_secondary_color_hdr_multiplier,
-                    audio_visualizer_settings.highlight_color_transition_enabled,
-                    audio_visualizer_settings.highlight_color_transition_speed
-                );
-            });
+                audio_visualizer_settings.highlight_color_transition_speed
+            );
+        });
+    }
 
-            ui.add(widgets::Separator::default());
+    if matches!(audio_visualizer_settings.color_mode, ColorMode::Frequency | ColorMode::FrequencyAndHeight) {
+        ui.label(RichText::new("Frequency Gradient").strong());
+        ui.push_id("frequency-gradient", |ui| update_color_gradient(ui, &mut audio_visualizer_settings.frequency_gradient));
+    }
 
-            ui.label(RichText::new("Background Color").strong());
-            let mut clear_color_rgba = clear_color.as_rgba_f32();
-            ui.color_edit_button_rgba_unmultiplied(&mut clear_color_rgba);
-            *clear_color = ClearColor(Color::rgba_from_array(clear_color_rgba));
+    if matches!(audio_visualizer_settings.color_mode, ColorMode::Height | ColorMode::FrequencyAndHeight) {
+        ui.label(RichText::new("Height Gradient").strong());
+        ui.push_id("height-gradient", |ui| update_color_gradient(ui, &mut audio_visualizer_settings.height_gradient));
+        ui.checkbox(&mut audio_visualizer_settings.height_gradient_along_column, "Gradient Along Column Height");
+    }
 
-            ui.add(widgets::Separator::default());
+    ui.add(widgets::Separator::default());
 
-            ui.label(RichText::new("Advanced Settings").color(egui::Color32::YELLOW).heading());
+    ui.label(RichText::new("Column Style").strong());
+    ui.label("Cap Roundness");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_cap_roundness, COLUMN_CAP_ROUNDNESS_RANGE));
 
-            ui.checkbox(&mut advanced_settings.show_fps, RichText::new("Show FPS").color(Color32::RED));
-            ui.checkbox(&mut advanced_settings.vsync, RichText::new("VSync").color(Color32::RED));
+    ui.label("Edge Softness");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_edge_softness, COLUMN_EDGE_SOFTNESS_RANGE));
 
-            ui.horizontal(|ui| {
-                ui.label(RichText::new("Apply changes:").color(egui::Color32::RED));
-                if ui.button("Apply").clicked() {
-                    advanced_settings_change_event_writer.send(AdvancedSettingsChangeEvent);
-                    audio_visualizer_restructure_event_writer.send(AudioVisualizerRestructureEvent);
+    ui.label("Glow Radius");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_glow_radius, COLUMN_GLOW_RADIUS_RANGE));
+
+    ui.label("Glow Strength");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_glow_strength, COLUMN_GLOW_STRENGTH_RANGE));
+
+    ui.label("Base Intensity");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.column_base_intensity, COLUMN_BASE_INTENSITY_RANGE));
+
+    ui.label("Amplitude Intensity Strength");
+    ui.add(widgets::Slider::new(&mut audio_visualizer_settings.amplitude_intensity_strength, AMPLITUDE_INTENSITY_STRENGTH_RANGE));
+
+    ui.add(widgets::Separator::default());
+}
+
+fn osc_output_ui(
+    ui: &mut egui::Ui,
+    osc_output_settings: &mut ResMut<OscOutputSettings>,
+    osc_sender: &OscSender
+) {
+    let mut edited_osc_output_settings = (**osc_output_settings).clone();
+
+    ui.checkbox(&mut edited_osc_output_settings.enabled, "Enabled");
+    ui.horizontal(|ui| {
+        ui.label("Host:");
+        ui.add(egui::TextEdit::singleline(&mut edited_osc_output_settings.host).desired_width(100.0));
+        ui.label("Port:");
+        ui.add(widgets::DragValue::new(&mut edited_osc_output_settings.port));
+    });
+    ui.label("Max Rate (0 is unlimited)");
+    ui.add(widgets::Slider::new(&mut edited_osc_output_settings.max_rate, 0.0..=120.0).suffix(" Hz"));
+
+    ui.label("Addresses (empty to disable)");
+    egui::Grid::new("osc-output-addresses").show(ui, |ui| {
+        for (label, address) in [
+            ("Average", &mut edited_osc_output_settings.average_address),
+            ("Columns", &mut edited_osc_output_settings.columns_address),
+            ("Bands", &mut edited_osc_output_settings.bands_address),
+            ("Beats", &mut edited_osc_output_settings.beats_address)
+        ] {
+            ui.label(label);
+            ui.text_edit_singleline(address);
+            ui.end_row();
+        }
+    });
+
+    if let Some(error) = &osc_sender.error {
+        ui.label(RichText::new(error).color(egui::Color32::RED));
+    }
+
+    osc_output_settings.set_if_neq(edited_osc_output_settings);
+}
+
+fn osc_input_ui(
+    ui: &mut egui::Ui,
+    osc_input_settings: &mut ResMut<OscInputSettings>,
+    osc_receiver: &OscReceiver
+) {
+    let mut edited_osc_input_settings = (**osc_input_settings).clone();
+
+    ui.checkbox(&mut edited_osc_input_settings.enabled, "Enabled");
+    ui.horizontal(|ui| {
+        ui.label("Host:");
+        ui.add(egui::TextEdit::singleline(&mut edited_osc_input_settings.host).desired_width(100.0));
+        ui.label("Port:");
+        ui.add(widgets::DragValue::new(&mut edited_osc_input_settings.port));
+    });
+
+    ui.label("Mappings (0..1 when normalized)");
+    egui::Grid::new("osc-input-mappings").show(ui, |ui| {
+        for mapping in edited_osc_input_settings.mappings.iter_mut() {
+            ui.label(format!("{:?}", mapping.target));
+            ui.add(egui::TextEdit::singleline(&mut mapping.address).desired_width(140.0));
+            ui.add_enabled(mapping.target.range().is_some(), egui::Checkbox::without_text(&mut mapping.normalized));
+            ui.end_row();
+        }
+    });
+
+    if let Some(error) = &osc_receiver.error {
+        ui.label(RichText::new(error).color(egui::Color32::RED));
+    }
+
+    osc_input_settings.set_if_neq(edited_osc_input_settings);
+}
+
+fn midi_ui(
+    ui: &mut egui::Ui,
+    midi_settings: &mut ResMut<MidiSettings>,
+    midi_status: &MidiStatus,
+    midi_learn_state: &mut MidiLearnState,
+    preset_library: &PresetLibrary
+) {
+    let mut edited_midi_settings = (**midi_settings).clone();
+
+    ui.checkbox(&mut edited_midi_settings.enabled, "Enabled");
+    if !midi_status.connected_ports.is_empty() {
+        ui.label(format!("Connected: {}", midi_status.connected_ports.join(", ")));
+    }
+
+    ui.label("Controls (click Learn, then turn a knob)");
+    egui::Grid::new("midi-control-bindings").show(ui, |ui| {
+        for target in MidiControlTarget::ALL {
+            let binding_index = edited_midi_settings.control_bindings.iter().position(|binding| binding.target == target);
+            let learning = midi_learn_state.learning == Some(MidiLearnTarget::Control(target));
+
+            ui.label(format!("{:?}", target));
+            ui.label(binding_index.map_or("-".to_string(), |i| {
+                let MidiControlBinding { channel, controller, .. } = edited_midi_settings.control_bindings[i];
+                format!("Ch {} CC {}", channel + 1, controller)
+            }));
+            if ui.selectable_label(learning, if learning { "Turn a knob..." } else { "Learn" }).clicked() {
+                midi_learn_state.learning = if learning { None } else { Some(MidiLearnTarget::Control(target)) };
+            }
+            if ui.add_enabled(binding_index.is_some(), egui::Button::new("Clear")).clicked() {
+                edited_midi_settings.control_bindings.remove(binding_index.unwrap());
+            }
+            ui.end_row();
+        }
+    });
+
+    ui.label("Preset Notes");
+    let mut removed_note_binding = None;
+    egui::Grid::new("midi-note-bindings").show(ui, |ui| {
+        for (i, binding) in edited_midi_settings.note_bindings.iter().enumerate() {
+            ui.label(format!("Ch {} {}", binding.channel + 1, midi_note_name(binding.note)));
+            ui.label(&binding.preset);
+            if ui.button("Remove").clicked() {
+                removed_note_binding = Some(i);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(i) = removed_note_binding {
+        edited_midi_settings.note_bindings.remove(i);
+    }
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("midi-note-preset")
+            .selected_text(midi_learn_state.note_preset.clone().unwrap_or_else(|| "Select preset".to_string()))
+            .show_ui(ui, |ui| {
+                for preset in &preset_library.presets {
+                    ui.selectable_value(&mut midi_learn_state.note_preset, Some(preset.name.clone()), &preset.name);
                 }
             });
+
+        let learn_target = midi_learn_state.note_preset.clone().map(MidiLearnTarget::Note);
+        let learning = learn_target.is_some() && midi_learn_state.learning == learn_target;
+        if ui.add_enabled(learn_target.is_some(), egui::SelectableLabel::new(learning, if learning { "Play a note..." } else { "Learn Note" })).clicked() {
+            midi_learn_state.learning = if learning { None } else { learn_target };
+        }
+    });
+
+    if let Some(error) = &midi_status.error {
+        ui.label(RichText::new(error).color(egui::Color32::RED));
+    }
+
+    midi_settings.set_if_neq(edited_midi_settings);
+}
+
+fn midi_note_name(
+    note: u8
+) -> String {
+    const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    format!("{}{}", NOTE_NAMES[note as usize % 12], note as i32 / 12 - 1)
+}
+
+fn preset_browser_ui(
+    ui: &mut egui::Ui,
+    preset_library: &PresetLibrary,
+    preset_transition_settings: &mut PresetTransitionSettings,
+    randomizer_locks: &mut RandomizerLocks,
+    preset_browser_state: &mut PresetBrowserState,
+    preset_event_writer: &mut EventWriter<PresetEvent>
+) {
+    ui.label(RichText::new("Presets").color(egui::Color32::YELLOW).heading());
+    if let Some(active_preset) = &preset_library.active_preset {
+        ui.label(format!("Active: {}", active_preset));
+    }
+
+    egui::ScrollArea::vertical()
+        .id_source("preset-list")
+        .max_height(150.0)
+        .show(ui, |ui| {
+            for preset in &preset_library.presets {
+                ui.horizontal(|ui| {
+                    let selected = preset_browser_state.selected_preset.as_ref() == Some(&preset.name);
+                    if ui.selectable_label(selected, &preset.name).clicked() {
+                        preset_browser_state.selected_preset = Some(preset.name.clone());
+                        preset_browser_state.preset_name = preset.name.clone();
+                        preset_event_writer.send(PresetEvent::Load(preset.name.clone()));
+                    }
+                    if preset.is_built_in() {
+                        ui.label(RichText::new("built-in").weak());
+                    }
+                });
+            }
         });
+
+    ui.horizontal(|ui| {
+        ui.label("Name:");
+        ui.text_edit_singleline(&mut preset_browser_state.preset_name);
+    });
+
+    let selected_preset = preset_browser_state.selected_preset.as_ref().and_then(|name| preset_library.get(name));
+    let selected_user_preset = selected_preset.is_some_and(|preset| !preset.is_built_in());
+    ui.horizontal(|ui| {
+        if ui.button("Save").clicked() {
+            preset_browser_state.selected_preset = Some(preset_browser_state.preset_name.trim().to_string());
+            preset_event_writer.send(PresetEvent::Save(preset_browser_state.preset_name.clone()));
+        }
+        if ui.add_enabled(selected_preset.is_some(), egui::Button::new("Duplicate")).clicked() {
+            preset_event_writer.send(PresetEvent::Duplicate(selected_preset.unwrap().name.clone()));
+        }
+        if ui.add_enabled(selected_user_preset, egui::Button::new("Rename")).clicked() {
+            preset_event_writer.send(PresetEvent::Rename(selected_preset.unwrap().name.clone(), preset_browser_state.preset_name.clone()));
+            preset_browser_state.selected_preset = Some(preset_browser_state.preset_name.trim().to_string());
+        }
+        if ui.add_enabled(selected_user_preset, egui::Button::new("Delete")).clicked() {
+            preset_event_writer.send(PresetEvent::Delete(selected_preset.unwrap().name.clone()));
+            preset_browser_state.selected_preset = None;
+        }
+        if ui.button("Refresh").clicked() {
+            preset_event_writer.send(PresetEvent::Refresh);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Randomize").clicked() {
+            preset_event_writer.send(PresetEvent::Randomize);
+        }
+        ui.label("Keep:");
+    });
+    ui.horizontal_wrapped(|ui| {
+        ui.checkbox(&mut randomizer_locks.frequencies, "Frequencies");
+        ui.checkbox(&mut randomizer_locks.geometry, "Geometry");
+        ui.checkbox(&mut randomizer_locks.colors, "Colors");
+        ui.checkbox(&mut randomizer_locks.column_style, "Column Style");
+    });
+
+    ui.label("Transition Duration");
+    ui.add(widgets::Slider::new(&mut preset_transition_settings.duration, 0.0..=10.0).suffix(" s"));
+
+    let easing_selection = preset_transition_settings.easing;
+    ui.horizontal(|ui| {
+        ui.label("Select easing:");
+        egui::ComboBox::from_id_source("easing-selection")
+            .selected_text(format!("{:?}", easing_selection))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut preset_transition_settings.easing, Easing::Linear, "Linear");
+                ui.selectable_value(&mut preset_transition_settings.easing, Easing::EaseIn, "Ease In");
+                ui.selectable_value(&mut preset_transition_settings.easing, Easing::EaseOut, "Ease Out");
+                ui.selectable_value(&mut preset_transition_settings.easing, Easing::EaseInOut, "Ease In Out");
+            });
+    });
+
+    ui.add(widgets::Separator::default());
 }
 
 fn update_color_material(
@@ -172,7 +587,7 @@ fn update_color_material(
         primary_color = Color::rgba_from_array(primary_color_rgba);
     });
     ui.label("HDR Multiplier:");
-    ui.add(widgets::Slider::new(&mut primary_color_hdr_multiplier, 1.0..=10.0));
+    ui.add(widgets::Slider::new(&mut primary_color_hdr_multiplier, HDR_MULTIPLIER_RANGE));
 
     ui.checkbox(&mut transition_enabled, "Enable Transition");
 
@@ -184,18 +599,50 @@ fn update_color_material(
             secondary_color = Color::rgba_from_array(secondary_color_rgba);
         });
         ui.label("HDR Multiplier:");
-        ui.add(widgets::Slider::new(&mut secondary_color_hdr_multiplier, 1.0..=10.0));
+        ui.add(widgets::Slider::new(&mut secondary_color_hdr_multiplier, HDR_MULTIPLIER_RANGE));
         ui.label("Transition Speed:");
         let mut was_negative = false;
         if transition_speed < 0.0 {
             transition_speed *= -1.0;
             was_negative = true;
         }
-        ui.add(widgets::Slider::new(&mut transition_speed, 0.0001..=0.5));
+        ui.add(widgets::Slider::new(&mut transition_speed, 0.0001..=*COLOR_TRANSITION_SPEED_RANGE.end()));
         if was_negative {
             transition_speed *= -1.0;
         }
     });
 
     (primary_color, secondary_color, primary_color_hdr_multiplier, secondary_color_hdr_multiplier, transition_enabled, transition_speed)
-}
\ No newline at end of file
+}
+
+fn update_color_gradient(
+    ui: &mut egui::Ui,
+    color_gradient: &mut ColorGradient
+) {
+    let mut removed_stop = None;
+    for (i, stop) in color_gradient.stops.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                let mut color_rgba = stop.color.as_rgba_f32();
+                ui.color_edit_button_rgba_unmultiplied(&mut color_rgba);
+                stop.color = Color::rgba_from_array(color_rgba);
+                ui.add(widgets::Slider::new(&mut stop.position, GRADIENT_STOP_POSITION_RANGE).text("Position"));
+                if ui.button("Remove").clicked() {
+                    removed_stop = Some(i);
+                }
+            });
+            ui.add(widgets::Slider::new(&mut stop.hdr_multiplier, HDR_MULTIPLIER_RANGE).text("HDR Multiplier"));
+        });
+    }
+
+    if let Some(i) = removed_stop {
+        color_gradient.stops.remove(i);
+    }
+    if ui.button("Add Stop").clicked() {
+        color_gradient.stops.push(ColorGradientStop::default());
+    }
+
+    if ui.ctx().dragged_id().is_none() {
+        color_gradient.stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+    }
+}
@@ -0,0 +1,273 @@
+use crate::audio_data::SPECTRUM_DATA_LENGTH;
+use spectrum_analyzer::{samples_fft_to_spectrum, scaling::scale_to_zero_to_one, windows::{hamming_window, hann_window}, FrequencyLimit};
+use super::components::*;
+
+#[derive(Clone)]
+pub struct AudioAnalysisFrame {
+    pub values: Vec<f32>,
+    pub highlighted: Vec<bool>,
+    pub average_value: f32
+}
+
+pub fn analyze_audio(
+    samples: &[f32],
+    audio_visualizer_settings: &AudioVisualizerSettings,
+    column_count: usize
+) -> Option<AudioAnalysisFrame> {
+    if samples.len() != audio_visualizer_settings.spectrum_data_length {
+        return None;
+    }
+
+    match audio_visualizer_settings.visualizer_type {
+        VisualilzerType::FrequencyVisualizer => analyze_audio_frequency(samples, audio_visualizer_settings, column_count),
+        VisualilzerType::SpectrumVisualizer => Some(analyze_audio_spectrum(samples, audio_visualizer_settings, column_count))
+    }
+}
+
+fn analyze_audio_frequency(
+    samples: &[f32],
+    audio_visualizer_settings: &AudioVisualizerSettings,
+    column_count: usize
+) -> Option<AudioAnalysisFrame> {
+    let mut spectrum_data: [f32; SPECTRUM_DATA_LENGTH] = samples.try_into().unwrap();
+
+    match audio_visualizer_settings.window_function {
+        WindowFunction::Hann => {
+            spectrum_data = hann_window(&spectrum_data).try_into().unwrap();
+        },
+        WindowFunction::Hamming => {
+            spectrum_data = hamming_window(&spectrum_data).try_into().unwrap();
+        },
+        _ => {}
+    }
+
+    let frequencies = samples_fft_to_spectrum(
+        &spectrum_data,
+        audio_visualizer_settings.sampling_rate,
+        FrequencyLimit::Range(audio_visualizer_settings.lower_frequency_limit, audio_visualizer_settings.upper_frequency_limit),
+        Some(&scale_to_zero_to_one),
+    ).ok()?;
+
+    let frequencies_data = frequencies.data();
+    let frequencies_len = frequencies_data.len();
+    let mut combined_frequencies = vec![0.0; column_count];
+
+    if frequencies_len >= column_count {
+        for (i, freq) in combined_frequencies.iter_mut().enumerate() {
+            let start_index = i * frequencies_len / column_count;
+            let end_index = (i + 1) * frequencies_len / column_count;
+            *freq = frequencies_data[start_index..end_index].iter().map(|(_, val)| val.val()).sum::<f32>() / (end_index - start_index) as f32;
+        }
+    } else {
+        let columns_per_frequency = column_count / frequencies_len;
+        let extra_columns = column_count % frequencies_len;
+        let mut current_column = 0;
+
+        for (i, (_, freq)) in frequencies_data.iter().enumerate() {
+            let count = if i < extra_columns { columns_per_frequency + 1 } else { columns_per_frequency };
+            for _ in 0..count {
+                combined_frequencies[current_column] = freq.val();
+                current_column += 1;
+            }
+        }
+    }
+
+    let section_count = audio_visualizer_settings.section_count.clamp(1, column_count);
+    let section_length = column_count / section_count;
+    let smoothing_range = audio_visualizer_settings.smoothing_range.max(1) as i32;
+    let half_smoothing_range = smoothing_range / 2;
+    let max_frequency_val = frequencies.max().1.val();
+    let highlighted_frequency_threshold = frequencies.average().val() * 2.0;
+
+    let mut values = vec![0.0; column_count];
+    let mut highlighted = vec![false; column_count];
+
+    for i in 0..column_count {
+        let section = i * section_count / column_count;
+        let section_start = section * column_count / section_count;
+        let section_end = (section + 1) * column_count / section_count;
+        let section_index = (i - section_start) * section_length / (section_end - section_start);
+
+        if section == 0 {
+            let mut smooth_frequency = 0.0;
+            let mut index = (section_index as i32 - half_smoothing_range).rem_euclid(section_length as i32);
+            for _ in 0..smoothing_range {
+                smooth_frequency += combined_frequencies[index as usize];
+                index = (index + 1) % section_length as i32;
+            }
+            combined_frequencies[section_index] = smooth_frequency / smoothing_range as f32;
+        }
+
+        values[i] = finite_or_zero(combined_frequencies[section_index] / max_frequency_val);
+        highlighted[i] = combined_frequencies[section_index] > highlighted_frequency_threshold;
+    }
+
+    Some(AudioAnalysisFrame {
+        values,
+        highlighted,
+        average_value: frequencies.average().val()
+    })
+}
+
+fn analyze_audio_spectrum(
+    samples: &[f32],
+    audio_visualizer_settings: &AudioVisualizerSettings,
+    column_count: usize
+) -> AudioAnalysisFrame {
+    let spectrum_data_len = samples.len();
+    let smoothing_range = audio_visualizer_settings.smoothing_range.max(1) as i32;
+
+    let mut highest_spectrum_value = 0.0;
+    let mut combined_spectrums = vec![0.0; column_count];
+    for (i, combined_spectrum) in combined_spectrums.iter_mut().enumerate() {
+        let start_index = i * spectrum_data_len / column_count;
+        let end_index = ((i + 1) * spectrum_data_len / column_count).max(start_index + 1);
+        for sample in &samples[start_index..end_index] {
+            *combined_spectrum += sample;
+            if *sample > highest_spectrum_value {
+                highest_spectrum_value = *sample;
+            }
+        }
+        *combined_spectrum /= (end_index - start_index) as f32;
+    }
+
+    let mut values = vec![0.0; column_count];
+    let mut highlighted = vec![false; column_count];
+
+    for i in 0..column_count {
+        let mut smooth_spectrum = 0.0;
+        let mut index = (i as i32 - smoothing_range / 2).rem_euclid(column_count as i32);
+        for _ in 0..smoothing_range {
+            smooth_spectrum += combined_spectrums[index as usize];
+            index = (index + 1) % column_count as i32;
+        }
+        combined_spectrums[i] = smooth_spectrum / smoothing_range as f32;
+
+        values[i] = finite_or_zero(combined_spectrums[i] / highest_spectrum_value);
+        highlighted[i] = combined_spectrums[i] > 0.0;
+    }
+
+    let average_value = values.iter().sum::<f32>() / column_count as f32;
+
+    AudioAnalysisFrame {
+        values,
+        highlighted,
+        average_value
+    }
+}
+
+fn finite_or_zero(value: f32) -> f32 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    fn sine_samples(frequency: f32, sampling_rate: u32) -> Vec<f32> {
+        (0..SPECTRUM_DATA_LENGTH).map(|i| (2.0 * PI * frequency * i as f32 / sampling_rate as f32).sin()).collect()
+    }
+
+    fn ramp_samples() -> Vec<f32> {
+        (0..SPECTRUM_DATA_LENGTH).map(|i| i as f32 / SPECTRUM_DATA_LENGTH as f32).collect()
+    }
+
+    fn settings(visualizer_type: VisualilzerType, section_count: usize, smoothing_range: usize) -> AudioVisualizerSettings {
+        AudioVisualizerSettings {
+            visualizer_type,
+            section_count,
+            smoothing_range,
+            ..Default::default()
+        }
+    }
+
+    fn assert_normalized(audio_analysis_frame: &AudioAnalysisFrame, column_count: usize) {
+        assert_eq!(audio_analysis_frame.values.len(), column_count);
+        assert_eq!(audio_analysis_frame.highlighted.len(), column_count);
+        assert!(audio_analysis_frame.values.iter().all(|value| (0.0..=1.0).contains(value)));
+        assert!(audio_analysis_frame.average_value.is_finite());
+    }
+
+    #[test]
+    fn frequency_binning_fills_every_column() {
+        let samples = sine_samples(440.0, 4096);
+        for column_count in [90, 360, 2000] {
+            let audio_analysis_frame = analyze_audio(&samples, &settings(VisualilzerType::FrequencyVisualizer, 1, 1), column_count).unwrap();
+            assert_normalized(&audio_analysis_frame, column_count);
+            let loudest_column = audio_analysis_frame.values.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+            let expected_column = ((440.0 - 20.0) / (1555.5 - 20.0) * column_count as f32) as usize;
+            assert!(loudest_column.abs_diff(expected_column) <= 1, "{} columns: loudest {} != {}", column_count, loudest_column, expected_column);
+        }
+    }
+
+    #[test]
+    fn frequency_binning_repeats_bins_when_there_are_more_columns_than_bins() {
+        let audio_visualizer_settings = AudioVisualizerSettings {
+            lower_frequency_limit: 100.0,
+            upper_frequency_limit: 300.0,
+            ..settings(VisualilzerType::FrequencyVisualizer, 1, 1)
+        };
+        let audio_analysis_frame = analyze_audio(&sine_samples(200.0, 4096), &audio_visualizer_settings, 2000).unwrap();
+
+        assert_normalized(&audio_analysis_frame, 2000);
+        assert_eq!(audio_analysis_frame.values.iter().cloned().fold(0.0, f32::max), 1.0);
+        assert_eq!(audio_analysis_frame.values[0], audio_analysis_frame.values[1]);
+    }
+
+    #[test]
+    fn spectrum_binning_averages_uneven_chunks() {
+        let samples = ramp_samples();
+        let highest_sample = samples[SPECTRUM_DATA_LENGTH - 1];
+        for column_count in [90, 360, 2000] {
+            let audio_analysis_frame = analyze_audio(&samples, &settings(VisualilzerType::SpectrumVisualizer, 1, 1), column_count).unwrap();
+            assert_normalized(&audio_analysis_frame, column_count);
+
+            for (i, value) in audio_analysis_frame.values.iter().enumerate() {
+                let start_index = i * SPECTRUM_DATA_LENGTH / column_count;
+                let end_index = (i + 1) * SPECTRUM_DATA_LENGTH / column_count;
+                let expected = samples[start_index..end_index].iter().sum::<f32>() / (end_index - start_index) as f32 / highest_sample;
+                assert!((value - expected).abs() < 1e-4, "column {} of {}: {} != {}", i, column_count, value, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn sections_repeat_the_first_section_when_column_count_is_not_divisible() {
+        let audio_analysis_frame = analyze_audio(&sine_samples(440.0, 4096), &settings(VisualilzerType::FrequencyVisualizer, 3, 1), 100).unwrap();
+        assert_normalized(&audio_analysis_frame, 100);
+
+        let first_section = &audio_analysis_frame.values[..33];
+        assert_eq!(audio_analysis_frame.values[33..66], *first_section);
+        assert_eq!(audio_analysis_frame.values[66], first_section[0]);
+        assert_eq!(audio_analysis_frame.values[99], first_section[32]);
+        assert!(audio_analysis_frame.values[66..].iter().all(|value| first_section.contains(value)));
+    }
+
+    #[test]
+    fn smoothing_range_zero_behaves_like_one() {
+        for visualizer_type in [VisualilzerType::FrequencyVisualizer, VisualilzerType::SpectrumVisualizer] {
+            let samples = if visualizer_type == VisualilzerType::FrequencyVisualizer { sine_samples(440.0, 4096) } else { ramp_samples() };
+            let unsmoothed = analyze_audio(&samples, &settings(visualizer_type, 1, 1), 360).unwrap();
+            let zero_smoothed = analyze_audio(&samples, &settings(visualizer_type, 1, 0), 360).unwrap();
+
+            assert_eq!(zero_smoothed.values, unsmoothed.values);
+            assert!(zero_smoothed.values.iter().any(|value| *value > 0.0));
+        }
+    }
+
+    #[test]
+    fn smoothing_averages_neighbouring_columns() {
+        let samples = ramp_samples();
+        let unsmoothed = analyze_audio(&samples, &settings(VisualilzerType::SpectrumVisualizer, 1, 1), 90).unwrap();
+        let smoothed = analyze_audio(&samples, &settings(VisualilzerType::SpectrumVisualizer, 1, 3), 90).unwrap();
+
+        assert!((smoothed.values[45] - unsmoothed.values[45]).abs() < 1e-3);
+        assert!(smoothed.values[0] > unsmoothed.values[0]);
+    }
+
+    #[test]
+    fn wrong_sample_count_is_rejected() {
+        assert!(analyze_audio(&[0.0; 16], &AudioVisualizerSettings::default(), 90).is_none());
+    }
+}
@@ -1,68 +1,136 @@
-use std::f32::consts::PI;
 use bevy::prelude::*;
+use bevy_inspector_egui::{inspector_options::std_options::NumberDisplay, prelude::*};
+use serde::{Deserialize, Serialize};
+use crate::visualizer::{analysis::AudioAnalysisFrame, minimum_frequency_range, AMPLITUDE_INTENSITY_STRENGTH_RANGE, COLOR_TRANSITION_SPEED_RANGE, COLUMN_BASE_INTENSITY_RANGE, COLUMN_CAP_ROUNDNESS_RANGE, COLUMN_COUNT_RANGE, COLUMN_EDGE_SOFTNESS_RANGE, COLUMN_GLOW_RADIUS_RANGE, COLUMN_GLOW_STRENGTH_RANGE, COLUMN_WIDTH_RANGE, FREQUENCY_LIMIT_RANGE, GRADIENT_STOP_POSITION_RANGE, HDR_MULTIPLIER_RANGE, MAX_HEIGHT_RANGE, RADIUS_RANGE, ROTATION_SPEED_RANGE, SAMPLING_RATE_RANGE, SCALE_STRENGTH_RANGE, SCALE_THRESHOLD_RANGE, SECTION_COUNT_RANGE, SMOOTHING_RANGE_RANGE};
 
 #[derive(Component)]
 pub struct AudioVisualizerContainer;
 
-#[derive(Component)]
-pub struct AudioVisualizerColumn;
+#[derive(Component, Default)]
+pub struct AudioVisualizerColumns {
+    pub heights: Vec<f32>,
+    pub highlighted: Vec<bool>,
+    pub radius: f32
+}
 
-#[derive(Resource)]
+impl AudioVisualizerColumns {
+    pub fn new(column_count: usize, radius: f32) -> Self {
+        Self {
+            heights: vec![1.0; column_count],
+            highlighted: vec![false; column_count],
+            radius
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct AudioVisualizerAnalysis {
+    pub average_frequency_value: f32,
+    pub frame: Option<AudioAnalysisFrame>
+}
+
+#[derive(Component, Clone, PartialEq, Serialize, Deserialize, Reflect, InspectorOptions)]
+#[serde(default)]
+#[reflect(Component, Default, InspectorOptions)]
 pub struct AudioVisualizerSettings {
+    pub visualizer_type: VisualilzerType,
+    pub position: Vec2,
+    #[serde(skip)]
+    #[reflect(ignore)]
     pub spectrum_data_length: usize,
+    #[inspector(min = *FREQUENCY_LIMIT_RANGE.start(), max = *FREQUENCY_LIMIT_RANGE.end(), display = NumberDisplay::Slider)]
     pub lower_frequency_limit: f32,
+    #[inspector(min = *FREQUENCY_LIMIT_RANGE.start(), max = *FREQUENCY_LIMIT_RANGE.end(), display = NumberDisplay::Slider)]
     pub upper_frequency_limit: f32,
+    #[inspector(min = *SAMPLING_RATE_RANGE.start(), max = *SAMPLING_RATE_RANGE.end(), display = NumberDisplay::Slider)]
     pub sampling_rate: u32,
     pub window_function: WindowFunction,
+    #[inspector(min = *COLUMN_COUNT_RANGE.start(), max = *COLUMN_COUNT_RANGE.end())]
     pub column_count: usize,
-    pub column_count_power_of_two: usize,
+    #[inspector(min = *SECTION_COUNT_RANGE.start(), max = *SECTION_COUNT_RANGE.end(), display = NumberDisplay::Slider)]
     pub section_count: usize,
+    #[inspector(min = *RADIUS_RANGE.start(), max = *RADIUS_RANGE.end(), display = NumberDisplay::Slider)]
     pub radius: f32,
+    #[inspector(min = *ROTATION_SPEED_RANGE.start(), max = *ROTATION_SPEED_RANGE.end(), display = NumberDisplay::Slider)]
     pub rotation_speed: f32,
-    pub angle_increment: f32,
+    #[inspector(min = *COLUMN_WIDTH_RANGE.start(), max = *COLUMN_WIDTH_RANGE.end(), display = NumberDisplay::Slider)]
     pub column_width: f32,
+    #[inspector(min = *MAX_HEIGHT_RANGE.start(), max = *MAX_HEIGHT_RANGE.end(), display = NumberDisplay::Slider)]
     pub max_height: f32,
+    #[inspector(min = *SMOOTHING_RANGE_RANGE.start(), max = *SMOOTHING_RANGE_RANGE.end())]
     pub smoothing_range: usize,
+    #[inspector(min = *SCALE_STRENGTH_RANGE.start(), max = *SCALE_STRENGTH_RANGE.end(), display = NumberDisplay::Slider)]
     pub scale_strenght: f32,
+    #[inspector(min = *SCALE_THRESHOLD_RANGE.start(), max = *SCALE_THRESHOLD_RANGE.end(), display = NumberDisplay::Slider)]
     pub scale_threshold: f32,
-    pub normal_color_material_handle: Option<Handle<ColorMaterial>>,
+    #[serde(skip)]
+    #[reflect(ignore)]
+    pub normal_current_color: Color,
     pub normal_color_transition_enabled: bool,
+    #[inspector(min = *COLOR_TRANSITION_SPEED_RANGE.start(), max = *COLOR_TRANSITION_SPEED_RANGE.end(), display = NumberDisplay::Slider)]
     pub normal_color_transition_speed: f32,
     pub normal_primary_color: Color,
     pub normal_secondary_color: Color,
+    #[inspector(min = *HDR_MULTIPLIER_RANGE.start(), max = *HDR_MULTIPLIER_RANGE.end(), display = NumberDisplay::Slider)]
     pub normal_primary_color_hdr_multiplier: f32,
+    #[inspector(min = *HDR_MULTIPLIER_RANGE.start(), max = *HDR_MULTIPLIER_RANGE.end(), display = NumberDisplay::Slider)]
     pub normal_secondary_color_hdr_multiplier: f32,
+    #[serde(skip)]
+    #[reflect(ignore)]
     pub normal_color_transition_progress: f32,
-    pub highlight_color_material_handle: Option<Handle<ColorMaterial>>,
+    #[serde(skip)]
+    #[reflect(ignore)]
+    pub highlight_current_color: Color,
     pub highlight_color_transition_enabled: bool,
+    #[inspector(min = *COLOR_TRANSITION_SPEED_RANGE.start(), max = *COLOR_TRANSITION_SPEED_RANGE.end(), display = NumberDisplay::Slider)]
     pub highlight_color_transition_speed: f32,
     pub highlight_primary_color: Color,
     pub highlight_secondary_color: Color,
+    #[inspector(min = *HDR_MULTIPLIER_RANGE.start(), max = *HDR_MULTIPLIER_RANGE.end(), display = NumberDisplay::Slider)]
     pub highlight_primary_color_hdr_multiplier: f32,
+    #[inspector(min = *HDR_MULTIPLIER_RANGE.start(), max = *HDR_MULTIPLIER_RANGE.end(), display = NumberDisplay::Slider)]
     pub highlight_secondary_color_hdr_multiplier: f32,
+    #[serde(skip)]
+    #[reflect(ignore)]
     pub highlight_color_transition_progress: f32,
+    pub color_mode: ColorMode,
+    pub frequency_gradient: ColorGradient,
+    pub height_gradient: ColorGradient,
+    pub height_gradient_along_column: bool,
+    #[inspector(min = *COLUMN_CAP_ROUNDNESS_RANGE.start(), max = *COLUMN_CAP_ROUNDNESS_RANGE.end(), display = NumberDisplay::Slider)]
+    pub column_cap_roundness: f32,
+    #[inspector(min = *COLUMN_EDGE_SOFTNESS_RANGE.start(), max = *COLUMN_EDGE_SOFTNESS_RANGE.end(), display = NumberDisplay::Slider)]
+    pub column_edge_softness: f32,
+    #[inspector(min = *COLUMN_GLOW_RADIUS_RANGE.start(), max = *COLUMN_GLOW_RADIUS_RANGE.end(), display = NumberDisplay::Slider)]
+    pub column_glow_radius: f32,
+    #[inspector(min = *COLUMN_GLOW_STRENGTH_RANGE.start(), max = *COLUMN_GLOW_STRENGTH_RANGE.end(), display = NumberDisplay::Slider)]
+    pub column_glow_strength: f32,
+    #[inspector(min = *COLUMN_BASE_INTENSITY_RANGE.start(), max = *COLUMN_BASE_INTENSITY_RANGE.end(), display = NumberDisplay::Slider)]
+    pub column_base_intensity: f32,
+    #[inspector(min = *AMPLITUDE_INTENSITY_STRENGTH_RANGE.start(), max = *AMPLITUDE_INTENSITY_STRENGTH_RANGE.end(), display = NumberDisplay::Slider)]
+    pub amplitude_intensity_strength: f32,
 }
 
 impl Default for AudioVisualizerSettings {
     fn default() -> Self {
         Self {
+            visualizer_type: VisualilzerType::FrequencyVisualizer,
+            position: Vec2::ZERO,
             spectrum_data_length: 8192,
             lower_frequency_limit: 20.0,
             upper_frequency_limit: 1555.5,
             sampling_rate: 4096,
             window_function: WindowFunction::None,
             column_count: 256,
-            column_count_power_of_two: 8,
             section_count: 1,
             radius: 200.0,
             rotation_speed: 0.005,
-            angle_increment: 2.0 * PI / 256.0,
             column_width: 2.5,
             max_height: 400.0,
             smoothing_range: 4,
             scale_strenght: 1000.0,
             scale_threshold: 2.0,
-            normal_color_material_handle: None,
+            normal_current_color: Color::WHITE,
             normal_color_transition_enabled: false,
             normal_color_transition_speed: 0.005,
             normal_primary_color: Color::WHITE,
@@ -70,7 +138,7 @@ impl Default for AudioVisualizerSettings {
             normal_primary_color_hdr_multiplier: 1.0,
             normal_secondary_color_hdr_multiplier: 1.0,
             normal_color_transition_progress: 0.0,
-            highlight_color_material_handle: None,
+            highlight_current_color: Color::RED,
             highlight_color_transition_enabled: false,
             highlight_color_transition_speed: 0.005,
             highlight_primary_color: Color::RED,
@@ -78,17 +146,233 @@ impl Default for AudioVisualizerSettings {
             highlight_primary_color_hdr_multiplier: 1.0,
             highlight_secondary_color_hdr_multiplier: 1.0,
             highlight_color_transition_progress: 0.0,
+            color_mode: ColorMode::Solid,
+            frequency_gradient: ColorGradient {
+                stops: vec![
+                    ColorGradientStop { position: 0.0, color: Color::RED, hdr_multiplier: 1.0 },
+                    ColorGradientStop { position: 0.2, color: Color::YELLOW, hdr_multiplier: 1.0 },
+                    ColorGradientStop { position: 0.4, color: Color::GREEN, hdr_multiplier: 1.0 },
+                    ColorGradientStop { position: 0.6, color: Color::CYAN, hdr_multiplier: 1.0 },
+                    ColorGradientStop { position: 0.8, color: Color::BLUE, hdr_multiplier: 1.0 },
+                    ColorGradientStop { position: 1.0, color: Color::FUCHSIA, hdr_multiplier: 1.0 },
+                ]
+            },
+            height_gradient: ColorGradient {
+                stops: vec![
+                    ColorGradientStop { position: 0.0, color: Color::MIDNIGHT_BLUE, hdr_multiplier: 1.0 },
+                    ColorGradientStop { position: 0.5, color: Color::ORANGE_RED, hdr_multiplier: 1.0 },
+                    ColorGradientStop { position: 1.0, color: Color::YELLOW, hdr_multiplier: 4.0 },
+                ]
+            },
+            height_gradient_along_column: false,
+            column_cap_roundness: 0.0,
+            column_edge_softness: 0.5,
+            column_glow_radius: 0.0,
+            column_glow_strength: 0.0,
+            column_base_intensity: 1.0,
+            amplitude_intensity_strength: 0.0,
+        }
+    }
+}
+
+impl AudioVisualizerSettings {
+    pub fn apply_preserving_color_transition(&mut self, mut audio_visualizer_settings: AudioVisualizerSettings) {
+        audio_visualizer_settings.normal_current_color = self.normal_current_color;
+        audio_visualizer_settings.normal_color_transition_progress = self.normal_color_transition_progress;
+        audio_visualizer_settings.normal_color_transition_speed = audio_visualizer_settings.normal_color_transition_speed.abs() * self.normal_color_transition_speed.signum();
+        audio_visualizer_settings.highlight_current_color = self.highlight_current_color;
+        audio_visualizer_settings.highlight_color_transition_progress = self.highlight_color_transition_progress;
+        audio_visualizer_settings.highlight_color_transition_speed = audio_visualizer_settings.highlight_color_transition_speed.abs() * self.highlight_color_transition_speed.signum();
+        *self = audio_visualizer_settings;
+    }
+
+    pub fn sanitize(&mut self) -> Vec<String> {
+        let mut corrections = Vec::new();
+
+        let column_count = self.column_count.clamp(*COLUMN_COUNT_RANGE.start(), *COLUMN_COUNT_RANGE.end());
+        if column_count != self.column_count {
+            corrections.push(format!("column_count {} is out of range, using {}", self.column_count, column_count));
+            self.column_count = column_count;
+        }
+
+        let section_count = self.section_count.clamp(1, self.column_count);
+        if section_count != self.section_count {
+            corrections.push(format!("section_count {} is out of range, using {}", self.section_count, section_count));
+            self.section_count = section_count;
+        }
+
+        let smoothing_range = self.smoothing_range.clamp(1, (self.column_count / 2).max(1));
+        if smoothing_range != self.smoothing_range {
+            corrections.push(format!("smoothing_range {} is out of range, using {}", self.smoothing_range, smoothing_range));
+            self.smoothing_range = smoothing_range;
+        }
+
+        let sampling_rate = self.sampling_rate.clamp(*SAMPLING_RATE_RANGE.start(), *SAMPLING_RATE_RANGE.end());
+        if sampling_rate != self.sampling_rate {
+            corrections.push(format!("sampling_rate {} is out of range, using {}", self.sampling_rate, sampling_rate));
+            self.sampling_rate = sampling_rate;
+        }
+
+        if !self.lower_frequency_limit.is_finite() || !self.upper_frequency_limit.is_finite() || self.lower_frequency_limit < 0.0 || self.lower_frequency_limit >= self.upper_frequency_limit {
+            let default_settings = Self::default();
+            corrections.push(format!("frequency range {}..{} is invalid, using {}..{}", self.lower_frequency_limit, self.upper_frequency_limit, default_settings.lower_frequency_limit, default_settings.upper_frequency_limit));
+            self.lower_frequency_limit = default_settings.lower_frequency_limit;
+            self.upper_frequency_limit = default_settings.upper_frequency_limit;
+        }
+
+        let nyquist_frequency = self.sampling_rate as f32 / 2.0;
+        if self.upper_frequency_limit > nyquist_frequency {
+            corrections.push(format!("upper_frequency_limit {} is above half the sampling_rate, using {}", self.upper_frequency_limit, nyquist_frequency));
+            self.upper_frequency_limit = nyquist_frequency;
+        }
+
+        let minimum_frequency_range = minimum_frequency_range(self.sampling_rate);
+        if self.upper_frequency_limit - self.lower_frequency_limit < minimum_frequency_range {
+            let lower_frequency_limit = (self.upper_frequency_limit - minimum_frequency_range).max(0.0);
+            let upper_frequency_limit = lower_frequency_limit + minimum_frequency_range;
+            corrections.push(format!("frequency range {}..{} is too narrow, using {}..{}", self.lower_frequency_limit, self.upper_frequency_limit, lower_frequency_limit, upper_frequency_limit));
+            self.lower_frequency_limit = lower_frequency_limit;
+            self.upper_frequency_limit = upper_frequency_limit;
         }
+
+        if self.column_width < *COLUMN_WIDTH_RANGE.start() {
+            corrections.push(format!("column_width {} is too small, using {}", self.column_width, COLUMN_WIDTH_RANGE.start()));
+            self.column_width = *COLUMN_WIDTH_RANGE.start();
+        }
+
+        if self.max_height < *MAX_HEIGHT_RANGE.start() {
+            corrections.push(format!("max_height {} is too small, using {}", self.max_height, MAX_HEIGHT_RANGE.start()));
+            self.max_height = *MAX_HEIGHT_RANGE.start();
+        }
+
+        if self.scale_threshold < *SCALE_THRESHOLD_RANGE.start() {
+            corrections.push(format!("scale_threshold {} is too small, using {}", self.scale_threshold, SCALE_THRESHOLD_RANGE.start()));
+            self.scale_threshold = *SCALE_THRESHOLD_RANGE.start();
+        }
+
+        self.frequency_gradient.sanitize("frequency_gradient", &mut corrections);
+        self.height_gradient.sanitize("height_gradient", &mut corrections);
+
+        corrections
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Reflect)]
+pub enum ColorMode {
+    Solid,
+    Frequency,
+    Height,
+    FrequencyAndHeight
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct ColorGradient {
+    pub stops: Vec<ColorGradientStop>
+}
+
+impl ColorGradient {
+    // Sampling the gradient expects sorted stops with positions between 0 and 1
+    fn sanitize(&mut self, name: &str, corrections: &mut Vec<String>) {
+        for (i, stop) in self.stops.iter_mut().enumerate() {
+            let position = if stop.position.is_nan() { *GRADIENT_STOP_POSITION_RANGE.start() } else { stop.position.clamp(*GRADIENT_STOP_POSITION_RANGE.start(), *GRADIENT_STOP_POSITION_RANGE.end()) };
+            if position != stop.position {
+                corrections.push(format!("{} stop {} position {} is out of range, using {}", name, i, stop.position, position));
+                stop.position = position;
+            }
+
+            let hdr_multiplier = if stop.hdr_multiplier.is_nan() { *HDR_MULTIPLIER_RANGE.start() } else { stop.hdr_multiplier.clamp(*HDR_MULTIPLIER_RANGE.start(), *HDR_MULTIPLIER_RANGE.end()) };
+            if hdr_multiplier != stop.hdr_multiplier {
+                corrections.push(format!("{} stop {} hdr_multiplier {} is out of range, using {}", name, i, stop.hdr_multiplier, hdr_multiplier));
+                stop.hdr_multiplier = hdr_multiplier;
+            }
+        }
+
+        if !self.stops.windows(2).all(|stops| stops[0].position <= stops[1].position) {
+            corrections.push(format!("{} stops are not sorted by position, sorting them", name));
+            self.stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Reflect, InspectorOptions)]
+#[reflect(Default, InspectorOptions)]
+pub struct ColorGradientStop {
+    #[inspector(min = *GRADIENT_STOP_POSITION_RANGE.start(), max = *GRADIENT_STOP_POSITION_RANGE.end(), display = NumberDisplay::Slider)]
+    pub position: f32,
+    pub color: Color,
+    #[inspector(min = *HDR_MULTIPLIER_RANGE.start(), max = *HDR_MULTIPLIER_RANGE.end(), display = NumberDisplay::Slider)]
+    pub hdr_multiplier: f32
+}
+
+impl Default for ColorGradientStop {
+    fn default() -> Self {
+        Self {
+            position: 1.0,
+            color: Color::WHITE,
+            hdr_multiplier: 1.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Reflect)]
 pub enum WindowFunction {
     None,
     Hann,
     Hamming
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut
+}
+
+impl Easing {
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct AudioVisualizerTransition {
+    pub from: AudioVisualizerSettings,
+    pub to: AudioVisualizerSettings,
+    pub timer: Timer,
+    pub easing: Easing
+}
+
+#[derive(Component)]
+pub struct AudioVisualizerFade {
+    pub opacity: f32,
+    pub fade_in: bool,
+    pub timer: Timer,
+    pub easing: Easing
+}
+
+impl AudioVisualizerFade {
+    pub fn new(fade_in: bool, duration: f32, easing: Easing) -> Self {
+        Self {
+            opacity: if fade_in { 0.0 } else { 1.0 },
+            fade_in,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+            easing
+        }
+    }
+
+    pub fn is_fading_out(&self) -> bool {
+        !self.fade_in
+    }
+}
+
 #[derive(Resource)]
 pub struct AudioVisualizerUpdateTimer{
     pub timer: Timer
@@ -103,11 +387,138 @@ impl Default for AudioVisualizerUpdateTimer {
 }
 
 #[derive(Event)]
-pub struct AudioVisualizerRestructureEvent;
+pub struct AudioVisualizerRestructureEvent(pub Entity);
+
+#[derive(Event)]
+pub struct AudioVisualizerSpawnEvent(pub AudioVisualizerSettings);
+
+#[derive(Event)]
+pub struct AudioVisualizerRestoreEvent(pub Vec<AudioVisualizerSettings>);
 
-#[derive(States, Debug, Clone, Eq, PartialEq, Hash, Default)]
+#[derive(Event)]
+pub struct AudioVisualizerTransitionEvent {
+    pub container: Entity,
+    pub settings: AudioVisualizerSettings,
+    pub duration: f32,
+    pub easing: Easing
+}
+
+#[derive(Resource, Default)]
+pub struct SelectedAudioVisualizer(pub Option<Entity>);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default, Serialize, Deserialize, Reflect)]
 pub enum VisualilzerType {
     #[default]
     FrequencyVisualizer,
     SpectrumVisualizer
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{audio_data::SPECTRUM_DATA_LENGTH, visualizer::analysis::analyze_audio};
+
+    fn sanitized(audio_visualizer_settings: AudioVisualizerSettings) -> (AudioVisualizerSettings, Vec<String>) {
+        let mut audio_visualizer_settings = audio_visualizer_settings;
+        let corrections = audio_visualizer_settings.sanitize();
+        (audio_visualizer_settings, corrections)
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert!(sanitized(AudioVisualizerSettings::default()).1.is_empty());
+    }
+
+    #[test]
+    fn narrow_frequency_range_is_widened() {
+        let (audio_visualizer_settings, corrections) = sanitized(AudioVisualizerSettings {
+            lower_frequency_limit: 100.0,
+            upper_frequency_limit: 100.2,
+            sampling_rate: 44100,
+            ..default()
+        });
+
+        assert_eq!(corrections.len(), 1);
+        assert!(audio_visualizer_settings.upper_frequency_limit - audio_visualizer_settings.lower_frequency_limit >= minimum_frequency_range(44100));
+        assert!(analyze_audio(&[0.5; SPECTRUM_DATA_LENGTH], &audio_visualizer_settings, 64).is_some());
+    }
+
+    #[test]
+    fn upper_frequency_limit_is_clamped_to_nyquist() {
+        let (audio_visualizer_settings, _) = sanitized(AudioVisualizerSettings {
+            lower_frequency_limit: 3000.0,
+            upper_frequency_limit: 5000.0,
+            sampling_rate: 4096,
+            ..default()
+        });
+
+        assert!(audio_visualizer_settings.upper_frequency_limit <= 2048.0);
+        assert!(audio_visualizer_settings.lower_frequency_limit < audio_visualizer_settings.upper_frequency_limit);
+        assert!(analyze_audio(&[0.5; SPECTRUM_DATA_LENGTH], &audio_visualizer_settings, 64).is_some());
+    }
+
+    #[test]
+    fn invalid_frequency_values_use_defaults() {
+        for (lower_frequency_limit, upper_frequency_limit) in [(f32::NAN, 100.0), (-5.0, 100.0), (200.0, 100.0), (0.0, f32::INFINITY)] {
+            let (audio_visualizer_settings, corrections) = sanitized(AudioVisualizerSettings { lower_frequency_limit, upper_frequency_limit, ..default() });
+            assert!(!corrections.is_empty());
+            assert_eq!(audio_visualizer_settings.lower_frequency_limit, AudioVisualizerSettings::default().lower_frequency_limit);
+        }
+    }
+
+    #[test]
+    fn unsanitized_frequency_range_does_not_panic() {
+        let audio_visualizer_settings = AudioVisualizerSettings {
+            lower_frequency_limit: 100.0,
+            upper_frequency_limit: 100.2,
+            sampling_rate: 44100,
+            ..default()
+        };
+
+        assert!(analyze_audio(&[0.5; SPECTRUM_DATA_LENGTH], &audio_visualizer_settings, 64).is_none());
+    }
+
+    #[test]
+    fn gradient_stops_are_clamped_and_sorted() {
+        let stop = |position: f32, hdr_multiplier: f32| ColorGradientStop { position, hdr_multiplier, ..default() };
+        let (audio_visualizer_settings, corrections) = sanitized(AudioVisualizerSettings {
+            frequency_gradient: ColorGradient { stops: vec![stop(0.8, 1.0), stop(-0.5, 20.0), stop(1.5, f32::NAN)] },
+            height_gradient: ColorGradient { stops: vec![stop(0.0, 1.0), stop(0.5, 2.0), stop(1.0, 3.0)] },
+            ..default()
+        });
+
+        let positions: Vec<f32> = audio_visualizer_settings.frequency_gradient.stops.iter().map(|stop| stop.position).collect();
+        let hdr_multipliers: Vec<f32> = audio_visualizer_settings.frequency_gradient.stops.iter().map(|stop| stop.hdr_multiplier).collect();
+        assert_eq!(positions, vec![0.0, 0.8, 1.0]);
+        assert_eq!(hdr_multipliers, vec![10.0, 1.0, 1.0]);
+        assert_eq!(corrections.len(), 5);
+        assert!(corrections.iter().all(|correction| correction.starts_with("frequency_gradient")));
+    }
+
+    #[test]
+    fn easing_starts_at_zero_and_ends_at_one() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.ease(0.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.ease(1.0), 1.0, "{:?}", easing);
+            assert!((easing.ease(0.5) - 0.5).abs() <= 0.5, "{:?}", easing);
+        }
+        assert_eq!(Easing::EaseInOut.ease(0.5), 0.5);
+    }
+
+    #[test]
+    fn easing_clamps_out_of_range_progress() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.ease(-0.5), 0.0, "{:?}", easing);
+            assert_eq!(easing.ease(1.5), 1.0, "{:?}", easing);
+            assert_eq!(easing.ease(f32::INFINITY), 1.0, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn easing_is_monotonic() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            let values: Vec<f32> = (0..=100).map(|i| easing.ease(i as f32 / 100.0)).collect();
+            assert!(values.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", easing);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use bevy::{prelude::*, render::{mesh::{MeshVertexAttribute, MeshVertexBufferLayout}, render_resource::{AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError, VertexFormat}}, sprite::{Material2d, Material2dKey}};
+
+pub const AUDIO_VISUALIZER_COLUMN_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(184_093_776_320_511_672_915_382_047_126_993_804);
+
+pub const ATTRIBUTE_COLUMN_OFFSET: MeshVertexAttribute = MeshVertexAttribute::new("ColumnOffset", 184_093_776_320_511_001, VertexFormat::Float32x2);
+pub const ATTRIBUTE_COLUMN_SIZE: MeshVertexAttribute = MeshVertexAttribute::new("ColumnSize", 184_093_776_320_511_002, VertexFormat::Float32x2);
+
+#[derive(Asset, TypePath, AsBindGroup, Clone, Default)]
+pub struct AudioVisualizerColumnMaterial {
+    #[uniform(0)]
+    pub uniform: AudioVisualizerColumnMaterialUniform
+}
+
+pub use uniform::AudioVisualizerColumnMaterialUniform;
+
+// The ShaderType derive generates size checks next to the struct for every field that rustc reports as unused
+#[allow(dead_code)]
+mod uniform {
+    use bevy::render::render_resource::ShaderType;
+
+    #[derive(ShaderType, Clone, Copy, Default)]
+    pub struct AudioVisualizerColumnMaterialUniform {
+        pub cap_roundness: f32,
+        pub edge_softness: f32,
+        pub glow_radius: f32,
+        pub glow_strength: f32,
+        pub base_intensity: f32,
+        pub intensity: f32,
+        pub opacity: f32
+    }
+}
+
+impl Material2d for AudioVisualizerColumnMaterial {
+    fn vertex_shader() -> ShaderRef {
+        AUDIO_VISUALIZER_COLUMN_SHADER_HANDLE.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        AUDIO_VISUALIZER_COLUMN_SHADER_HANDLE.into()
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: Material2dKey<Self>
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_COLUMN_OFFSET.at_shader_location(1),
+            ATTRIBUTE_COLUMN_SIZE.at_shader_location(2),
+            Mesh::ATTRIBUTE_COLOR.at_shader_location(3)
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+
+        Ok(())
+    }
+}
@@ -1,26 +1,76 @@
-use bevy::{prelude::*, window::WindowResized};
+use bevy::{asset::load_internal_asset, prelude::*, sprite::Material2dPlugin};
 use core::f32::consts::PI;
+use std::ops::RangeInclusive;
+use crate::audio_data::{components::AudioSamples, SPECTRUM_DATA_LENGTH};
 
+pub mod analysis;
 pub mod components;
+pub mod material;
 mod systems;
 
 use systems::*;
 use components::*;
+use material::*;
 
 pub struct AudioVisualizerPlugin;
 
 impl Plugin for AudioVisualizerPlugin {
     fn build(&self, app: &mut App) {
+        load_internal_asset!(app, AUDIO_VISUALIZER_COLUMN_SHADER_HANDLE, "shaders/audio_visualizer_column.wgsl", Shader::from_wgsl);
+
         app
-        .init_state::<VisualilzerType>()
+        .add_plugins(Material2dPlugin::<AudioVisualizerColumnMaterial>::default())
+        .register_type::<AudioVisualizerSettings>()
+        .register_type::<ColorGradientStop>()
         .init_resource::<AudioVisualizerUpdateTimer>()
-        .init_resource::<AudioVisualizerSettings>()
+        .init_resource::<AudioSamples>()
+        .init_resource::<SelectedAudioVisualizer>()
         .add_event::<AudioVisualizerRestructureEvent>()
-        .add_systems(Startup, setup_audio_visualizer)
-        .add_systems(Update, (tick_audio_visualizer_update_timer, update_audio_visualizer_rotation, update_audio_visualizer_scale, update_color_transition))
-        .add_systems(Update, visualize_audio_spectrum.run_if(in_state(VisualilzerType::SpectrumVisualizer)))
-        .add_systems(Update, visualize_audio_frequency.run_if(in_state(VisualilzerType::FrequencyVisualizer)))
-        .add_systems(Update, restructure_audio_visualizer.run_if(on_event::<AudioVisualizerRestructureEvent>()))
-        .add_systems(Update, center_audio_visualizer.run_if(on_event::<WindowResized>()));
+        .add_event::<AudioVisualizerSpawnEvent>()
+        .add_event::<AudioVisualizerRestoreEvent>()
+        .add_event::<AudioVisualizerTransitionEvent>()
+        .add_systems(PostStartup, setup_audio_visualizer)
+        .add_systems(Update, (update_audio_visualizer_transition, update_audio_visualizer_fade).before(update_color_transition))
+        .add_systems(Update, (tick_audio_visualizer_update_timer, update_audio_visualizer_rotation, update_audio_visualizer_scale, update_audio_visualizer_position, update_color_transition))
+        .add_systems(Update, sanitize_audio_visualizer_settings.before(visualize_audio_spectrum).before(visualize_audio_frequency).before(detect_audio_visualizer_structure_changes))
+        .add_systems(Update, (visualize_audio_spectrum, visualize_audio_frequency))
+        .add_systems(Update, update_audio_visualizer_material.after(visualize_audio_frequency))
+        .add_systems(Update, update_audio_visualizer_mesh.after(visualize_audio_spectrum).after(visualize_audio_frequency).after(update_audio_visualizer_scale).after(update_color_transition))
+        .add_systems(Update, handle_audio_visualizer_spawn_events.run_if(on_event::<AudioVisualizerSpawnEvent>()).in_set(AudioVisualizerSpawnSet))
+        .add_systems(Update, restore_audio_visualizers.run_if(on_event::<AudioVisualizerRestoreEvent>()).in_set(AudioVisualizerSpawnSet))
+        .add_systems(Update, handle_audio_visualizer_transition_events.run_if(on_event::<AudioVisualizerTransitionEvent>()))
+        .add_systems(Update, (detect_audio_visualizer_structure_changes, restructure_audio_visualizer.run_if(on_event::<AudioVisualizerRestructureEvent>())).chain());
     }
-}
\ No newline at end of file
+}
+
+pub const RESTRUCTURE_DEBOUNCE_SECONDS: f32 = 0.2;
+
+pub const MIN_FREQUENCY_RANGE: f32 = 8.0;
+pub const FREQUENCY_LIMIT_RANGE: RangeInclusive<f32> = 0.0..=22050.0;
+pub const SAMPLING_RATE_RANGE: RangeInclusive<u32> = 16..=44100;
+pub const COLUMN_COUNT_RANGE: RangeInclusive<usize> = 2..=SPECTRUM_DATA_LENGTH;
+pub const SECTION_COUNT_RANGE: RangeInclusive<usize> = 1..=10;
+pub const SMOOTHING_RANGE_RANGE: RangeInclusive<usize> = 1..=SPECTRUM_DATA_LENGTH / 2;
+pub const RADIUS_RANGE: RangeInclusive<f32> = 0.0..=1000.0;
+pub const COLUMN_WIDTH_RANGE: RangeInclusive<f32> = 1.0..=20.0;
+pub const MAX_HEIGHT_RANGE: RangeInclusive<f32> = 2.0..=1000.0;
+pub const ROTATION_SPEED_RANGE: RangeInclusive<f32> = -0.5..=0.5;
+pub const SCALE_STRENGTH_RANGE: RangeInclusive<f32> = 0.0..=20000.0;
+pub const SCALE_THRESHOLD_RANGE: RangeInclusive<f32> = 0.55..=10.0;
+pub const COLOR_TRANSITION_SPEED_RANGE: RangeInclusive<f32> = -0.5..=0.5;
+pub const HDR_MULTIPLIER_RANGE: RangeInclusive<f32> = 1.0..=10.0;
+pub const COLUMN_CAP_ROUNDNESS_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+pub const COLUMN_EDGE_SOFTNESS_RANGE: RangeInclusive<f32> = 0.0..=10.0;
+pub const COLUMN_GLOW_RADIUS_RANGE: RangeInclusive<f32> = 0.0..=50.0;
+pub const COLUMN_GLOW_STRENGTH_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+pub const COLUMN_BASE_INTENSITY_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+pub const AMPLITUDE_INTENSITY_STRENGTH_RANGE: RangeInclusive<f32> = 0.0..=100.0;
+pub const GRADIENT_STOP_POSITION_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AudioVisualizerSpawnSet;
+
+// The spectrum needs at least two FFT bins inside the frequency range, with a margin for rounding
+pub fn minimum_frequency_range(sampling_rate: u32) -> f32 {
+    MIN_FREQUENCY_RANGE.max(3.0 * sampling_rate as f32 / SPECTRUM_DATA_LENGTH as f32)
+}
@@ -0,0 +1,76 @@
+#import bevy_sprite::{
+    mesh2d_functions as mesh_functions,
+    mesh2d_view_bindings::view,
+}
+
+#ifdef TONEMAP_IN_SHADER
+#import bevy_core_pipeline::tonemapping
+#endif
+
+struct AudioVisualizerColumnMaterial {
+    cap_roundness: f32,
+    edge_softness: f32,
+    glow_radius: f32,
+    glow_strength: f32,
+    base_intensity: f32,
+    intensity: f32,
+    opacity: f32,
+};
+
+@group(2) @binding(0) var<uniform> material: AudioVisualizerColumnMaterial;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    // offset from the column center in pixels, x across the column and y along it
+    @location(1) offset: vec2<f32>,
+    // width and height of the column in pixels
+    @location(2) size: vec2<f32>,
+    @location(3) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) offset: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let model = mesh_functions::get_model_matrix(vertex.instance_index);
+    let world_position = mesh_functions::mesh2d_position_local_to_world(model, vec4<f32>(vertex.position, 1.0));
+    out.position = mesh_functions::mesh2d_position_world_to_clip(world_position);
+    out.offset = vertex.offset;
+    out.size = vertex.size;
+    out.color = vertex.color;
+    return out;
+}
+
+// signed distance to a box whose ends along y are rounded with the given radius
+fn rounded_column_distance(offset: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    let q = abs(offset) - half_size + vec2<f32>(radius);
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let half_size = in.size / 2.0;
+    let radius = min(material.cap_roundness * half_size.x, half_size.y);
+    let column_distance = rounded_column_distance(in.offset, half_size, radius);
+
+    let softness = max(material.edge_softness, 0.0001);
+    let body = 1.0 - smoothstep(-softness, softness, column_distance);
+    let glow = material.glow_strength * exp(-max(column_distance, 0.0) / max(material.glow_radius, 0.0001));
+    let coverage = max(body, glow * step(0.0, column_distance));
+
+    let along = clamp(in.offset.y / in.size.y + 0.5, 0.0, 1.0);
+    let vertical_intensity = mix(material.base_intensity, 1.0, along);
+
+    var color = vec4<f32>(in.color.rgb * vertical_intensity * material.intensity, in.color.a * coverage * material.opacity);
+#ifdef TONEMAP_IN_SHADER
+    color = tonemapping::tone_mapping(color, view.color_grading);
+#endif
+    return color;
+}
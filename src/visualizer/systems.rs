@@ -1,77 +1,448 @@
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle, window::PrimaryWindow};
-use crate::audio_data::{components::AudioData, SPECTRUM_DATA_LENGTH};
-use spectrum_analyzer::{samples_fft_to_spectrum, scaling::scale_to_zero_to_one, windows::{hamming_window, hann_window}, FrequencyLimit};
-use ringbuffer::RingBuffer;
+use bevy::{prelude::*, render::{mesh::{Indices, PrimitiveTopology}, render_asset::RenderAssetUsages, view::NoFrustumCulling}, sprite::{MaterialMesh2dBundle, Mesh2dHandle}, utils::HashMap, window::PrimaryWindow};
+use crate::audio_data::components::AudioSamples;
 use crate::visualizer::*;
-use super::components::*;
+use super::{analysis::*, components::*, material::*};
 
-
-const FIXED_ARRAY_SIZE: usize = 1024;
- 
 pub fn setup_audio_visualizer(
     mut commands: Commands,
+    audio_visualizer_spawn_event_reader: EventReader<AudioVisualizerSpawnEvent>,
+    audio_visualizer_restore_event_reader: EventReader<AudioVisualizerRestoreEvent>,
     window: Query<&Window, With<PrimaryWindow>>,
-    mut audio_visualizer_settings: ResMut<AudioVisualizerSettings>,
-    meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>
+    mut selected_audio_visualizer: ResMut<SelectedAudioVisualizer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AudioVisualizerColumnMaterial>>
 ) {
-    let window = window.get_single().unwrap();
-    audio_visualizer_settings.column_width = window.width() / audio_visualizer_settings.column_count as f32;
+    // Runs after every Startup system so visualizers requested by the config or an embedding app replace the default one
+    if !audio_visualizer_spawn_event_reader.is_empty() || !audio_visualizer_restore_event_reader.is_empty() {
+        return;
+    }
 
-    audio_visualizer_settings.normal_color_material_handle = Some(materials.add(audio_visualizer_settings.normal_primary_color));
+    let mut audio_visualizer_settings = AudioVisualizerSettings::default();
+    if let Ok(window) = window.get_single() {
+        audio_visualizer_settings.column_width = window.width() / audio_visualizer_settings.column_count as f32;
+    }
 
-    audio_visualizer_settings.highlight_color_material_handle = Some(materials.add(audio_visualizer_settings.highlight_primary_color));
+    selected_audio_visualizer.0 = Some(spawn_audio_visualizer(&mut commands, audio_visualizer_settings, None, &mut meshes, &mut materials));
+}
 
-    build_audio_visualizer(&mut commands, audio_visualizer_settings, meshes, materials);
+pub fn handle_audio_visualizer_spawn_events(
+    mut commands: Commands,
+    mut audio_visualizer_spawn_event_reader: EventReader<AudioVisualizerSpawnEvent>,
+    mut selected_audio_visualizer: ResMut<SelectedAudioVisualizer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AudioVisualizerColumnMaterial>>
+) {
+    for AudioVisualizerSpawnEvent(audio_visualizer_settings) in audio_visualizer_spawn_event_reader.read() {
+        selected_audio_visualizer.0 = Some(spawn_audio_visualizer(&mut commands, audio_visualizer_settings.clone(), None, &mut meshes, &mut materials));
+    }
 }
 
-pub fn restructure_audio_visualizer(
+pub fn restore_audio_visualizers(
     mut commands: Commands,
-    mut audio_visulizer_container_query: Query<Entity, With<AudioVisualizerContainer>>,
-    mut audio_visualizer_settings: ResMut<AudioVisualizerSettings>,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<ColorMaterial>>
+    mut audio_visualizer_restore_event_reader: EventReader<AudioVisualizerRestoreEvent>,
+    mut audio_visualizer_restructure_event_writer: EventWriter<AudioVisualizerRestructureEvent>,
+    mut audio_visualizer_container_query: Query<(Entity, &mut AudioVisualizerSettings, Option<&AudioVisualizerFade>), With<AudioVisualizerContainer>>,
+    mut selected_audio_visualizer: ResMut<SelectedAudioVisualizer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AudioVisualizerColumnMaterial>>
+) {
+    let Some(AudioVisualizerRestoreEvent(restored_visualizers)) = audio_visualizer_restore_event_reader.read().last() else {
+        return;
+    };
+
+    let mut restored_visualizers = restored_visualizers.iter().cloned();
+    for (container, mut audio_visualizer_settings, audio_visualizer_fade) in audio_visualizer_container_query.iter_mut() {
+        if audio_visualizer_fade.is_some_and(AudioVisualizerFade::is_fading_out) {
+            continue;
+        }
+
+        match restored_visualizers.next() {
+            Some(restored_audio_visualizer_settings) => {
+                let structural_change = audio_visualizer_settings.column_count != restored_audio_visualizer_settings.column_count;
+                audio_visualizer_settings.apply_preserving_color_transition(restored_audio_visualizer_settings);
+                commands.entity(container).remove::<AudioVisualizerTransition>();
+                if structural_change {
+                    audio_visualizer_restructure_event_writer.send(AudioVisualizerRestructureEvent(container));
+                }
+            },
+            None => {
+                commands.entity(container).despawn_recursive();
+                if selected_audio_visualizer.0 == Some(container) {
+                    selected_audio_visualizer.0 = None;
+                }
+            }
+        }
+    }
+    for audio_visualizer_settings in restored_visualizers {
+        selected_audio_visualizer.0 = Some(spawn_audio_visualizer(&mut commands, audio_visualizer_settings, None, &mut meshes, &mut materials));
+    }
+}
+
+pub fn sanitize_audio_visualizer_settings(
+    mut audio_visualizer_container_query: Query<&mut AudioVisualizerSettings, (With<AudioVisualizerContainer>, Changed<AudioVisualizerSettings>)>
+) {
+    for mut audio_visualizer_settings in audio_visualizer_container_query.iter_mut() {
+        let mut sanitized_audio_visualizer_settings = audio_visualizer_settings.clone();
+        let corrections = sanitized_audio_visualizer_settings.sanitize();
+        if corrections.is_empty() {
+            continue;
+        }
+
+        for correction in corrections {
+            warn!("Invalid audio visualizer setting: {}", correction);
+        }
+        *audio_visualizer_settings = sanitized_audio_visualizer_settings;
+    }
+}
+
+pub fn detect_audio_visualizer_structure_changes(
+    time: Res<Time>,
+    mut audio_visualizer_restructure_event_writer: EventWriter<AudioVisualizerRestructureEvent>,
+    audio_visualizer_container_query: Query<(Entity, &AudioVisualizerSettings, &AudioVisualizerColumns), With<AudioVisualizerContainer>>,
+    mut pending_column_counts: Local<HashMap<Entity, (usize, f32)>>
 ) {
-    let current_audio_visualizer = audio_visulizer_container_query.get_single_mut().unwrap();
+    let mut still_pending_column_counts = HashMap::new();
+    for (container, audio_visualizer_settings, audio_visualizer_columns) in audio_visualizer_container_query.iter() {
+        if audio_visualizer_settings.column_count == audio_visualizer_columns.heights.len() {
+            continue;
+        }
+
+        let unchanged_duration = match pending_column_counts.get(&container) {
+            Some((column_count, unchanged_duration)) if *column_count == audio_visualizer_settings.column_count => unchanged_duration + time.delta_seconds(),
+            _ => 0.0
+        };
+        if unchanged_duration >= RESTRUCTURE_DEBOUNCE_SECONDS {
+            audio_visualizer_restructure_event_writer.send(AudioVisualizerRestructureEvent(container));
+        } else {
+            still_pending_column_counts.insert(container, (audio_visualizer_settings.column_count, unchanged_duration));
+        }
+    }
+    *pending_column_counts = still_pending_column_counts;
+}
 
-    audio_visualizer_settings.column_count = 2_u32.pow(audio_visualizer_settings.column_count_power_of_two as u32) as usize;
-    audio_visualizer_settings.angle_increment = 2.0 * PI / audio_visualizer_settings.column_count as f32;
+pub fn restructure_audio_visualizer(
+    mut audio_visualizer_restructure_event_reader: EventReader<AudioVisualizerRestructureEvent>,
+    mut audio_visualizer_container_query: Query<(&AudioVisualizerSettings, &mut AudioVisualizerColumns, &Mesh2dHandle), With<AudioVisualizerContainer>>,
+    mut meshes: ResMut<Assets<Mesh>>
+) {
+    for AudioVisualizerRestructureEvent(container) in audio_visualizer_restructure_event_reader.read() {
+        let Ok((audio_visualizer_settings, mut audio_visualizer_columns, mesh_handle)) = audio_visualizer_container_query.get_mut(*container) else {
+            continue;
+        };
 
-    build_audio_visualizer(&mut commands, audio_visualizer_settings, meshes, materials);
-    commands.entity(current_audio_visualizer).despawn_recursive();
+        *audio_visualizer_columns = AudioVisualizerColumns::new(audio_visualizer_settings.column_count, audio_visualizer_columns.radius);
+        meshes.insert(mesh_handle.0.id(), build_audio_visualizer_mesh(audio_visualizer_settings, &audio_visualizer_columns));
+    }
 }
 
-fn build_audio_visualizer(
-    commands: &mut Commands,
-    audio_visualizer_settings: ResMut<AudioVisualizerSettings>,
+pub fn handle_audio_visualizer_transition_events(
+    mut commands: Commands,
+    mut audio_visualizer_transition_event_reader: EventReader<AudioVisualizerTransitionEvent>,
+    mut audio_visualizer_restructure_event_writer: EventWriter<AudioVisualizerRestructureEvent>,
+    mut audio_visualizer_container_query: Query<(&mut AudioVisualizerSettings, &Transform), With<AudioVisualizerContainer>>,
+    mut selected_audio_visualizer: ResMut<SelectedAudioVisualizer>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>
+    mut materials: ResMut<Assets<AudioVisualizerColumnMaterial>>
 ) {
-    commands.spawn((
+    for audio_visualizer_transition_event in audio_visualizer_transition_event_reader.read() {
+        let container = audio_visualizer_transition_event.container;
+        let Ok((mut audio_visualizer_settings, transform)) = audio_visualizer_container_query.get_mut(container) else {
+            continue;
+        };
+
+        if audio_visualizer_transition_event.duration <= 0.0 {
+            *audio_visualizer_settings = audio_visualizer_transition_event.settings.clone();
+            commands.entity(container).remove::<AudioVisualizerTransition>();
+            audio_visualizer_restructure_event_writer.send(AudioVisualizerRestructureEvent(container));
+            continue;
+        }
+
+        let structural_change = audio_visualizer_settings.column_count != audio_visualizer_transition_event.settings.column_count
+            || audio_visualizer_settings.visualizer_type != audio_visualizer_transition_event.settings.visualizer_type;
+
+        if structural_change {
+            let audio_visualizer_fade = AudioVisualizerFade::new(true, audio_visualizer_transition_event.duration, audio_visualizer_transition_event.easing);
+            let new_container = spawn_audio_visualizer(&mut commands, audio_visualizer_transition_event.settings.clone(), Some(audio_visualizer_fade), &mut meshes, &mut materials);
+            commands.entity(new_container).insert(
+                Transform::from_translation(audio_visualizer_transition_event.settings.position.extend(0.0)).with_rotation(transform.rotation)
+            );
+            commands.entity(container)
+                .remove::<AudioVisualizerTransition>()
+                .insert(AudioVisualizerFade::new(false, audio_visualizer_transition_event.duration, audio_visualizer_transition_event.easing));
+
+            if selected_audio_visualizer.0 == Some(container) {
+                selected_audio_visualizer.0 = Some(new_container);
+            }
+        } else {
+            commands.entity(container).insert(AudioVisualizerTransition {
+                from: audio_visualizer_settings.clone(),
+                to: audio_visualizer_transition_event.settings.clone(),
+                timer: Timer::from_seconds(audio_visualizer_transition_event.duration, TimerMode::Once),
+                easing: audio_visualizer_transition_event.easing
+            });
+        }
+    }
+}
+
+pub fn update_audio_visualizer_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut audio_visualizer_container_query: Query<(Entity, &mut AudioVisualizerSettings, &mut AudioVisualizerTransition), With<AudioVisualizerContainer>>
+) {
+    for (container, mut audio_visualizer_settings, mut audio_visualizer_transition) in audio_visualizer_container_query.iter_mut() {
+        audio_visualizer_transition.timer.tick(time.delta());
+        let t = audio_visualizer_transition.easing.ease(audio_visualizer_transition.timer.fraction());
+
+        let interpolated_settings = interpolate_audio_visualizer_settings(&audio_visualizer_transition.from, &audio_visualizer_transition.to, t);
+        audio_visualizer_settings.apply_preserving_color_transition(interpolated_settings);
+
+        if audio_visualizer_transition.timer.finished() {
+            commands.entity(container).remove::<AudioVisualizerTransition>();
+        }
+    }
+}
+
+pub fn update_audio_visualizer_fade(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut audio_visualizer_container_query: Query<(Entity, &mut AudioVisualizerFade), With<AudioVisualizerContainer>>
+) {
+    for (container, mut audio_visualizer_fade) in audio_visualizer_container_query.iter_mut() {
+        audio_visualizer_fade.timer.tick(time.delta());
+        let t = audio_visualizer_fade.easing.ease(audio_visualizer_fade.timer.fraction());
+        audio_visualizer_fade.opacity = if audio_visualizer_fade.fade_in { t } else { 1.0 - t };
+
+        if audio_visualizer_fade.timer.finished() {
+            if audio_visualizer_fade.fade_in {
+                commands.entity(container).remove::<AudioVisualizerFade>();
+            } else {
+                commands.entity(container).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn interpolate_audio_visualizer_settings(
+    from: &AudioVisualizerSettings,
+    to: &AudioVisualizerSettings,
+    t: f32
+) -> AudioVisualizerSettings {
+    let t = t.clamp(0.0, 1.0);
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    let mut settings = if t < 0.5 { from.clone() } else { to.clone() };
+
+    settings.position = from.position.lerp(to.position, t);
+    settings.lower_frequency_limit = lerp(from.lower_frequency_limit, to.lower_frequency_limit);
+    settings.upper_frequency_limit = lerp(from.upper_frequency_limit, to.upper_frequency_limit);
+    settings.sampling_rate = lerp(from.sampling_rate as f32, to.sampling_rate as f32).ceil() as u32;
+    settings.radius = lerp(from.radius, to.radius);
+    settings.rotation_speed = lerp(from.rotation_speed, to.rotation_speed);
+    settings.column_width = lerp(from.column_width, to.column_width);
+    settings.max_height = lerp(from.max_height, to.max_height);
+    settings.scale_strenght = lerp(from.scale_strenght, to.scale_strenght);
+    settings.scale_threshold = lerp(from.scale_threshold, to.scale_threshold);
+
+    settings.normal_color_transition_speed = lerp(from.normal_color_transition_speed.abs(), to.normal_color_transition_speed.abs());
+    settings.normal_primary_color = lerp_color(from.normal_primary_color, to.normal_primary_color, t);
+    settings.normal_secondary_color = lerp_color(from.normal_secondary_color, to.normal_secondary_color, t);
+    settings.normal_primary_color_hdr_multiplier = lerp(from.normal_primary_color_hdr_multiplier, to.normal_primary_color_hdr_multiplier);
+    settings.normal_secondary_color_hdr_multiplier = lerp(from.normal_secondary_color_hdr_multiplier, to.normal_secondary_color_hdr_multiplier);
+    settings.highlight_color_transition_speed = lerp(from.highlight_color_transition_speed.abs(), to.highlight_color_transition_speed.abs());
+    settings.highlight_primary_color = lerp_color(from.highlight_primary_color, to.highlight_primary_color, t);
+    settings.highlight_secondary_color = lerp_color(from.highlight_secondary_color, to.highlight_secondary_color, t);
+    settings.highlight_primary_color_hdr_multiplier = lerp(from.highlight_primary_color_hdr_multiplier, to.highlight_primary_color_hdr_multiplier);
+    settings.highlight_secondary_color_hdr_multiplier = lerp(from.highlight_secondary_color_hdr_multiplier, to.highlight_secondary_color_hdr_multiplier);
+
+    if let Some(frequency_gradient) = interpolate_color_gradient(&from.frequency_gradient, &to.frequency_gradient, t) {
+        settings.frequency_gradient = frequency_gradient;
+    }
+    if let Some(height_gradient) = interpolate_color_gradient(&from.height_gradient, &to.height_gradient, t) {
+        settings.height_gradient = height_gradient;
+    }
+
+    settings.column_cap_roundness = lerp(from.column_cap_roundness, to.column_cap_roundness);
+    settings.column_edge_softness = lerp(from.column_edge_softness, to.column_edge_softness);
+    settings.column_glow_radius = lerp(from.column_glow_radius, to.column_glow_radius);
+    settings.column_glow_strength = lerp(from.column_glow_strength, to.column_glow_strength);
+    settings.column_base_intensity = lerp(from.column_base_intensity, to.column_base_intensity);
+    settings.amplitude_intensity_strength = lerp(from.amplitude_intensity_strength, to.amplitude_intensity_strength);
+
+    settings
+}
+
+fn interpolate_color_gradient(
+    from: &ColorGradient,
+    to: &ColorGradient,
+    t: f32
+) -> Option<ColorGradient> {
+    if from.stops.len() != to.stops.len() {
+        return None;
+    }
+
+    let stops = from.stops.iter().zip(&to.stops)
+        .map(|(from_stop, to_stop)| ColorGradientStop {
+            position: from_stop.position + (to_stop.position - from_stop.position) * t,
+            color: lerp_color(from_stop.color, to_stop.color, t),
+            hdr_multiplier: from_stop.hdr_multiplier + (to_stop.hdr_multiplier - from_stop.hdr_multiplier) * t
+        })
+        .collect();
+
+    Some(ColorGradient { stops })
+}
+
+fn spawn_audio_visualizer(
+    commands: &mut Commands,
+    audio_visualizer_settings: AudioVisualizerSettings,
+    audio_visualizer_fade: Option<AudioVisualizerFade>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<AudioVisualizerColumnMaterial>
+) -> Entity {
+    let audio_visualizer_columns = AudioVisualizerColumns::new(audio_visualizer_settings.column_count, audio_visualizer_settings.radius);
+    let material = AudioVisualizerColumnMaterial {
+        uniform: audio_visualizer_column_material_uniform(&audio_visualizer_settings, &AudioVisualizerAnalysis::default(), audio_visualizer_fade.as_ref())
+    };
+
+    let mut container = commands.spawn((
         MaterialMesh2dBundle {
-            mesh: meshes.add(Rectangle::new(1.0, 1.0)).into(),
-            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.0)),
+            mesh: meshes.add(build_audio_visualizer_mesh(&audio_visualizer_settings, &audio_visualizer_columns)).into(),
+            material: materials.add(material),
+            transform: Transform::from_translation(audio_visualizer_settings.position.extend(0.0)),
             ..default()
         },
-        AudioVisualizerContainer
-    )
-    ).with_children(|parent| {
-        for i in 0..audio_visualizer_settings.column_count {
-            let angle = i as f32 * 2.0 * PI / audio_visualizer_settings.column_count as f32;
-            let x = audio_visualizer_settings.radius * angle.cos();
-            let y = audio_visualizer_settings.radius * angle.sin();
-    
-            parent.spawn((
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(Rectangle::new(audio_visualizer_settings.column_width, 1.0)).into(),
-                    material: audio_visualizer_settings.normal_color_material_handle.clone().unwrap(),
-                    transform: Transform::from_xyz(x, y, 0.0).with_rotation(Quat::from_rotation_z(angle + PI / 2.0)),
-                    ..default()
-                },
-                AudioVisualizerColumn
-            ));
+        NoFrustumCulling,
+        AudioVisualizerContainer,
+        AudioVisualizerAnalysis::default(),
+        audio_visualizer_columns,
+        audio_visualizer_settings
+    ));
+    if let Some(audio_visualizer_fade) = audio_visualizer_fade {
+        container.insert(audio_visualizer_fade);
+    }
+
+    container.id()
+}
+
+fn build_audio_visualizer_mesh(
+    audio_visualizer_settings: &AudioVisualizerSettings,
+    audio_visualizer_columns: &AudioVisualizerColumns
+) -> Mesh {
+    let indices = (0..audio_visualizer_columns.heights.len() as u32)
+        .flat_map(|i| [i * 4, i * 4 + 1, i * 4 + 2, i * 4 + 2, i * 4 + 3, i * 4])
+        .collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+    mesh.insert_indices(Indices::U32(indices));
+    write_audio_visualizer_mesh_vertices(&mut mesh, audio_visualizer_settings, audio_visualizer_columns);
+
+    mesh
+}
+
+fn write_audio_visualizer_mesh_vertices(
+    mesh: &mut Mesh,
+    audio_visualizer_settings: &AudioVisualizerSettings,
+    audio_visualizer_columns: &AudioVisualizerColumns
+) {
+    let column_count = audio_visualizer_columns.heights.len();
+    let padding = audio_visualizer_settings.column_edge_softness + audio_visualizer_settings.column_glow_radius;
+    let half_width = audio_visualizer_settings.column_width / 2.0 + padding;
+
+    let mut positions = Vec::with_capacity(column_count * 4);
+    let mut offsets = Vec::with_capacity(column_count * 4);
+    let mut sizes = Vec::with_capacity(column_count * 4);
+    let mut colors = Vec::with_capacity(column_count * 4);
+
+    for (i, (&height, &highlighted)) in audio_visualizer_columns.heights.iter().zip(&audio_visualizer_columns.highlighted).enumerate() {
+        let angle = i as f32 * 2.0 * PI / column_count as f32;
+        let radial = Vec2::new(angle.cos(), angle.sin());
+        let tangent = radial.perp();
+        let center = radial * audio_visualizer_columns.radius;
+        let half_height = height / 2.0 + padding;
+
+        let column_offsets = [
+            Vec2::new(-half_width, -half_height),
+            Vec2::new(half_width, -half_height),
+            Vec2::new(half_width, half_height),
+            Vec2::new(-half_width, half_height)
+        ];
+        positions.extend(column_offsets.map(|offset| (center + tangent * offset.x + radial * offset.y).extend(0.0).to_array()));
+        offsets.extend(column_offsets.map(|offset| offset.to_array()));
+        sizes.extend([[audio_visualizer_settings.column_width, height]; 4]);
+
+        let frequency_position = i as f32 / column_count as f32;
+        let height_position = height / audio_visualizer_settings.max_height;
+        let base_height_position = if audio_visualizer_settings.height_gradient_along_column { 0.0 } else { height_position };
+        let (base_color, tip_color) = match audio_visualizer_settings.color_mode {
+            ColorMode::Solid if highlighted => (audio_visualizer_settings.highlight_current_color, audio_visualizer_settings.highlight_current_color),
+            ColorMode::Solid => (audio_visualizer_settings.normal_current_color, audio_visualizer_settings.normal_current_color),
+            ColorMode::Frequency => {
+                let color = sample_color_gradient(&audio_visualizer_settings.frequency_gradient, frequency_position);
+                (color, color)
+            },
+            ColorMode::Height => (
+                sample_color_gradient(&audio_visualizer_settings.height_gradient, base_height_position),
+                sample_color_gradient(&audio_visualizer_settings.height_gradient, height_position)
+            ),
+            ColorMode::FrequencyAndHeight => {
+                let color = sample_color_gradient(&audio_visualizer_settings.frequency_gradient, frequency_position);
+                (
+                    color * Vec4::from(sample_color_gradient(&audio_visualizer_settings.height_gradient, base_height_position).as_rgba_f32()),
+                    color * Vec4::from(sample_color_gradient(&audio_visualizer_settings.height_gradient, height_position).as_rgba_f32())
+                )
+            }
+        };
+        let (base_color, tip_color) = (base_color.as_linear_rgba_f32(), tip_color.as_linear_rgba_f32());
+        colors.extend([base_color, base_color, tip_color, tip_color]);
+    }
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(ATTRIBUTE_COLUMN_OFFSET, offsets);
+    mesh.insert_attribute(ATTRIBUTE_COLUMN_SIZE, sizes);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+}
+
+pub fn update_audio_visualizer_mesh(
+    audio_visualizer_container_query: Query<(&AudioVisualizerSettings, &AudioVisualizerColumns, &Mesh2dHandle), With<AudioVisualizerContainer>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    audio_visualizer_update_timer: Res<AudioVisualizerUpdateTimer>
+) {
+    if audio_visualizer_update_timer.timer.just_finished() {
+        for (audio_visualizer_settings, audio_visualizer_columns, mesh_handle) in audio_visualizer_container_query.iter() {
+            if let Some(mesh) = meshes.get_mut(&mesh_handle.0) {
+                write_audio_visualizer_mesh_vertices(mesh, audio_visualizer_settings, audio_visualizer_columns);
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_audio_visualizer_material(
+    audio_visualizer_container_query: Query<(&AudioVisualizerSettings, &AudioVisualizerAnalysis, &Handle<AudioVisualizerColumnMaterial>, Option<&AudioVisualizerFade>), With<AudioVisualizerContainer>>,
+    mut materials: ResMut<Assets<AudioVisualizerColumnMaterial>>,
+    audio_visualizer_update_timer: Res<AudioVisualizerUpdateTimer>
+) {
+    if audio_visualizer_update_timer.timer.just_finished() {
+        for (audio_visualizer_settings, audio_visualizer_analysis, material_handle, audio_visualizer_fade) in audio_visualizer_container_query.iter() {
+            if let Some(material) = materials.get_mut(material_handle) {
+                material.uniform = audio_visualizer_column_material_uniform(audio_visualizer_settings, audio_visualizer_analysis, audio_visualizer_fade);
+            }
         }
-    });
+    }
+}
+
+fn audio_visualizer_column_material_uniform(
+    audio_visualizer_settings: &AudioVisualizerSettings,
+    audio_visualizer_analysis: &AudioVisualizerAnalysis,
+    audio_visualizer_fade: Option<&AudioVisualizerFade>
+) -> AudioVisualizerColumnMaterialUniform {
+    AudioVisualizerColumnMaterialUniform {
+        cap_roundness: audio_visualizer_settings.column_cap_roundness,
+        edge_softness: audio_visualizer_settings.column_edge_softness,
+        glow_radius: audio_visualizer_settings.column_glow_radius,
+        glow_strength: audio_visualizer_settings.column_glow_strength,
+        base_intensity: audio_visualizer_settings.column_base_intensity,
+        intensity: 1.0 + audio_visualizer_analysis.average_frequency_value * audio_visualizer_settings.amplitude_intensity_strength,
+        opacity: audio_visualizer_fade.map_or(1.0, |audio_visualizer_fade| audio_visualizer_fade.opacity)
+    }
 }
 
 pub fn tick_audio_visualizer_update_timer(
@@ -82,242 +453,199 @@ pub fn tick_audio_visualizer_update_timer(
 }
 
 pub fn visualize_audio_spectrum(
-    audio_data: NonSend<AudioData>,
-    mut column_query: Query<(&mut Transform, &mut Handle<ColorMaterial>), With<AudioVisualizerColumn>>,
+    audio_samples: Res<AudioSamples>,
+    mut audio_visualizer_container_query: Query<(&AudioVisualizerSettings, &mut AudioVisualizerAnalysis, &mut AudioVisualizerColumns), With<AudioVisualizerContainer>>,
     window_query: Query<&Window, With<PrimaryWindow>>,
-    audio_visualizer_settings: Res<AudioVisualizerSettings>,
-    audio_visualizer_update_timer: ResMut<AudioVisualizerUpdateTimer>
+    audio_visualizer_update_timer: Res<AudioVisualizerUpdateTimer>
+,
+    mut missing_data: Local<bool>
 ) {
-    if audio_visualizer_update_timer.timer.just_finished() {
-        let spectrum_data = audio_data.latest_audio_data.lock().unwrap();
-        
-        if spectrum_data.len() == audio_visualizer_settings.spectrum_data_length {
-            let window = window_query.get_single().unwrap();
-
-            let max_height = window.height();
-
-            let mut highest_spectrum_value = 0.0;
-            let mut combined_spectrums: [f32; FIXED_ARRAY_SIZE] = [0.0; FIXED_ARRAY_SIZE];
-            let sum_range = spectrum_data.len() / audio_visualizer_settings.column_count;
-            for i in 0..audio_visualizer_settings.column_count {
-                for j in i * sum_range..(i + 1) * sum_range {
-                    combined_spectrums[i] += spectrum_data[j];
-                    if spectrum_data[i] > highest_spectrum_value {
-                        highest_spectrum_value = spectrum_data[i];
-                    }
-                }
-            }
+    if !audio_visualizer_update_timer.timer.just_finished() {
+        return;
+    }
 
-            for (i, (mut transform, mut material)) in column_query.iter_mut().enumerate() {
-                let new_height;
-                let mut smooth_spectrum = 0.0;
-                let mut index: i32 = (i as i32 - (audio_visualizer_settings.smoothing_range / 2) as i32 + audio_visualizer_settings.column_count as i32) % (audio_visualizer_settings.column_count) as i32;
-                for _ in 0..audio_visualizer_settings.smoothing_range {
-                    smooth_spectrum += combined_spectrums[index as usize];
-                    index = (index + 1) % audio_visualizer_settings.column_count as i32;
-                }
-                combined_spectrums[i] = smooth_spectrum / audio_visualizer_settings.smoothing_range as f32;
-                new_height = combined_spectrums[i] / sum_range as f32 * max_height / highest_spectrum_value;
-                
-                if new_height.is_nan() {
-                    transform.scale.y = 1.0;
-                } else {
-                    transform.scale.y = new_height.clamp(1.0, audio_visualizer_settings.max_height);
-                }
+    // Apps without a primary window scale the columns to their maximum height instead of the window height
+    let window_height = window_query.get_single().ok().map(|window| window.height());
 
-                if combined_spectrums[i] / sum_range as f32 > 0.0 {
-                    *material = audio_visualizer_settings.highlight_color_material_handle.clone().unwrap()
-                } else {
-                    *material = audio_visualizer_settings.normal_color_material_handle.clone().unwrap()
-                }
+    for (audio_visualizer_settings, mut audio_visualizer_analysis, mut audio_visualizer_columns) in audio_visualizer_container_query.iter_mut() {
+        if audio_visualizer_settings.visualizer_type != VisualilzerType::SpectrumVisualizer {
+            continue;
+        }
+
+        let column_count = audio_visualizer_columns.heights.len();
+
+        let audio_analysis_frame = analyze_audio(&audio_samples.samples, audio_visualizer_settings, column_count);
+        if let Some(audio_analysis_frame) = &audio_analysis_frame {
+            let max_height = window_height.unwrap_or(audio_visualizer_settings.max_height);
+
+            for i in 0..column_count {
+                audio_visualizer_columns.heights[i] = (audio_analysis_frame.values[i] * max_height).clamp(1.0, audio_visualizer_settings.max_height);
+                audio_visualizer_columns.highlighted[i] = audio_analysis_frame.highlighted[i];
             }
-        } else {
-            println!("Not enough data to visualize!");
         }
+        audio_visualizer_analysis.frame = audio_analysis_frame;
     }
+
+    warn_on_missing_audio_data(&audio_visualizer_container_query, VisualilzerType::SpectrumVisualizer, &mut missing_data);
 }
 
 pub fn visualize_audio_frequency(
-    mut audio_data: NonSendMut<AudioData>,
-    mut column_query: Query<(&mut Transform, &mut Handle<ColorMaterial>), With<AudioVisualizerColumn>>,
-    audio_visualizer_settings: Res<AudioVisualizerSettings>,
-    audio_visualizer_update_timer: ResMut<AudioVisualizerUpdateTimer>
+    audio_samples: Res<AudioSamples>,
+    mut audio_visualizer_container_query: Query<(&AudioVisualizerSettings, &mut AudioVisualizerAnalysis, &mut AudioVisualizerColumns), With<AudioVisualizerContainer>>,
+    audio_visualizer_update_timer: Res<AudioVisualizerUpdateTimer>
+,
+    mut missing_data: Local<bool>
 ) {
-    if audio_visualizer_update_timer.timer.just_finished() {
-        let spectrum_data = audio_data.latest_audio_data.lock().unwrap();
-        
-        if spectrum_data.len() == audio_visualizer_settings.spectrum_data_length {
-            let mut spectrum_data_clone: [f32; SPECTRUM_DATA_LENGTH] = spectrum_data.iter().copied().collect::<Vec<f32>>().try_into().unwrap();
-            drop(spectrum_data);
-
-            match audio_visualizer_settings.window_function {
-                WindowFunction::Hann => {
-                    spectrum_data_clone = hann_window(&spectrum_data_clone).try_into().unwrap();
-                },
-                WindowFunction::Hamming => {
-                    spectrum_data_clone = hamming_window(&spectrum_data_clone).try_into().unwrap();
-                },
-                _ => {}
-            }
+    if !audio_visualizer_update_timer.timer.just_finished() {
+        return;
+    }
 
-            let frequencies = samples_fft_to_spectrum(
-                &spectrum_data_clone,
-                audio_visualizer_settings.sampling_rate,
-                FrequencyLimit::Range(audio_visualizer_settings.lower_frequency_limit, audio_visualizer_settings.upper_frequency_limit),
-                Some(&scale_to_zero_to_one),
-            ).unwrap();
-
-            audio_data.latest_average_frequency_value = frequencies.average().val();
-
-            let frequencies_data = frequencies.data();
-            let frequencies_len = frequencies_data.len();
-            let column_count = audio_visualizer_settings.column_count;
-            let mut combined_frequencies: [f32; FIXED_ARRAY_SIZE] = [0.0; FIXED_ARRAY_SIZE];
-
-            if frequencies_len >= column_count {
-                let sum_range = frequencies_len / column_count;
-                for (i, freq) in combined_frequencies.iter_mut().enumerate().take(column_count) {
-                    let start_index = i * sum_range;
-                    let end_index = if i == column_count - 1 {
-                        frequencies_len
-                    } else {
-                        (i + 1) * sum_range
-                    };
-                    *freq = frequencies_data[start_index..end_index].iter().map(|(_, val)| val.val()).sum::<f32>() / sum_range as f32;
-                }
-            } else {
-                let columns_per_frequency = column_count / frequencies_len;
-                let extra_columns = column_count % frequencies_len;
-                let mut current_column = 0;
-
-                for (i, (_, freq)) in frequencies_data.iter().enumerate() {
-                    let count = if i < extra_columns { columns_per_frequency + 1 } else { columns_per_frequency };
-                    for _ in 0..count {
-                        combined_frequencies[current_column] = freq.val();
-                        current_column += 1;
-                    }
-                }
-            }
+    for (audio_visualizer_settings, mut audio_visualizer_analysis, mut audio_visualizer_columns) in audio_visualizer_container_query.iter_mut() {
+        if audio_visualizer_settings.visualizer_type != VisualilzerType::FrequencyVisualizer {
+            continue;
+        }
 
-            let section_count = audio_visualizer_settings.section_count;
+        let column_count = audio_visualizer_columns.heights.len();
+
+        let audio_analysis_frame = analyze_audio(&audio_samples.samples, audio_visualizer_settings, column_count);
+        if let Some(audio_analysis_frame) = &audio_analysis_frame {
             let max_height = audio_visualizer_settings.max_height;
-            let smoothing_range = audio_visualizer_settings.smoothing_range as i32;
-            let half_smoothing_range = smoothing_range / 2;
-            let highlight_color = audio_visualizer_settings.highlight_color_material_handle.clone().unwrap();
-            let normal_color = audio_visualizer_settings.normal_color_material_handle.clone().unwrap();
-            let max_frequency_val = frequencies.max().1.val();
-            let highlighted_frequency_threshold = frequencies.average().val() * 2.0;
-
-            for (i, (mut transform, mut material)) in column_query.iter_mut().enumerate() {
-                let clamped_index = i % (column_count / section_count);
-
-                let new_height = if i < column_count / section_count {
-                    let mut smooth_frequency = 0.0;
-                    let mut index = (clamped_index as i32 - half_smoothing_range + column_count as i32 / section_count as i32) % (column_count / section_count) as i32;
-                    for _ in 0..smoothing_range {
-                        smooth_frequency += combined_frequencies[index as usize];
-                        index = (index + 1) % (column_count / section_count) as i32;
-                    }
-                    combined_frequencies[clamped_index] = smooth_frequency / smoothing_range as f32;
-                    combined_frequencies[clamped_index] * max_height / max_frequency_val
-                } else {
-                    let opposite_index = (i - column_count / section_count) % (column_count / section_count);
-                    combined_frequencies[opposite_index] * max_height / max_frequency_val
-                };
-
-                transform.scale.y = if new_height.is_nan() { 1.0 } else { new_height.clamp(1.0, max_height) };
-
-                *material = if combined_frequencies[clamped_index] > highlighted_frequency_threshold {
-                    highlight_color.clone()
-                } else {
-                    normal_color.clone()
-                };
+
+            for i in 0..column_count {
+                audio_visualizer_columns.heights[i] = (audio_analysis_frame.values[i] * max_height).clamp(1.0, max_height);
+                audio_visualizer_columns.highlighted[i] = audio_analysis_frame.highlighted[i];
             }
-        } else {
-            println!("Not enough data to visualize!");
+            audio_visualizer_analysis.average_frequency_value = audio_analysis_frame.average_value;
         }
+        audio_visualizer_analysis.frame = audio_analysis_frame;
     }
+
+    warn_on_missing_audio_data(&audio_visualizer_container_query, VisualilzerType::FrequencyVisualizer, &mut missing_data);
+}
+
+// Only warns when the visualizers of a type go from having enough audio data to not having it, not on every update
+fn warn_on_missing_audio_data(
+    audio_visualizer_container_query: &Query<(&AudioVisualizerSettings, &mut AudioVisualizerAnalysis, &mut AudioVisualizerColumns), With<AudioVisualizerContainer>>,
+    visualizer_type: VisualilzerType,
+    missing_data: &mut bool
+) {
+    let is_missing_data = audio_visualizer_container_query.iter()
+        .any(|(audio_visualizer_settings, audio_visualizer_analysis, _)| audio_visualizer_settings.visualizer_type == visualizer_type && audio_visualizer_analysis.frame.is_none());
+
+    if is_missing_data && !*missing_data {
+        warn!("Not enough audio data to visualize!");
+    }
+    *missing_data = is_missing_data;
 }
 
 pub fn update_audio_visualizer_scale(
-    audio_data: NonSend<AudioData>,
-    mut audio_visulizer_container_query: Query<&mut Transform, With<AudioVisualizerColumn>>,
-    audio_visualizer_settings: Res<AudioVisualizerSettings>,
-    audio_visualizer_update_timer: ResMut<AudioVisualizerUpdateTimer>
+    mut audio_visualizer_container_query: Query<(&AudioVisualizerSettings, &AudioVisualizerAnalysis, &mut AudioVisualizerColumns), With<AudioVisualizerContainer>>,
+    audio_visualizer_update_timer: Res<AudioVisualizerUpdateTimer>
 ) {
     if audio_visualizer_update_timer.timer.just_finished() {
-        let radius_scaler = audio_data.latest_average_frequency_value * audio_visualizer_settings.scale_strenght;
-        for (i, mut transform) in audio_visulizer_container_query.iter_mut().enumerate() {
-            let current_x = transform.translation.x;
-            let current_y = transform.translation.y;
-            let angle = i as f32 * audio_visualizer_settings.angle_increment;
-            let x = (audio_visualizer_settings.radius + radius_scaler) * angle.cos();
-            let y = (audio_visualizer_settings.radius + radius_scaler) * angle.sin();
-
-            transform.translation.x = (x - current_x) / audio_visualizer_settings.scale_threshold + current_x;
-            transform.translation.y = (y - current_y) / audio_visualizer_settings.scale_threshold + current_y;
+        for (audio_visualizer_settings, audio_visualizer_analysis, mut audio_visualizer_columns) in audio_visualizer_container_query.iter_mut() {
+            let radius_scaler = audio_visualizer_analysis.average_frequency_value * audio_visualizer_settings.scale_strenght;
+            let radius = audio_visualizer_settings.radius + radius_scaler;
+
+            audio_visualizer_columns.radius += (radius - audio_visualizer_columns.radius) / audio_visualizer_settings.scale_threshold;
         }
     }
 }
 
 pub fn update_audio_visualizer_rotation(
-    mut audio_visulizer_container_query: Query<&mut Transform, With<AudioVisualizerContainer>>,
-    audio_visulizer_settings: Res<AudioVisualizerSettings>,
-    audio_visualizer_update_timer: ResMut<AudioVisualizerUpdateTimer>
+    mut audio_visualizer_container_query: Query<(&mut Transform, &AudioVisualizerSettings), With<AudioVisualizerContainer>>,
+    audio_visualizer_update_timer: Res<AudioVisualizerUpdateTimer>
 ) {
-    if audio_visualizer_update_timer.timer.just_finished() && audio_visulizer_settings.rotation_speed != 0.0 {
-        let mut audio_visualizer_container = audio_visulizer_container_query.get_single_mut().unwrap();
-        audio_visualizer_container.rotation *= Quat::from_rotation_z(-audio_visulizer_settings.rotation_speed);
+    if audio_visualizer_update_timer.timer.just_finished() {
+        for (mut audio_visualizer_container, audio_visualizer_settings) in audio_visualizer_container_query.iter_mut() {
+            if audio_visualizer_settings.rotation_speed != 0.0 {
+                audio_visualizer_container.rotation *= Quat::from_rotation_z(-audio_visualizer_settings.rotation_speed);
+            }
+        }
     }
 }
 
-pub fn center_audio_visualizer(
-    mut audio_visulizer_container_query: Query<&mut Transform, With<AudioVisualizerContainer>>
+#[allow(clippy::type_complexity)]
+pub fn update_audio_visualizer_position(
+    mut audio_visualizer_container_query: Query<(&mut Transform, &AudioVisualizerSettings), (With<AudioVisualizerContainer>, Changed<AudioVisualizerSettings>)>
 ) {
-    let mut audio_visualizer_container = audio_visulizer_container_query.get_single_mut().unwrap();
-    audio_visualizer_container.translation = Vec3::new(0.0, 0.0, 0.0);
+    for (mut audio_visualizer_container, audio_visualizer_settings) in audio_visualizer_container_query.iter_mut() {
+        audio_visualizer_container.translation = audio_visualizer_settings.position.extend(audio_visualizer_container.translation.z);
+    }
 }
 
 pub fn update_color_transition(
-    audio_visualizer_update_timer: ResMut<AudioVisualizerUpdateTimer>,
-    mut audio_visualizer_settings: ResMut<AudioVisualizerSettings>,
-    mut materials: ResMut<Assets<ColorMaterial>>
+    audio_visualizer_update_timer: Res<AudioVisualizerUpdateTimer>,
+    mut audio_visualizer_container_query: Query<&mut AudioVisualizerSettings, With<AudioVisualizerContainer>>
 ) {
     if audio_visualizer_update_timer.timer.just_finished() {
-        let normal_material = materials.get_mut(audio_visualizer_settings.normal_color_material_handle.clone().unwrap()).unwrap();
-        if audio_visualizer_settings.normal_color_transition_enabled {
-            if audio_visualizer_settings.normal_color_transition_progress > 1.0 && audio_visualizer_settings.normal_color_transition_speed > 0.0 || audio_visualizer_settings.normal_color_transition_progress < 0.0 && audio_visualizer_settings.normal_color_transition_speed < 0.0 {
-                audio_visualizer_settings.normal_color_transition_speed *= -1.0;
-            }
-            audio_visualizer_settings.normal_color_transition_progress += audio_visualizer_settings.normal_color_transition_speed;
-            let updated_color = lerp_color(audio_visualizer_settings.normal_primary_color, audio_visualizer_settings.normal_secondary_color, audio_visualizer_settings.normal_color_transition_progress);
-            let hdr_multiplier = audio_visualizer_settings.normal_primary_color_hdr_multiplier + (audio_visualizer_settings.normal_secondary_color_hdr_multiplier - audio_visualizer_settings.normal_primary_color_hdr_multiplier) * audio_visualizer_settings.normal_color_transition_progress;
-            let mut updated_color_rgba = updated_color.as_rgba_f32();
-            updated_color_rgba.iter_mut().take(3).for_each(|c| *c *= hdr_multiplier);
-            normal_material.color = Color::rgba_from_array(updated_color_rgba);
-        } else {
-            let mut updated_color_rgba = audio_visualizer_settings.normal_primary_color.as_rgba_f32();
-            updated_color_rgba.iter_mut().take(3).for_each(|c| *c *= audio_visualizer_settings.normal_primary_color_hdr_multiplier);
-            normal_material.color = Color::rgba_from_array(updated_color_rgba);
+        for mut audio_visualizer_settings in audio_visualizer_container_query.iter_mut() {
+            update_instance_color_transition(&mut audio_visualizer_settings);
         }
+    }
+}
 
-        let highlight_material = materials.get_mut(audio_visualizer_settings.highlight_color_material_handle.clone().unwrap()).unwrap();
-        if audio_visualizer_settings.highlight_color_transition_enabled {
-            if audio_visualizer_settings.highlight_color_transition_progress > 1.0 && audio_visualizer_settings.highlight_color_transition_speed > 0.0 || audio_visualizer_settings.highlight_color_transition_progress < 0.0 && audio_visualizer_settings.highlight_color_transition_speed < 0.0 {
-                audio_visualizer_settings.highlight_color_transition_speed *= -1.0;
-            }
-            audio_visualizer_settings.highlight_color_transition_progress += audio_visualizer_settings.highlight_color_transition_speed;
-            let updated_color = lerp_color(audio_visualizer_settings.highlight_primary_color, audio_visualizer_settings.highlight_secondary_color, audio_visualizer_settings.highlight_color_transition_progress);
-            let hdr_multiplier = audio_visualizer_settings.highlight_primary_color_hdr_multiplier + (audio_visualizer_settings.highlight_secondary_color_hdr_multiplier - audio_visualizer_settings.highlight_primary_color_hdr_multiplier) * audio_visualizer_settings.highlight_color_transition_progress;
-            let mut updated_color_rgba = updated_color.as_rgba_f32();
-            updated_color_rgba.iter_mut().take(3).for_each(|c| *c *= hdr_multiplier);
-            highlight_material.color = Color::rgba_from_array(updated_color_rgba);
-        } else {
-            let mut updated_color_rgba = audio_visualizer_settings.highlight_primary_color.as_rgba_f32();
-            updated_color_rgba.iter_mut().take(3).for_each(|c| *c *= audio_visualizer_settings.highlight_primary_color_hdr_multiplier);
-            highlight_material.color = Color::rgba_from_array(updated_color_rgba);
+fn update_instance_color_transition(
+    audio_visualizer_settings: &mut AudioVisualizerSettings
+) {
+    if audio_visualizer_settings.normal_color_transition_enabled {
+        if audio_visualizer_settings.normal_color_transition_progress > 1.0 && audio_visualizer_settings.normal_color_transition_speed > 0.0 || audio_visualizer_settings.normal_color_transition_progress < 0.0 && audio_visualizer_settings.normal_color_transition_speed < 0.0 {
+            audio_visualizer_settings.normal_color_transition_speed *= -1.0;
+        }
+        audio_visualizer_settings.normal_color_transition_progress += audio_visualizer_settings.normal_color_transition_speed;
+        let updated_color = lerp_color(audio_visualizer_settings.normal_primary_color, audio_visualizer_settings.normal_secondary_color, audio_visualizer_settings.normal_color_transition_progress);
+        let hdr_multiplier = audio_visualizer_settings.normal_primary_color_hdr_multiplier + (audio_visualizer_settings.normal_secondary_color_hdr_multiplier - audio_visualizer_settings.normal_primary_color_hdr_multiplier) * audio_visualizer_settings.normal_color_transition_progress;
+        let mut updated_color_rgba = updated_color.as_rgba_f32();
+        updated_color_rgba.iter_mut().take(3).for_each(|c| *c *= hdr_multiplier);
+        audio_visualizer_settings.normal_current_color = Color::rgba_from_array(updated_color_rgba);
+    } else {
+        let mut updated_color_rgba = audio_visualizer_settings.normal_primary_color.as_rgba_f32();
+        updated_color_rgba.iter_mut().take(3).for_each(|c| *c *= audio_visualizer_settings.normal_primary_color_hdr_multiplier);
+        audio_visualizer_settings.normal_current_color = Color::rgba_from_array(updated_color_rgba);
+    }
+
+    if audio_visualizer_settings.highlight_color_transition_enabled {
+        if audio_visualizer_settings.highlight_color_transition_progress > 1.0 && audio_visualizer_settings.highlight_color_transition_speed > 0.0 || audio_visualizer_settings.highlight_color_transition_progress < 0.0 && audio_visualizer_settings.highlight_color_transition_speed < 0.0 {
+            audio_visualizer_settings.highlight_color_transition_speed *= -1.0;
+        }
+        audio_visualizer_settings.highlight_color_transition_progress += audio_visualizer_settings.highlight_color_transition_speed;
+        let updated_color = lerp_color(audio_visualizer_settings.highlight_primary_color, audio_visualizer_settings.highlight_secondary_color, audio_visualizer_settings.highlight_color_transition_progress);
+        let hdr_multiplier = audio_visualizer_settings.highlight_primary_color_hdr_multiplier + (audio_visualizer_settings.highlight_secondary_color_hdr_multiplier - audio_visualizer_settings.highlight_primary_color_hdr_multiplier) * audio_visualizer_settings.highlight_color_transition_progress;
+        let mut updated_color_rgba = updated_color.as_rgba_f32();
+        updated_color_rgba.iter_mut().take(3).for_each(|c| *c *= hdr_multiplier);
+        audio_visualizer_settings.highlight_current_color = Color::rgba_from_array(updated_color_rgba);
+    } else {
+        let mut updated_color_rgba = audio_visualizer_settings.highlight_primary_color.as_rgba_f32();
+        updated_color_rgba.iter_mut().take(3).for_each(|c| *c *= audio_visualizer_settings.highlight_primary_color_hdr_multiplier);
+        audio_visualizer_settings.highlight_current_color = Color::rgba_from_array(updated_color_rgba);
+    }
+}
+
+fn sample_color_gradient(
+    color_gradient: &ColorGradient,
+    position: f32
+) -> Color {
+    let hdr_color = |stop: &ColorGradientStop| {
+        let mut color_rgba = stop.color.as_rgba_f32();
+        color_rgba.iter_mut().take(3).for_each(|c| *c *= stop.hdr_multiplier);
+        Color::rgba_from_array(color_rgba)
+    };
+
+    let Some(first_stop) = color_gradient.stops.first() else {
+        return Color::WHITE;
+    };
+    if position <= first_stop.position {
+        return hdr_color(first_stop);
+    }
+
+    for stops in color_gradient.stops.windows(2) {
+        if position <= stops[1].position {
+            let t = (position - stops[0].position) / (stops[1].position - stops[0].position).max(f32::EPSILON);
+            return lerp_color(hdr_color(&stops[0]), hdr_color(&stops[1]), t);
         }
     }
+
+    hdr_color(color_gradient.stops.last().unwrap())
 }
 
 fn lerp_color(
@@ -331,4 +659,127 @@ fn lerp_color(
     result[2] = color1.b() + (color2.b() - color1.b()) * t;
     result[3] = color1.a() + (color2.a() - color1.a()) * t;
     Color::rgba(result[0], result[1], result[2], result[3])
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::audio_data::SPECTRUM_DATA_LENGTH;
+
+    fn transition_target() -> AudioVisualizerSettings {
+        AudioVisualizerSettings {
+            visualizer_type: VisualilzerType::SpectrumVisualizer,
+            position: Vec2::new(100.0, -50.0),
+            lower_frequency_limit: 40.0,
+            upper_frequency_limit: 3000.0,
+            sampling_rate: 8192,
+            column_count: 90,
+            radius: 400.0,
+            max_height: 800.0,
+            normal_primary_color: Color::BLUE,
+            column_glow_strength: 1.0,
+            ..default()
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn spectrum_is_visualized_without_a_window() {
+        let mut world = World::new();
+        let mut audio_visualizer_update_timer = AudioVisualizerUpdateTimer::default();
+        let timer_duration = audio_visualizer_update_timer.timer.duration();
+        audio_visualizer_update_timer.timer.tick(timer_duration);
+        world.insert_resource(audio_visualizer_update_timer);
+        world.insert_resource(AudioSamples {
+            samples: (0..SPECTRUM_DATA_LENGTH).map(|i| (2.0 * PI * 440.0 * i as f32 / 8192.0).sin()).collect()
+        });
+
+        let audio_visualizer_settings = transition_target();
+        let column_count = audio_visualizer_settings.column_count;
+        let max_height = audio_visualizer_settings.max_height;
+        let audio_visualizer = world.spawn((
+            audio_visualizer_settings,
+            AudioVisualizerAnalysis::default(),
+            AudioVisualizerColumns::new(column_count, 400.0),
+            AudioVisualizerContainer
+        )).id();
+
+        world.run_system_once(visualize_audio_spectrum);
+
+        let audio_visualizer_columns = world.get::<AudioVisualizerColumns>(audio_visualizer).unwrap();
+        assert!(world.get::<AudioVisualizerAnalysis>(audio_visualizer).unwrap().frame.is_some());
+        assert!(audio_visualizer_columns.heights.iter().all(|height| (1.0..=max_height).contains(height)));
+        assert!(audio_visualizer_columns.heights.iter().any(|height| *height > 1.0));
+    }
+
+    #[test]
+    fn interpolation_starts_at_from_settings() {
+        let from = AudioVisualizerSettings::default();
+        let settings = interpolate_audio_visualizer_settings(&from, &transition_target(), 0.0);
+
+        assert!(settings == from);
+    }
+
+    #[test]
+    fn interpolation_ends_at_to_settings() {
+        let to = transition_target();
+        let settings = interpolate_audio_visualizer_settings(&AudioVisualizerSettings::default(), &to, 1.0);
+
+        assert_eq!(settings.visualizer_type, to.visualizer_type);
+        assert_eq!(settings.column_count, to.column_count);
+        assert_eq!(settings.sampling_rate, to.sampling_rate);
+        assert_eq!(settings.position, to.position);
+        assert_close(settings.lower_frequency_limit, to.lower_frequency_limit);
+        assert_close(settings.upper_frequency_limit, to.upper_frequency_limit);
+        assert_close(settings.radius, to.radius);
+        assert_close(settings.max_height, to.max_height);
+        assert_close(settings.column_glow_strength, to.column_glow_strength);
+        assert_eq!(settings.normal_primary_color.as_rgba_f32(), to.normal_primary_color.as_rgba_f32());
+    }
+
+    #[test]
+    fn interpolation_switches_discrete_settings_halfway() {
+        let from = AudioVisualizerSettings::default();
+        let to = transition_target();
+
+        assert_eq!(interpolate_audio_visualizer_settings(&from, &to, 0.49).column_count, from.column_count);
+        assert_eq!(interpolate_audio_visualizer_settings(&from, &to, 0.5).column_count, to.column_count);
+        assert_close(interpolate_audio_visualizer_settings(&from, &to, 0.5).radius, (from.radius + to.radius) / 2.0);
+    }
+
+    #[test]
+    fn interpolation_clamps_out_of_range_progress() {
+        let from = AudioVisualizerSettings::default();
+        let to = transition_target();
+
+        assert!(interpolate_audio_visualizer_settings(&from, &to, -1.0) == interpolate_audio_visualizer_settings(&from, &to, 0.0));
+        assert!(interpolate_audio_visualizer_settings(&from, &to, 2.0) == interpolate_audio_visualizer_settings(&from, &to, 1.0));
+    }
+
+    #[test]
+    fn color_gradients_only_interpolate_with_matching_stop_counts() {
+        let from = AudioVisualizerSettings::default();
+        assert!(interpolate_color_gradient(&from.frequency_gradient, &from.height_gradient, 0.5).is_none());
+
+        let color_gradient = interpolate_color_gradient(&from.height_gradient, &from.height_gradient, 0.5).unwrap();
+        assert!(color_gradient == from.height_gradient);
+    }
+
+    #[test]
+    fn color_gradient_sampling_clamps_to_the_outer_stops() {
+        let color_gradient = ColorGradient {
+            stops: vec![
+                ColorGradientStop { position: 0.2, color: Color::RED, hdr_multiplier: 1.0 },
+                ColorGradientStop { position: 0.8, color: Color::BLUE, hdr_multiplier: 2.0 },
+            ]
+        };
+
+        assert_eq!(sample_color_gradient(&color_gradient, -1.0).as_rgba_f32(), Color::RED.as_rgba_f32());
+        assert_eq!(sample_color_gradient(&color_gradient, 2.0).as_rgba_f32(), [0.0, 0.0, 2.0, 1.0]);
+        assert_eq!(sample_color_gradient(&ColorGradient { stops: Vec::new() }, 0.5).as_rgba_f32(), Color::WHITE.as_rgba_f32());
+    }
+}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+use std::{net::SocketAddr, sync::{mpsc::SyncSender, Arc, Mutex}};
+use crate::cli::WebSocketFormat;
+
+#[derive(Resource, Clone)]
+pub struct WebSocketServerSettings {
+    pub address: String,
+    pub format: WebSocketFormat
+}
+
+#[derive(Resource)]
+pub struct WebSocketServer {
+    pub format: WebSocketFormat,
+    pub clients: Arc<Mutex<Vec<WebSocketClient>>>
+}
+
+pub struct WebSocketClient {
+    pub address: SocketAddr,
+    pub frame_sender: SyncSender<Arc<Vec<u8>>>
+}
@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+use std::time::Duration;
+use crate::audio_analysis::AudioAnalysisSet;
+
+pub mod components;
+pub mod protocol;
+mod systems;
+
+use systems::*;
+use components::*;
+
+pub struct WebSocketPlugin;
+
+impl Plugin for WebSocketPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_systems(Startup, setup_websocket_server)
+        .add_systems(PostUpdate, broadcast_audio_analysis.run_if(resource_exists::<WebSocketServer>).after(AudioAnalysisSet));
+    }
+}
+
+pub const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+pub const WEBSOCKET_VERSION: &str = "13";
+pub const WEBSOCKET_OPCODE_TEXT: u8 = 0x1;
+pub const WEBSOCKET_OPCODE_BINARY: u8 = 0x2;
+pub const WEBSOCKET_OPCODE_CLOSE: u8 = 0x8;
+pub const WEBSOCKET_OPCODE_PING: u8 = 0x9;
+pub const WEBSOCKET_OPCODE_PONG: u8 = 0xA;
+pub const WEBSOCKET_MAX_HANDSHAKE_LENGTH: usize = 8192;
+pub const WEBSOCKET_HANDSHAKE_READ_TIMEOUT: Duration = Duration::from_secs(10);
+pub const WEBSOCKET_MAX_INCOMING_PAYLOAD_LENGTH: usize = 65536;
+pub const WEBSOCKET_CLIENT_QUEUE_LENGTH: usize = 16;
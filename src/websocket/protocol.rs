@@ -0,0 +1,310 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+use crate::websocket::*;
+
+pub fn accept_websocket_handshake(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write
+) -> io::Result<()> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut websocket_key = None;
+    let mut upgrade = false;
+    let mut websocket_version = None;
+    let mut header_length = request_line.len();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "connection closed during handshake"));
+        }
+        header_length += header.len();
+        if header_length > WEBSOCKET_MAX_HANDSHAKE_LENGTH {
+            return Err(io::Error::new(ErrorKind::InvalidData, "handshake is too long"));
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("sec-websocket-key") {
+                websocket_key = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("upgrade") {
+                upgrade = value.split(',').any(|protocol| protocol.trim().eq_ignore_ascii_case("websocket"));
+            } else if name.eq_ignore_ascii_case("sec-websocket-version") {
+                websocket_version = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let Some(websocket_key) = websocket_key.filter(|_| upgrade && request_line.starts_with("GET ")) else {
+        writer.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+        return Err(io::Error::new(ErrorKind::InvalidData, "not a WebSocket handshake"));
+    };
+    if websocket_version.as_deref() != Some(WEBSOCKET_VERSION) {
+        write!(writer, "HTTP/1.1 426 Upgrade Required\r\nSec-WebSocket-Version: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", WEBSOCKET_VERSION)?;
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("unsupported WebSocket version {:?}", websocket_version)));
+    }
+
+    write!(writer, "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n", websocket_accept_key(&websocket_key))?;
+    writer.flush()
+}
+
+pub fn websocket_accept_key(websocket_key: &str) -> String {
+    STANDARD.encode(sha1(format!("{}{}", websocket_key, WEBSOCKET_GUID).as_bytes()))
+}
+
+pub fn encode_websocket_frame(
+    opcode: u8,
+    payload: &[u8]
+) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+
+    if payload.len() < 126 {
+        frame.push(payload.len() as u8);
+    } else if payload.len() <= u16::MAX as usize {
+        frame.push(126);
+        frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    }
+
+    frame.extend_from_slice(payload);
+    frame
+}
+
+pub fn read_websocket_frame(
+    reader: &mut impl Read
+) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+    let payload_length = match header[1] & 0x7F {
+        126 => {
+            let mut length = [0; 2];
+            reader.read_exact(&mut length)?;
+            u16::from_be_bytes(length) as u64
+        },
+        127 => {
+            let mut length = [0; 8];
+            reader.read_exact(&mut length)?;
+            u64::from_be_bytes(length)
+        },
+        length => length as u64
+    };
+
+    if payload_length > WEBSOCKET_MAX_INCOMING_PAYLOAD_LENGTH as u64 {
+        return Err(io::Error::new(ErrorKind::InvalidData, "incoming frame is too large"));
+    }
+
+    let mut mask = [0; 4];
+    if masked {
+        reader.read_exact(&mut mask)?;
+    }
+
+    let mut payload = vec![0; payload_length as usize];
+    reader.read_exact(&mut payload)?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok((opcode, payload))
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            words[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6)
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (value, new_value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(new_value);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (i, value) in state.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::{BufReader, Cursor}, net::{TcpListener, TcpStream}, thread};
+
+    fn masked_frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+        let mut frame = vec![0x80 | opcode];
+        match payload.len() {
+            length if length < 126 => frame.push(0x80 | length as u8),
+            length if length <= u16::MAX as usize => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(length as u16).to_be_bytes());
+            },
+            length => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(length as u64).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+        frame
+    }
+
+    #[test]
+    fn accept_key_matches_rfc_6455() {
+        assert_eq!(websocket_accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn sha1_matches_known_digests() {
+        let hex = |digest: [u8; 20]| digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(hex(sha1(&[b'a'; 1000])), "291e9a6c66994949b57ba5e650361e98fc36b1ba");
+    }
+
+    #[test]
+    fn unmasked_frames_round_trip() {
+        for (payload_length, header_length) in [(0, 2), (125, 2), (126, 4), (u16::MAX as usize, 4), (u16::MAX as usize + 1, 10)] {
+            let payload: Vec<u8> = (0..payload_length).map(|i| i as u8).collect();
+            let frame = encode_websocket_frame(WEBSOCKET_OPCODE_BINARY, &payload);
+            assert_eq!(frame.len(), header_length + payload_length);
+            assert_eq!(frame[0], 0x80 | WEBSOCKET_OPCODE_BINARY);
+
+            let (opcode, decoded_payload) = read_websocket_frame(&mut Cursor::new(frame)).unwrap();
+            assert_eq!(opcode, WEBSOCKET_OPCODE_BINARY);
+            assert_eq!(decoded_payload, payload);
+        }
+    }
+
+    #[test]
+    fn masked_frames_are_unmasked() {
+        for payload_length in [5, 300, u16::MAX as usize + 1] {
+            let payload: Vec<u8> = (0..payload_length).map(|i| (i * 7) as u8).collect();
+            let frame = masked_frame(WEBSOCKET_OPCODE_TEXT, &payload, [0x37, 0xfa, 0x21, 0x3d]);
+
+            let (opcode, decoded_payload) = read_websocket_frame(&mut Cursor::new(frame)).unwrap();
+            assert_eq!(opcode, WEBSOCKET_OPCODE_TEXT);
+            assert_eq!(decoded_payload, payload);
+        }
+    }
+
+    #[test]
+    fn oversized_and_truncated_frames_are_rejected() {
+        let frame = masked_frame(WEBSOCKET_OPCODE_BINARY, &vec![0; WEBSOCKET_MAX_INCOMING_PAYLOAD_LENGTH + 1], [1, 2, 3, 4]);
+        assert_eq!(read_websocket_frame(&mut Cursor::new(frame)).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let mut frame = encode_websocket_frame(WEBSOCKET_OPCODE_TEXT, b"hello");
+        frame.pop();
+        assert_eq!(read_websocket_frame(&mut Cursor::new(frame)).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn handshake_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(b"GET /analysis HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n").unwrap();
+
+            let mut response = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                response.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let frame = read_websocket_frame(&mut reader).unwrap();
+            stream.write_all(&masked_frame(WEBSOCKET_OPCODE_TEXT, b"pong", [9, 8, 7, 6])).unwrap();
+            (response, frame)
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        accept_websocket_handshake(&mut reader, &mut writer).unwrap();
+        writer.write_all(&encode_websocket_frame(WEBSOCKET_OPCODE_TEXT, b"ping")).unwrap();
+        let client_frame = read_websocket_frame(&mut reader).unwrap();
+
+        let (response, server_frame) = client.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+        assert_eq!(server_frame, (WEBSOCKET_OPCODE_TEXT, b"ping".to_vec()));
+        assert_eq!(client_frame, (WEBSOCKET_OPCODE_TEXT, b"pong".to_vec()));
+    }
+
+    #[test]
+    fn handshake_without_upgrade_is_rejected() {
+        let mut response = Vec::new();
+        let result = accept_websocket_handshake(&mut Cursor::new(b"GET / HTTP/1.1\r\nHost: localhost\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n".to_vec()), &mut response);
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(response.starts_with(b"HTTP/1.1 400 Bad Request"));
+    }
+
+    #[test]
+    fn handshake_with_unsupported_version_is_rejected() {
+        for version_header in ["", "Sec-WebSocket-Version: 8\r\n"] {
+            let mut response = Vec::new();
+            let request = format!("GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: WebSocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n{}\r\n", version_header);
+            let result = accept_websocket_handshake(&mut Cursor::new(request.into_bytes()), &mut response);
+
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+            let response = String::from_utf8(response).unwrap();
+            assert!(response.starts_with("HTTP/1.1 426 Upgrade Required\r\n"));
+            assert!(response.contains("Sec-WebSocket-Version: 13\r\n"));
+        }
+    }
+
+    #[test]
+    fn handshake_without_key_is_rejected() {
+        let mut response = Vec::new();
+        let result = accept_websocket_handshake(&mut Cursor::new(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec()), &mut response);
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(response.starts_with(b"HTTP/1.1 400 Bad Request"));
+    }
+}
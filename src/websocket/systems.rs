@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+use serde_json::json;
+use std::{io::{BufReader, Write}, net::{Shutdown, TcpListener, TcpStream}, sync::{mpsc::{self, TrySendError}, Arc, Mutex}, thread};
+use crate::{audio_analysis::{components::AudioAnalysisEvent, AUDIO_BANDS}, cli::WebSocketFormat, websocket::*};
+use super::{components::*, protocol::*};
+
+pub fn setup_websocket_server(
+    mut commands: Commands,
+    websocket_server_settings: Option<Res<WebSocketServerSettings>>
+) {
+    let Some(websocket_server_settings) = websocket_server_settings else {
+        return;
+    };
+    let address = &websocket_server_settings.address;
+
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(error) => {
+            error!("Failed to start WebSocket server on {}: {}", address, error);
+            return;
+        }
+    };
+
+    info!("WebSocket server listening on {}", listener.local_addr().map_or(address.clone(), |local_address| local_address.to_string()));
+
+    let clients = Arc::new(Mutex::new(Vec::new()));
+    let listener_clients = clients.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let clients = listener_clients.clone();
+            thread::spawn(move || handle_websocket_connection(stream, clients));
+        }
+    });
+
+    commands.insert_resource(WebSocketServer {
+        format: websocket_server_settings.format,
+        clients
+    });
+}
+
+pub fn broadcast_audio_analysis(
+    mut audio_analysis_event_reader: EventReader<AudioAnalysisEvent>,
+    websocket_server: Res<WebSocketServer>
+) {
+    let mut clients = websocket_server.clients.lock().unwrap();
+
+    for audio_analysis_event in audio_analysis_event_reader.read() {
+        if clients.is_empty() {
+            continue;
+        }
+
+        let frame = Arc::new(match websocket_server.format {
+            WebSocketFormat::Json => encode_websocket_frame(WEBSOCKET_OPCODE_TEXT, encode_json_analysis_message(audio_analysis_event).as_bytes()),
+            WebSocketFormat::Binary => encode_websocket_frame(WEBSOCKET_OPCODE_BINARY, &encode_binary_analysis_message(audio_analysis_event))
+        });
+
+        // Slow clients skip frames instead of holding up the others
+        clients.retain(|client| match client.frame_sender.try_send(frame.clone()) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_)) => {
+                info!("WebSocket client {} disconnected", client.address);
+                false
+            }
+        });
+    }
+}
+
+fn handle_websocket_connection(
+    stream: TcpStream,
+    clients: Arc<Mutex<Vec<WebSocketClient>>>
+) {
+    let (Ok(address), Ok(mut writer)) = (stream.peer_addr(), stream.try_clone()) else {
+        return;
+    };
+    let _ = stream.set_nodelay(true);
+
+    // Clients that never finish the handshake would otherwise keep their thread forever
+    let _ = stream.set_read_timeout(Some(WEBSOCKET_HANDSHAKE_READ_TIMEOUT));
+    let mut reader = BufReader::new(stream);
+    if let Err(error) = accept_websocket_handshake(&mut reader, &mut writer) {
+        warn!("Rejected WebSocket connection from {}: {}", address, error);
+        return;
+    }
+    // Connected clients usually only listen, so they may stay silent for as long as they like
+    let _ = reader.get_ref().set_read_timeout(None);
+
+    let (frame_sender, frame_receiver) = mpsc::sync_channel::<Arc<Vec<u8>>>(WEBSOCKET_CLIENT_QUEUE_LENGTH);
+    clients.lock().unwrap().push(WebSocketClient { address, frame_sender: frame_sender.clone() });
+    info!("WebSocket client {} connected", address);
+
+    thread::spawn(move || {
+        for frame in frame_receiver {
+            if writer.write_all(&frame).is_err() || frame[0] & 0x0F == WEBSOCKET_OPCODE_CLOSE {
+                let _ = writer.shutdown(Shutdown::Both);
+                break;
+            }
+        }
+    });
+
+    loop {
+        match read_websocket_frame(&mut reader) {
+            Ok((WEBSOCKET_OPCODE_PING, payload)) => {
+                let _ = frame_sender.try_send(Arc::new(encode_websocket_frame(WEBSOCKET_OPCODE_PONG, &payload)));
+            },
+            Ok((WEBSOCKET_OPCODE_CLOSE, payload)) => {
+                let _ = frame_sender.send(Arc::new(encode_websocket_frame(WEBSOCKET_OPCODE_CLOSE, &payload[..payload.len().min(2)])));
+                break;
+            },
+            Ok(_) => {},
+            Err(_) => {
+                let _ = reader.get_ref().shutdown(Shutdown::Both);
+                break;
+            }
+        }
+    }
+}
+
+fn encode_json_analysis_message(
+    audio_analysis_event: &AudioAnalysisEvent
+) -> String {
+    let bands: serde_json::Map<String, serde_json::Value> = AUDIO_BANDS.iter().zip(&audio_analysis_event.band_energies)
+        .map(|((name, _, _), energy)| (name.to_string(), json!(energy)))
+        .collect();
+    let beats: serde_json::Map<String, serde_json::Value> = AUDIO_BANDS.iter().zip(&audio_analysis_event.beats)
+        .map(|((name, _, _), beat)| (name.to_string(), json!(beat)))
+        .collect();
+
+    json!({
+        "frame": audio_analysis_event.frame,
+        "time": audio_analysis_event.time,
+        "average": audio_analysis_event.columns.average_value,
+        "values": audio_analysis_event.columns.values,
+        "highlighted": audio_analysis_event.columns.highlighted,
+        "bands": bands,
+        "beats": beats
+    }).to_string()
+}
+
+fn encode_binary_analysis_message(
+    audio_analysis_event: &AudioAnalysisEvent
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + (audio_analysis_event.columns.values.len() + audio_analysis_event.band_energies.len()) * 4);
+
+    message.extend_from_slice(&audio_analysis_event.frame.to_le_bytes());
+    message.extend_from_slice(&audio_analysis_event.time.to_le_bytes());
+    message.extend_from_slice(&audio_analysis_event.columns.average_value.to_le_bytes());
+    message.extend_from_slice(&(audio_analysis_event.columns.values.len() as u32).to_le_bytes());
+    for value in &audio_analysis_event.columns.values {
+        message.extend_from_slice(&value.to_le_bytes());
+    }
+    message.extend_from_slice(&(audio_analysis_event.band_energies.len() as u32).to_le_bytes());
+    for band_energy in &audio_analysis_event.band_energies {
+        message.extend_from_slice(&band_energy.to_le_bytes());
+    }
+    let beat_flags = audio_analysis_event.beats.iter().enumerate().fold(0u32, |beat_flags, (i, beat)| beat_flags | ((*beat as u32) << i));
+    message.extend_from_slice(&beat_flags.to_le_bytes());
+
+    message
+}
+